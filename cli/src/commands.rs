@@ -1,15 +1,43 @@
+pub mod batch;
+pub mod budget;
+pub mod buffer;
+pub mod compute_budget;
 pub mod config;
+pub mod diagnose;
+pub mod diff;
+pub mod fee_payer;
 pub mod generate;
+pub mod gnark;
+pub mod history;
 pub mod init;
+pub mod inspect;
+pub mod instruction_spec;
+pub mod invocations;
+pub mod localnet;
+pub mod manifest;
+pub mod priority_fee;
+pub mod report;
 pub mod run;
+pub mod runs;
 pub mod simulate;
+pub mod submit;
+pub mod svm;
+pub mod verify;
 pub mod version;
+pub mod versioned;
 pub mod view;
 
+pub use budget::Budgets;
 pub use config::{ConfigAction, run_config};
+pub use diff::run_diff;
 pub use generate::run_generate;
+pub use history::run_history;
 pub use init::{ensure_initialized, run_init};
-pub use run::run_pipeline;
-pub use simulate::run_simulate;
+pub use inspect::run_inspect;
+pub use report::{ReportAction, run_report};
+pub use run::{StepSelection, run_pipeline};
+pub use simulate::{SimulateOptions, run_simulate};
+pub use submit::{SubmitOptions, run_submit};
+pub use verify::run_verify;
 pub use version::run_version;
 pub use view::run_view;
@@ -0,0 +1,321 @@
+//! Simulation of many proof and witness pairs in one command
+//!
+//! `zklense simulate --batch <DIR|PATTERN>` finds `<name>.proof` files in a
+//! directory, or matching a path with `*` and `?` wildcards, pairs each with
+//! the `<name>.pw` next to it and simulates every pair. Verification cost
+//! should not depend on the inputs, so inputs whose compute units stray from
+//! the median are flagged as outliers, next to the ones that fail.
+
+use comfy_table::{Cell, Color};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::report::{BatchEntry, BatchReport, Status};
+use super::runs;
+use super::simulate::format_number;
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Deviation from the median compute units that makes an input an outlier
+pub const OUTLIER_THRESHOLD_PERCENT: f64 = 1.0;
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters
+/// and `?` any single one
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Last `*` seen and the position in `name` it currently stands for
+    let mut backtrack = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            backtrack = Some((star, matched + 1));
+            p = star + 1;
+            n = matched + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn has_wildcard(part: &str) -> bool {
+    part.contains(['*', '?'])
+}
+
+/// Paths matching `pattern`, wildcards allowed in any component
+fn expand(pattern: &Path) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if !has_wildcard(&part) {
+            for path in &mut matches {
+                path.push(component);
+            }
+            continue;
+        }
+        matches = matches
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter(|entry| wildcard_match(&part, &entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect();
+    }
+    matches.retain(|path| path.exists());
+    matches
+}
+
+fn is_proof(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "proof")
+}
+
+/// Proof files in `dir`
+fn proofs_in(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| is_proof(path))
+        .collect()
+}
+
+/// Proof and witness pairs in the directory `source`, or matching the
+/// pattern `source`, relative to `base_path`
+pub fn discover(base_path: &Path, source: &str) -> error::Result<Vec<(PathBuf, PathBuf)>> {
+    let path = base_path.join(source);
+    let mut proofs = if path.is_dir() {
+        proofs_in(&path)
+    } else if has_wildcard(source) {
+        expand(&path)
+            .into_iter()
+            .flat_map(|path| {
+                if path.is_dir() {
+                    proofs_in(&path)
+                } else if is_proof(&path) {
+                    vec![path]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect()
+    } else {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "--batch expects a directory or a pattern such as 'inputs/*.proof', got '{}'",
+            source
+        )));
+    };
+    proofs.sort();
+    proofs.dedup();
+    if proofs.is_empty() {
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "No .proof files found for '{}'",
+            source
+        )));
+    }
+
+    let pairs: Vec<(PathBuf, PathBuf)> = proofs
+        .into_iter()
+        .map(|proof| {
+            let witness = proof.with_extension("pw");
+            (proof, witness)
+        })
+        .collect();
+    let unpaired: Vec<String> = pairs
+        .iter()
+        .filter(|(_, witness)| !witness.is_file())
+        .map(|(proof, _)| proof.display().to_string())
+        .collect();
+    if !unpaired.is_empty() {
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "No .pw witness next to {}",
+            unpaired.join(", ")
+        )));
+    }
+    Ok(pairs)
+}
+
+/// Flag outliers against the median of the successful inputs and count them
+pub fn summarize(source: &str, mut entries: Vec<BatchEntry>) -> BatchReport {
+    let compute_units = runs::compute_unit_statistics(
+        entries
+            .iter()
+            .filter(|entry| entry.status == Status::Success)
+            .filter_map(|entry| entry.units_consumed)
+            .collect(),
+    );
+    if let Some(cu) = &compute_units
+        && cu.median > 0
+    {
+        for entry in &mut entries {
+            if entry.status != Status::Success {
+                continue;
+            }
+            entry.deviation_percent = entry
+                .units_consumed
+                .map(|units| (units as f64 - cu.median as f64) / cu.median as f64 * 100.0);
+            entry.outlier = entry
+                .deviation_percent
+                .is_some_and(|deviation| deviation.abs() > OUTLIER_THRESHOLD_PERCENT);
+        }
+    }
+    let failed = entries
+        .iter()
+        .filter(|entry| entry.status == Status::Failed)
+        .count();
+    BatchReport {
+        source: source.to_string(),
+        inputs: entries.len(),
+        succeeded: entries.len() - failed,
+        failed,
+        constant_cost: compute_units.as_ref().is_some_and(|cu| cu.min == cu.max),
+        compute_units,
+        outlier_threshold_percent: OUTLIER_THRESHOLD_PERCENT,
+        outliers: entries.iter().filter(|entry| entry.outlier).count(),
+        entries,
+    }
+}
+
+/// Print every input, outliers and failures highlighted, and the spread of
+/// compute units
+pub fn print_batch(batch: &BatchReport, base_path: &Path) {
+    ui::section(emoji::CHART, "Batch");
+    let mut table = ui::create_table(&[
+        "Input",
+        "Compute Units",
+        "Deviation",
+        "Fee (lamports)",
+        "Size",
+        "Status",
+    ]);
+    for entry in &batch.entries {
+        let input = Path::new(&entry.proof_path);
+        let input = input.strip_prefix(base_path).unwrap_or(input);
+        let deviation = match entry.deviation_percent {
+            Some(deviation) if entry.outlier => {
+                Cell::new(format!("{:+.2}%", deviation)).fg(Color::Yellow)
+            }
+            Some(deviation) => Cell::new(format!("{:+.2}%", deviation)),
+            None => Cell::new("-"),
+        };
+        let status = match (&entry.error, entry.outlier) {
+            (Some(error), _) => Cell::new(error).fg(Color::Red),
+            (None, true) => Cell::new("outlier").fg(Color::Yellow),
+            (None, false) => Cell::new("ok").fg(Color::Green),
+        };
+        table.add_row(vec![
+            Cell::new(input.display()),
+            Cell::new(
+                entry
+                    .units_consumed
+                    .map(format_number)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            deviation,
+            Cell::new(format_number(
+                entry.fee_at_recommended_limit.unwrap_or(entry.fee),
+            )),
+            Cell::new(entry.transaction_size),
+            status,
+        ]);
+    }
+    ui_println!("{table}");
+
+    let mut items = vec![(
+        "Inputs",
+        format!(
+            "{} ({} succeeded, {} failed)",
+            batch.inputs, batch.succeeded, batch.failed
+        ),
+    )];
+    if let Some(cu) = &batch.compute_units {
+        items.push((
+            "CU Range",
+            format!(
+                "{} – {} (median {})",
+                format_number(cu.min),
+                format_number(cu.max),
+                format_number(cu.median)
+            ),
+        ));
+        items.push((
+            "Constant Cost",
+            if batch.constant_cost { "yes" } else { "no" }.to_string(),
+        ));
+    }
+    items.push((
+        "Outliers",
+        format!(
+            "{} beyond ±{}% of the median",
+            batch.outliers, batch.outlier_threshold_percent
+        ),
+    ));
+    let items: Vec<(&str, &str)> = items.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    ui::print_tree(&items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, units_consumed: Option<u64>, status: Status) -> BatchEntry {
+        BatchEntry {
+            proof_path: format!("{}.proof", name),
+            witness_path: format!("{}.pw", name),
+            status,
+            units_consumed,
+            deviation_percent: None,
+            outlier: false,
+            transaction_size: 600,
+            fee: 5000,
+            fee_at_recommended_limit: None,
+            error: None,
+            retries: 0,
+        }
+    }
+
+    #[test]
+    fn test_pairs_are_discovered_and_outliers_flagged() {
+        let dir = std::env::temp_dir().join(format!("zklense-batch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for set in ["a", "b"] {
+            fs::create_dir_all(dir.join(set)).unwrap();
+            fs::write(dir.join(set).join("circuit.proof"), b"").unwrap();
+            fs::write(dir.join(set).join("circuit.pw"), b"").unwrap();
+        }
+        fs::write(dir.join("a").join("notes.txt"), b"").unwrap();
+
+        let pairs = discover(&dir, "*/circuit.proof").unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].1, dir.join("a").join("circuit.pw"));
+        assert_eq!(discover(&dir, "*").unwrap(), pairs);
+        assert_eq!(discover(&dir, "a").unwrap(), pairs[..1]);
+
+        fs::remove_file(dir.join("b").join("circuit.pw")).unwrap();
+        assert!(discover(&dir, "b").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let batch = summarize(
+            "inputs",
+            vec![
+                entry("a", Some(150_000), Status::Success),
+                entry("b", Some(150_000), Status::Success),
+                entry("c", Some(160_000), Status::Success),
+                entry("d", Some(1_000), Status::Failed),
+            ],
+        );
+        assert_eq!((batch.succeeded, batch.failed, batch.outliers), (3, 1, 1));
+        assert!(!batch.constant_cost);
+        assert!(batch.entries[2].outlier);
+        assert_eq!(batch.entries[3].deviation_percent, None);
+        assert!(wildcard_match("set-?.pro*", "set-1.proof"));
+        assert!(!wildcard_match("*.proof", "circuit.pw"));
+    }
+}
@@ -0,0 +1,200 @@
+//! Compute-unit, size and fee budgets enforced by `zklense simulate`
+//!
+//! Budgets come from the `[budgets]` table in `.zklense/config.toml` and can
+//! be overridden per run with command-line flags:
+//!
+//! ```toml
+//! [budgets]
+//! max_cu = 250000
+//! max_budget_percent = 50.0
+//! max_message_size = 1000
+//! max_total_fee = 10000
+//! ```
+
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::report::SimulationReport;
+use super::simulate::format_number;
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Upper limits for a simulated verification. Unset limits are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budgets {
+    /// Compute units consumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cu: Option<u64>,
+    /// Share of the requested compute unit limit used, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_budget_percent: Option<f64>,
+    /// Serialized message size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_size: Option<usize>,
+    /// Total fee in lamports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_fee: Option<u64>,
+}
+
+impl Budgets {
+    /// Whether no limit is set
+    pub fn is_empty(&self) -> bool {
+        *self == Budgets::default()
+    }
+
+    /// Combine with `overrides`, whose limits take precedence
+    pub fn merged_with(&self, overrides: &Budgets) -> Budgets {
+        Budgets {
+            max_cu: overrides.max_cu.or(self.max_cu),
+            max_budget_percent: overrides.max_budget_percent.or(self.max_budget_percent),
+            max_message_size: overrides.max_message_size.or(self.max_message_size),
+            max_total_fee: overrides.max_total_fee.or(self.max_total_fee),
+        }
+    }
+}
+
+/// Outcome of checking one budget
+#[derive(Debug, Clone)]
+pub struct BudgetCheck {
+    /// Config key of the budget, e.g. `max_cu`
+    pub key: &'static str,
+    pub name: &'static str,
+    pub limit: f64,
+    pub actual: f64,
+    /// Digits after the decimal point when displayed
+    precision: usize,
+    unit: &'static str,
+}
+
+impl BudgetCheck {
+    fn new(key: &'static str, name: &'static str, limit: f64, actual: f64) -> Self {
+        let (precision, unit) = match key {
+            "max_cu" => (0, "CU"),
+            "max_budget_percent" => (2, "%"),
+            "max_message_size" => (0, "bytes"),
+            _ => (0, "lamports"),
+        };
+        BudgetCheck {
+            key,
+            name,
+            limit,
+            actual,
+            precision,
+            unit,
+        }
+    }
+
+    pub fn ok(&self) -> bool {
+        self.actual <= self.limit
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self.unit {
+            "%" => format!("{:.*}%", self.precision, value),
+            unit => format!("{} {}", format_number(value as u64), unit),
+        }
+    }
+
+    fn json_value(&self, value: f64) -> Value {
+        if self.precision == 0 {
+            json!(value as u64)
+        } else {
+            json!(value)
+        }
+    }
+}
+
+/// Check every configured budget against a report
+pub fn check_budgets(budgets: &Budgets, report: &SimulationReport) -> Vec<BudgetCheck> {
+    let mut checks = Vec::new();
+    if let Some(max) = budgets.max_cu {
+        checks.push(BudgetCheck::new(
+            "max_cu",
+            "Compute units",
+            max as f64,
+            report.compute_units.total_compute_units_consumed as f64,
+        ));
+    }
+    if let Some(max) = budgets.max_budget_percent {
+        checks.push(BudgetCheck::new(
+            "max_budget_percent",
+            "Budget used",
+            max,
+            report.compute_units.percentage_of_compute_budget_used,
+        ));
+    }
+    if let Some(max) = budgets.max_message_size {
+        checks.push(BudgetCheck::new(
+            "max_message_size",
+            "Message size",
+            max as f64,
+            report.transaction_size.message_size as f64,
+        ));
+    }
+    if let Some(max) = budgets.max_total_fee {
+        checks.push(BudgetCheck::new(
+            "max_total_fee",
+            "Total fee",
+            max as f64,
+            report.cost.total_fee as f64,
+        ));
+    }
+    checks
+}
+
+/// Print budget checks as a table
+pub fn print_budget_checks(checks: &[BudgetCheck]) {
+    ui::section(emoji::PIN, "Budgets");
+    let mut table = ui::create_table(&["Budget", "Limit", "Actual", "Status"]);
+    for check in checks {
+        table.add_row(vec![
+            Cell::new(check.name),
+            Cell::new(check.format(check.limit)),
+            Cell::new(check.format(check.actual)),
+            if check.ok() {
+                Cell::new(format!("{} within", emoji::SUCCESS)).fg(Color::Green)
+            } else {
+                Cell::new(format!("{} exceeded", emoji::ERROR)).fg(Color::Red)
+            },
+        ]);
+    }
+    ui_println!("{table}");
+    ui::blank();
+}
+
+/// Budget checks for JSON output
+pub fn checks_json(checks: &[BudgetCheck]) -> Value {
+    json!(
+        checks
+            .iter()
+            .map(|c| json!({
+                "budget": c.key,
+                "limit": c.json_value(c.limit),
+                "actual": c.json_value(c.actual),
+                "ok": c.ok(),
+            }))
+            .collect::<Vec<_>>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_override_config_budgets() {
+        let config: Budgets = toml::from_str("max_cu = 200000\nmax_message_size = 1000").unwrap();
+        let flags = Budgets {
+            max_cu: Some(150_000),
+            max_total_fee: Some(5000),
+            ..Default::default()
+        };
+        let merged = config.merged_with(&flags);
+        assert_eq!(merged.max_cu, Some(150_000));
+        assert_eq!(merged.max_message_size, Some(1000));
+        assert_eq!(merged.max_total_fee, Some(5000));
+        assert_eq!(merged.max_budget_percent, None);
+        assert!(Budgets::default().is_empty());
+    }
+}
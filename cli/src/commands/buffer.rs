@@ -0,0 +1,276 @@
+//! Buffered delivery of proofs too large for a single transaction
+//!
+//! `zklense simulate --buffered` creates a buffer account owned by the
+//! program in the `[buffer]` section of `.zklense/instruction.toml`, writes
+//! `proof || public_witness` to it over as many transactions as needed and
+//! then runs the verify instruction, which reads the proof from the buffer.
+//! Compute units and fees are added up across all transactions.
+
+use comfy_table::{Cell, Color};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use super::instruction_spec::{BufferSpec, parse_discriminator};
+use super::report::{BufferedDelivery, DeliveryTransaction};
+use super::simulate::{MAX_TRANSACTION_SIZE, estimated_fee, format_number};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Create and write transactions filling a buffer with a proof
+pub struct DeliveryPlan {
+    pub buffer: Pubkey,
+    /// Program owning the buffer
+    pub program: Pubkey,
+    pub buffer_size: usize,
+    pub rent_lamports: u64,
+    pub chunk_size: usize,
+    /// The create transaction followed by the write transactions
+    pub transactions: Vec<Transaction>,
+}
+
+/// Program owning the buffer: `[buffer] program`, or the verifier
+pub fn program_id(spec: &BufferSpec, verifier: &Pubkey) -> error::Result<Pubkey> {
+    match spec.program.as_deref() {
+        None | Some("program") => Ok(*verifier),
+        Some(address) => Pubkey::from_str(address).map_err(|_| {
+            ZkLenseError::Config(format!("Invalid buffer program address '{}'", address))
+        }),
+    }
+}
+
+/// Size of a buffer holding `payload_len` bytes after the header
+pub fn buffer_size(spec: &BufferSpec, payload_len: usize) -> usize {
+    spec.header_size + payload_len
+}
+
+/// Write `chunk` at `offset` in `buffer`
+fn write_instruction(
+    program: &Pubkey,
+    buffer: &Pubkey,
+    authority: &Pubkey,
+    discriminator: &[u8],
+    offset: u32,
+    chunk: &[u8],
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    data.extend_from_slice(&offset.to_le_bytes());
+    data.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+    data.extend_from_slice(chunk);
+    Instruction {
+        program_id: *program,
+        accounts: vec![
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+fn transaction_size(transaction: &Transaction) -> usize {
+    bincode::serialized_size(transaction).unwrap_or(0) as usize
+}
+
+impl DeliveryPlan {
+    /// Plan the delivery of `payload` to `buffer`, paid for by `payer`
+    pub fn new(
+        spec: &BufferSpec,
+        verifier: &Pubkey,
+        payer: &Pubkey,
+        buffer: &Pubkey,
+        payload: &[u8],
+        rent_lamports: u64,
+    ) -> error::Result<DeliveryPlan> {
+        let program = program_id(spec, verifier)?;
+        let discriminator = parse_discriminator(spec.write_discriminator.as_deref())?;
+        let size = buffer_size(spec, payload.len());
+
+        // Data past 127 bytes takes a second byte to encode its length
+        let empty_write = Transaction::new_with_payer(
+            &[write_instruction(
+                &program,
+                buffer,
+                payer,
+                &discriminator,
+                0,
+                &[],
+            )],
+            Some(payer),
+        );
+        let max_chunk = MAX_TRANSACTION_SIZE.saturating_sub(transaction_size(&empty_write) + 1);
+        let chunk_size = match spec.chunk_size {
+            Some(chunk_size) if chunk_size == 0 || chunk_size > max_chunk => {
+                return Err(ZkLenseError::Config(format!(
+                    "Buffer chunk_size must be between 1 and {} bytes, got {}",
+                    max_chunk, chunk_size
+                )));
+            }
+            Some(chunk_size) => chunk_size,
+            None => max_chunk,
+        };
+
+        let mut transactions = vec![Transaction::new_with_payer(
+            &[solana_system_interface::instruction::create_account(
+                payer,
+                buffer,
+                rent_lamports,
+                size as u64,
+                &program,
+            )],
+            Some(payer),
+        )];
+        for (index, chunk) in payload.chunks(chunk_size).enumerate() {
+            let offset = (spec.header_size + index * chunk_size) as u32;
+            transactions.push(Transaction::new_with_payer(
+                &[write_instruction(
+                    &program,
+                    buffer,
+                    payer,
+                    &discriminator,
+                    offset,
+                    chunk,
+                )],
+                Some(payer),
+            ));
+        }
+
+        Ok(DeliveryPlan {
+            buffer: *buffer,
+            program,
+            buffer_size: size,
+            rent_lamports,
+            chunk_size,
+            transactions,
+        })
+    }
+
+    /// Totals over the delivery `results`, which stop at the first failure,
+    /// and the verify transaction
+    pub fn summarize(
+        &self,
+        results: &[RpcSimulateTransactionResult],
+        verify: &Transaction,
+        verify_result: &RpcSimulateTransactionResult,
+    ) -> BufferedDelivery {
+        let steps = self
+            .transactions
+            .iter()
+            .zip(results)
+            .enumerate()
+            .map(|(index, pair)| (if index == 0 { "create" } else { "write" }, pair))
+            .chain(std::iter::once(("verify", (verify, verify_result))));
+        let transactions: Vec<DeliveryTransaction> = steps
+            .map(|(step, (transaction, result))| DeliveryTransaction {
+                step: step.to_string(),
+                transaction_size: transaction_size(transaction),
+                units_consumed: result.units_consumed.unwrap_or(0),
+                fee: estimated_fee(transaction),
+                error: result.err.as_ref().map(|e| format!("{:?}", e)),
+            })
+            .collect();
+
+        BufferedDelivery {
+            buffer: self.buffer.to_string(),
+            program: self.program.to_string(),
+            buffer_size: self.buffer_size,
+            rent_lamports: self.rent_lamports,
+            chunk_size: self.chunk_size,
+            total_compute_units: transactions.iter().map(|t| t.units_consumed).sum(),
+            total_fee: transactions.iter().map(|t| t.fee).sum(),
+            transactions,
+        }
+    }
+}
+
+/// Print every transaction of a buffered delivery and the totals
+pub fn print_delivery(delivery: &BufferedDelivery) {
+    ui::section(emoji::PACKAGE, "Buffered Delivery");
+    let mut table = ui::create_table(&["#", "Step", "Size", "Compute Units", "Fee", "Status"]);
+    for (index, transaction) in delivery.transactions.iter().enumerate() {
+        let status = match &transaction.error {
+            None => Cell::new("ok").fg(Color::Green),
+            Some(error) => Cell::new(error).fg(Color::Red),
+        };
+        table.add_row(vec![
+            Cell::new(index + 1),
+            Cell::new(&transaction.step),
+            Cell::new(format!("{} bytes", transaction.transaction_size)),
+            Cell::new(format_number(transaction.units_consumed)),
+            Cell::new(format!("{} lamports", format_number(transaction.fee))),
+            status,
+        ]);
+    }
+    ui_println!("{table}");
+    ui::print_tree(&[
+        ("Buffer", &delivery.buffer),
+        (
+            "Buffer Size",
+            &format!(
+                "{} bytes in chunks of {}",
+                format_number(delivery.buffer_size as u64),
+                format_number(delivery.chunk_size as u64)
+            ),
+        ),
+        ("Transactions", &delivery.transactions.len().to_string()),
+        ("Total CU", &format_number(delivery.total_compute_units)),
+        (
+            "Total Fee",
+            &format!(
+                "{} lamports ({:.9} SOL)",
+                format_number(delivery.total_fee),
+                delivery.total_fee as f64 / LAMPORTS_PER_SOL as f64
+            ),
+        ),
+        (
+            "Rent Deposit",
+            &format!(
+                "{} lamports, refundable when the buffer is closed",
+                format_number(delivery.rent_lamports)
+            ),
+        ),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_splits_payload_into_fitting_chunks() {
+        let spec = BufferSpec {
+            write_discriminator: Some("0x01".to_string()),
+            header_size: 40,
+            ..Default::default()
+        };
+        let payer = Pubkey::new_unique();
+        let payload: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        let plan = DeliveryPlan::new(
+            &spec,
+            &Pubkey::new_unique(),
+            &payer,
+            &Pubkey::new_unique(),
+            &payload,
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(plan.buffer_size, 3040);
+        assert_eq!(
+            plan.transactions.len(),
+            1 + 3000_usize.div_ceil(plan.chunk_size)
+        );
+        for transaction in &plan.transactions {
+            assert!(transaction_size(transaction) <= MAX_TRANSACTION_SIZE);
+        }
+        let last = &plan.transactions.last().unwrap().message.instructions[0].data;
+        let offset = u32::from_le_bytes(last[1..5].try_into().unwrap()) as usize;
+        assert_eq!(offset - 40 + last.len() - 9, 3000);
+        assert!(transaction_size(&plan.transactions[1]) > MAX_TRANSACTION_SIZE - 4);
+    }
+}
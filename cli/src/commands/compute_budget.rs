@@ -0,0 +1,97 @@
+//! Compute budget instructions and compute-unit limit recommendations
+//!
+//! `zklense simulate --tune-cu` first simulates with the maximum limit, then
+//! re-simulates with [`recommended_cu_limit`] to confirm the tighter limit
+//! still lets verification succeed. `--heap-frame` adds a
+//! `RequestHeapFrame` instruction, and `--measure-heap` searches for the
+//! smallest heap frame verification succeeds with.
+
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::str::FromStr;
+
+/// Highest compute unit limit a transaction can request
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+/// Safety margin added to the consumed compute units by default, in percent
+pub const DEFAULT_CU_MARGIN_PERCENT: f64 = 10.0;
+/// Recommended limits are rounded up to a multiple of this
+const CU_LIMIT_GRANULARITY: u64 = 1_000;
+/// Heap every program gets without requesting a frame, in bytes
+pub const DEFAULT_HEAP_FRAME: u32 = 32 * 1024;
+/// Largest heap frame a transaction can request, in bytes
+pub const MAX_HEAP_FRAME: u32 = 256 * 1024;
+/// Heap frames must be a multiple of this
+pub const HEAP_FRAME_GRANULARITY: u32 = 1024;
+
+pub fn program_id() -> Pubkey {
+    Pubkey::from_str("ComputeBudget111111111111111111111111111111").unwrap()
+}
+
+/// `SetComputeUnitLimit` instruction
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2u8];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// `RequestHeapFrame` instruction
+pub fn request_heap_frame(bytes: u32) -> Instruction {
+    let mut data = vec![1u8];
+    data.extend_from_slice(&bytes.to_le_bytes());
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Whether the runtime accepts a heap frame of `bytes`
+pub fn is_valid_heap_frame(bytes: u32) -> bool {
+    (DEFAULT_HEAP_FRAME..=MAX_HEAP_FRAME).contains(&bytes)
+        && bytes.is_multiple_of(HEAP_FRAME_GRANULARITY)
+}
+
+/// Smallest compute unit limit covering `consumed` plus `margin_percent`,
+/// rounded up to a multiple of 1,000 and capped at [`MAX_COMPUTE_UNITS`]
+pub fn recommended_cu_limit(consumed: u64, margin_percent: f64) -> u32 {
+    let with_margin = (consumed as f64 * (1.0 + margin_percent.max(0.0) / 100.0)).ceil() as u64;
+    let rounded = with_margin.div_ceil(CU_LIMIT_GRANULARITY) * CU_LIMIT_GRANULARITY;
+    rounded.clamp(CU_LIMIT_GRANULARITY, MAX_COMPUTE_UNITS as u64) as u32
+}
+
+/// Prioritization fee in lamports for `cu_limit` units at `cu_price`
+/// micro-lamports per unit, rounded up and saturating like the runtime
+pub fn prioritization_fee(cu_limit: u32, cu_price_microlamports: u64) -> u64 {
+    (cu_limit as u128 * cu_price_microlamports as u128)
+        .div_ceil(1_000_000)
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommended_cu_limit_rounds_up_and_caps() {
+        assert_eq!(recommended_cu_limit(181_234, 10.0), 200_000);
+        assert_eq!(recommended_cu_limit(200_000, 0.0), 200_000);
+        assert_eq!(recommended_cu_limit(200_001, 0.0), 201_000);
+        assert_eq!(recommended_cu_limit(1_350_000, 10.0), MAX_COMPUTE_UNITS);
+        assert_eq!(recommended_cu_limit(0, 10.0), 1_000);
+        assert_eq!(set_compute_unit_limit(200_000).data, [2, 64, 13, 3, 0]);
+        assert_eq!(request_heap_frame(64 * 1024).data, [1, 0, 0, 1, 0]);
+        assert!(is_valid_heap_frame(DEFAULT_HEAP_FRAME));
+        assert!(!is_valid_heap_frame(
+            MAX_HEAP_FRAME + HEAP_FRAME_GRANULARITY
+        ));
+        assert!(!is_valid_heap_frame(40_000));
+        assert_eq!(prioritization_fee(1, 1), 1);
+        assert_eq!(prioritization_fee(200_000, 5), 1);
+        assert_eq!(prioritization_fee(200_000, 10), 2);
+        assert_eq!(prioritization_fee(MAX_COMPUTE_UNITS, u64::MAX), u64::MAX);
+    }
+}
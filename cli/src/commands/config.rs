@@ -1,11 +1,17 @@
 use console::style;
+use serde_json::json;
+use std::collections::BTreeMap;
 use std::io;
 
+use super::fee_payer::{self, FeePayer};
 use super::init::{
-    DEFAULT_WEB_APP_URL, SolanaNetwork, get_solana_network, get_solana_rpc_url, read_config,
-    reset_solana_rpc_url, resolve_project_path, set_solana_network, set_solana_rpc_url,
+    DEFAULT_WEB_APP_URL, KEYPAIR_PATH_KEY, SolanaNetwork, get_solana_network, get_solana_rpc_url,
+    read_config, reset_solana_rpc_url, resolve_project_path, set_solana_network,
+    set_solana_rpc_url, write_config_value,
 };
+use crate::error;
 use crate::ui::{self, emoji};
+use crate::ui_println;
 
 /// Display current configuration
 pub fn run_config_show(path: Option<String>) -> io::Result<()> {
@@ -48,10 +54,56 @@ pub fn run_config_show(path: Option<String>) -> io::Result<()> {
         "Version",
         config.get("version").map(|s| s.as_str()).unwrap_or("0.1.0"),
     );
+    let keypair = match config.get(KEYPAIR_PATH_KEY) {
+        Some(path) => path.clone(),
+        None => fee_payer::default_keypair_path()
+            .map(|p| format!("{} (Solana CLI default)", p.display()))
+            .unwrap_or_else(|| "-".to_string()),
+    };
+    ui::add_kv_row(&mut table, emoji::GEAR, "Keypair", &keypair);
+
+    let budgets = &config.budgets;
+    let limits = [
+        ("Max CU", budgets.max_cu.map(|v| v.to_string())),
+        (
+            "Max budget %",
+            budgets.max_budget_percent.map(|v| format!("{}%", v)),
+        ),
+        (
+            "Max message size",
+            budgets.max_message_size.map(|v| format!("{} bytes", v)),
+        ),
+        (
+            "Max total fee",
+            budgets.max_total_fee.map(|v| format!("{} lamports", v)),
+        ),
+    ];
+    for (label, value) in limits {
+        if let Some(value) = value {
+            ui::add_kv_row(&mut table, emoji::PIN, label, &value);
+        }
+    }
 
-    println!("{table}");
+    ui_println!("{table}");
     ui::blank();
 
+    let settings: BTreeMap<&String, &String> = config.settings.iter().collect();
+    ui::emit_json(&json!({
+        "command": "config show",
+        "network": network.as_str(),
+        "rpc_url": rpc_url,
+        "rpc_is_custom": rpc_url != network.rpc_url(),
+        "default_rpc_url": network.rpc_url(),
+        "web_app_url": config
+            .get("web_app_url")
+            .map(|s| s.as_str())
+            .unwrap_or(DEFAULT_WEB_APP_URL),
+        "version": config.get("version").map(|s| s.as_str()).unwrap_or("0.1.0"),
+        "keypair_path": config.get(KEYPAIR_PATH_KEY),
+        "settings": settings,
+        "budgets": config.budgets,
+    }));
+
     Ok(())
 }
 
@@ -67,6 +119,12 @@ pub fn run_config_get_network(path: Option<String>) -> io::Result<()> {
     ui::print_tree(&items);
     ui::blank();
 
+    ui::emit_json(&json!({
+        "command": "config get-network",
+        "network": network.as_str(),
+        "rpc_url": rpc_url,
+    }));
+
     Ok(())
 }
 
@@ -85,6 +143,12 @@ pub fn run_config_set_network(network_str: &str, path: Option<String>) -> io::Re
             "Solana network is already set to: {}",
             style(network).bold()
         ));
+        ui::emit_json(&json!({
+            "command": "config set-network",
+            "network": network.as_str(),
+            "previous_network": old_network.as_str(),
+            "changed": false,
+        }));
         return Ok(());
     }
 
@@ -104,6 +168,14 @@ pub fn run_config_set_network(network_str: &str, path: Option<String>) -> io::Re
     ui::print_value_with_emoji(emoji::LINK, "RPC URL", network.rpc_url());
     ui::blank();
 
+    ui::emit_json(&json!({
+        "command": "config set-network",
+        "network": network.as_str(),
+        "previous_network": old_network.as_str(),
+        "rpc_url": network.rpc_url(),
+        "changed": true,
+    }));
+
     Ok(())
 }
 
@@ -129,7 +201,7 @@ pub fn run_config_list_networks(path: Option<String>) -> io::Result<()> {
         };
         let url = style(network.rpc_url()).dim();
 
-        println!("  {} {:<12} {}", marker, name, url);
+        ui_println!("  {} {:<12} {}", marker, name, url);
     }
 
     ui::blank();
@@ -137,14 +209,14 @@ pub fn run_config_list_networks(path: Option<String>) -> io::Result<()> {
     // Show current RPC URL
     let is_custom = current_rpc != current.rpc_url();
     if is_custom {
-        println!(
+        ui_println!(
             "  {} {} {}",
             emoji::LINK,
             style("Current RPC (custom):").dim(),
             style(&current_rpc).cyan()
         );
     } else {
-        println!(
+        ui_println!(
             "  {} {} {}",
             emoji::LINK,
             style("Current RPC:").dim(),
@@ -153,12 +225,31 @@ pub fn run_config_list_networks(path: Option<String>) -> io::Result<()> {
     }
 
     ui::blank();
-    println!("  {} {}", emoji::BULB, style("Commands:").dim());
-    println!(
+
+    let networks: Vec<serde_json::Value> = SolanaNetwork::all()
+        .iter()
+        .map(|network| {
+            json!({
+                "name": network.as_str(),
+                "rpc_url": network.rpc_url(),
+                "current": *network == current,
+            })
+        })
+        .collect();
+    ui::emit_json(&json!({
+        "command": "config list-networks",
+        "current": current.as_str(),
+        "rpc_url": current_rpc,
+        "rpc_is_custom": is_custom,
+        "networks": networks,
+    }));
+
+    ui_println!("  {} {}", emoji::BULB, style("Commands:").dim());
+    ui_println!(
         "     {} Switch network",
         style("zklense config set-network <network>").cyan()
     );
-    println!(
+    ui_println!(
         "     {} Custom RPC",
         style("zklense config set-rpc <url>").cyan()
     );
@@ -189,9 +280,17 @@ pub fn run_config_get_rpc(path: Option<String>) -> io::Result<()> {
 
     ui::print_tree(&items);
 
+    ui::emit_json(&json!({
+        "command": "config get-rpc",
+        "rpc_url": rpc_url,
+        "network": network.as_str(),
+        "rpc_is_custom": is_custom,
+        "default_rpc_url": network.rpc_url(),
+    }));
+
     if is_custom {
         ui::blank();
-        println!(
+        ui_println!(
             "  {} Default for {} is: {}",
             emoji::INFO,
             network,
@@ -229,6 +328,12 @@ pub fn run_config_set_rpc(rpc_url: &str, path: Option<String>) -> io::Result<()>
             "RPC URL is already set to: {}",
             style(rpc_url).bold()
         ));
+        ui::emit_json(&json!({
+            "command": "config set-rpc",
+            "rpc_url": rpc_url,
+            "previous_rpc_url": old_rpc,
+            "changed": false,
+        }));
         return Ok(());
     }
 
@@ -236,14 +341,21 @@ pub fn run_config_set_rpc(rpc_url: &str, path: Option<String>) -> io::Result<()>
     set_solana_rpc_url(&base_path, rpc_url)?;
     ui::spinner_success(&spinner, "RPC URL updated");
 
+    ui::emit_json(&json!({
+        "command": "config set-rpc",
+        "rpc_url": rpc_url,
+        "previous_rpc_url": old_rpc,
+        "changed": true,
+    }));
+
     ui::blank();
-    println!(
+    ui_println!(
         "  {} {} {}",
         emoji::TREE_BRANCH,
         style("Old:").dim(),
         style(&old_rpc).dim().strikethrough()
     );
-    println!(
+    ui_println!(
         "  {} {} {}",
         emoji::TREE_END,
         style("New:").dim(),
@@ -262,6 +374,14 @@ pub fn run_config_reset_rpc(path: Option<String>) -> io::Result<()> {
 
     let new_rpc = reset_solana_rpc_url(&base_path)?;
 
+    ui::emit_json(&json!({
+        "command": "config reset-rpc",
+        "network": network.as_str(),
+        "rpc_url": new_rpc,
+        "previous_rpc_url": old_rpc,
+        "changed": old_rpc != new_rpc,
+    }));
+
     if old_rpc == new_rpc {
         ui::info(&format!(
             "RPC URL is already set to the default: {}",
@@ -273,13 +393,13 @@ pub fn run_config_reset_rpc(path: Option<String>) -> io::Result<()> {
             style(network).bold()
         ));
         ui::blank();
-        println!(
+        ui_println!(
             "  {} {} {}",
             emoji::TREE_BRANCH,
             style("Old:").dim(),
             style(&old_rpc).dim().strikethrough()
         );
-        println!(
+        ui_println!(
             "  {} {} {}",
             emoji::TREE_END,
             style("New:").dim(),
@@ -291,9 +411,42 @@ pub fn run_config_reset_rpc(path: Option<String>) -> io::Result<()> {
     Ok(())
 }
 
+/// Set the fee payer keypair used by `zklense simulate`
+pub fn run_config_set_keypair(keypair_path: &str, path: Option<String>) -> io::Result<()> {
+    let base_path = resolve_project_path(path.as_deref())?;
+
+    // Refuse files that are not valid keypairs
+    let payer = FeePayer::load(&base_path, keypair_path).map_err(|e| {
+        ui::panel_error(
+            "INVALID KEYPAIR",
+            &e.to_string(),
+            None,
+            Some(&["Create one with: solana-keygen new -o <path>"]),
+        );
+        io::Error::new(io::ErrorKind::InvalidInput, e.to_string())
+    })?;
+
+    write_config_value(&base_path, KEYPAIR_PATH_KEY, keypair_path)?;
+    ui::success(&format!(
+        "Fee payer keypair set to {}",
+        style(keypair_path).bold()
+    ));
+    ui::blank();
+    ui::print_tree(&[("Address", &payer.pubkey.to_string())]);
+    ui::blank();
+
+    ui::emit_json(&json!({
+        "command": "config set-keypair",
+        "keypair_path": keypair_path,
+        "address": payer.pubkey.to_string(),
+    }));
+
+    Ok(())
+}
+
 /// Main config command runner
-pub fn run_config(action: ConfigAction, path: Option<String>) -> io::Result<()> {
-    match action {
+pub fn run_config(action: ConfigAction, path: Option<String>) -> error::Result<()> {
+    let result = match action {
         ConfigAction::Show => run_config_show(path),
         ConfigAction::GetNetwork => run_config_get_network(path),
         ConfigAction::SetNetwork(network) => run_config_set_network(&network, path),
@@ -301,7 +454,9 @@ pub fn run_config(action: ConfigAction, path: Option<String>) -> io::Result<()>
         ConfigAction::GetRpc => run_config_get_rpc(path),
         ConfigAction::SetRpc(rpc_url) => run_config_set_rpc(&rpc_url, path),
         ConfigAction::ResetRpc => run_config_reset_rpc(path),
-    }
+        ConfigAction::SetKeypair(keypair_path) => run_config_set_keypair(&keypair_path, path),
+    };
+    result.map_err(Into::into)
 }
 
 /// Config subcommand actions
@@ -313,4 +468,5 @@ pub enum ConfigAction {
     GetRpc,
     SetRpc(String),
     ResetRpc,
+    SetKeypair(String),
 }
@@ -0,0 +1,440 @@
+//! Explanations for failed verify transactions
+//!
+//! Decodes the `TransactionError` of a failed simulation or submission,
+//! matches it against the program logs and, when the verifier itself
+//! failed, against a native check of the proof with the local verifying key.
+//! Most failures come down to a handful of causes, each reported with an
+//! explanation and a fix.
+
+use console::style;
+use solana_client::rpc_response::UiTransactionError;
+use solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, transaction::Transaction,
+    transaction::TransactionError,
+};
+use std::fs;
+use std::path::Path;
+
+use super::compute_budget::MAX_COMPUTE_UNITS;
+use super::gnark;
+use super::report::{Diagnosis, FailureCause};
+use super::simulate::{ProofResult, format_number, parse_compute_budget_instructions};
+use super::verify;
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Log lines kept as evidence
+const MAX_EVIDENCE: usize = 5;
+
+/// Outcome of checking the proof against `target/<circuit>.vk` natively
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArtifactCheck {
+    /// No readable verifying key, proof or witness
+    Unavailable,
+    Valid,
+    WitnessMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    Invalid(String),
+}
+
+/// What the failed transaction was meant to do
+pub struct Context<'a> {
+    pub transaction: &'a Transaction,
+    pub program_id: &'a Pubkey,
+    pub network: &'a str,
+    pub artifacts: ArtifactCheck,
+}
+
+/// Verify the proof natively against the circuit's verifying key
+pub fn check_artifacts(base_path: &Path, proof_result: &ProofResult) -> ArtifactCheck {
+    let Ok(circuit) = super::run::read_circuit_name(base_path) else {
+        return ArtifactCheck::Unavailable;
+    };
+    let vk_path = base_path.join(super::run::target_file(&circuit, "vk"));
+    let parsed = fs::read(&vk_path).ok().and_then(|vk| {
+        Some((
+            gnark::parse_verifying_key(&vk).ok()?,
+            gnark::parse_proof(&proof_result.proof).ok()?,
+            gnark::parse_witness(&proof_result.public_witness).ok()?,
+        ))
+    });
+    let Some((vk, proof, witness)) = parsed else {
+        return ArtifactCheck::Unavailable;
+    };
+
+    let public_inputs = &witness.values[..(witness.nb_public as usize).min(witness.values.len())];
+    if public_inputs.len() != vk.nb_public_inputs() {
+        return ArtifactCheck::WitnessMismatch {
+            expected: vk.nb_public_inputs(),
+            actual: public_inputs.len(),
+        };
+    }
+    match verify::verify_proof(&vk, &proof, public_inputs) {
+        Ok(_) => ArtifactCheck::Valid,
+        Err(e) => ArtifactCheck::Invalid(e.message().to_string()),
+    }
+}
+
+/// Log lines that mention a failure, most recent last
+fn evidence(logs: &[String], needles: &[&str]) -> Vec<String> {
+    let lines: Vec<String> = logs
+        .iter()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            needles.iter().any(|needle| line.contains(needle))
+        })
+        .cloned()
+        .collect();
+    lines[lines.len().saturating_sub(MAX_EVIDENCE)..].to_vec()
+}
+
+/// Whether any log line contains one of `needles`
+fn logs_contain(logs: &[String], needles: &[&str]) -> bool {
+    !evidence(logs, needles).is_empty()
+}
+
+const HEAP_NEEDLES: &[&str] = &["memory allocation failed", "out of memory"];
+
+/// Whether the logs show the program running out of heap
+pub fn heap_exhausted(logs: &[String]) -> bool {
+    logs_contain(logs, HEAP_NEEDLES)
+}
+
+/// Custom error codes of the verifier program sunspot generates, with what
+/// each means and how to fix it
+const VERIFIER_ERRORS: &[(u32, &str, &str)] = &[
+    (
+        0,
+        "The proof has the wrong length for this verifier",
+        "Pass target/<circuit>.proof unmodified, or regenerate it with 'zklense run --from prove'",
+    ),
+    (
+        1,
+        "The public witness has the wrong length or number of inputs for this verifier",
+        "Regenerate the witness and proof for this circuit with 'zklense run --from execute'",
+    ),
+    (
+        2,
+        "A proof point is not on the curve or a public input is outside the scalar field",
+        "Regenerate the proof with 'zklense run --from prove' and check it with 'zklense verify'",
+    ),
+    (
+        3,
+        "The commitment proof in the proof does not verify",
+        "Regenerate the proof with 'zklense run --from prove' and check it with 'zklense verify'",
+    ),
+    (
+        4,
+        "The pairing check failed: the proof does not verify against the verifying key embedded in the program",
+        "Run 'zklense verify'; if the proof verifies locally, rebuild and redeploy the verifier with 'zklense run' so it embeds the current verifying key",
+    ),
+];
+
+const FAILURE_NEEDLES: &[&str] = &["failed", "error", "panicked", "exceeded", "invalid"];
+
+fn diagnosis(cause: FailureCause, explanation: String, fix: &str) -> Diagnosis {
+    Diagnosis {
+        cause,
+        instruction_index: None,
+        program: None,
+        custom_code: None,
+        explanation,
+        fix: fix.to_string(),
+        evidence: Vec::new(),
+    }
+}
+
+fn program_not_deployed(context: &Context) -> Diagnosis {
+    diagnosis(
+        FailureCause::ProgramNotDeployed,
+        format!(
+            "Program {} is not deployed on {}",
+            context.program_id, context.network
+        ),
+        "Deploy target/<circuit>.so with 'solana program deploy', pass the deployed ID with --program-id, or simulate with --localnet or --offline",
+    )
+}
+
+/// Explain why `err` happened
+pub fn diagnose(err: &UiTransactionError, logs: &[String], context: &Context) -> Diagnosis {
+    let mut result = match TransactionError::from(err.clone()) {
+        TransactionError::InstructionError(index, error) => {
+            instruction_failure(index, &error, logs, context)
+        }
+        TransactionError::ProgramAccountNotFound | TransactionError::InvalidProgramForExecution => {
+            program_not_deployed(context)
+        }
+        TransactionError::AccountNotFound
+        | TransactionError::InsufficientFundsForFee
+        | TransactionError::InsufficientFundsForRent { .. } => diagnosis(
+            FailureCause::FeePayerNotFunded,
+            "The fee payer has no lamports, or too few to pay the fee".to_string(),
+            "Fund the fee payer (solana airdrop 1 <address> --url devnet) or pass another keypair with --keypair",
+        ),
+        TransactionError::SignatureFailure => diagnosis(
+            FailureCause::SignatureVerification,
+            "A signature does not match the transaction".to_string(),
+            "Make sure every signer account in .zklense/instruction.toml has a keypair, or drop --sig-verify",
+        ),
+        TransactionError::BlockhashNotFound => diagnosis(
+            FailureCause::BlockhashNotFound,
+            "The transaction's blockhash expired or is unknown to the node".to_string(),
+            "Retry, or pass --replace-blockhash to let the node use its latest blockhash",
+        ),
+        TransactionError::AddressLookupTableNotFound
+        | TransactionError::InvalidAddressLookupTableOwner
+        | TransactionError::InvalidAddressLookupTableData
+        | TransactionError::InvalidAddressLookupTableIndex => diagnosis(
+            FailureCause::LookupTableInvalid,
+            format!("An address lookup table could not be used: {}", err),
+            "Check the --lookup-table address exists on this cluster and is active, or use --create-lookup-table",
+        ),
+        other => diagnosis(
+            FailureCause::Unknown,
+            format!("The transaction was rejected: {}", other),
+            "Review the program logs in the report",
+        ),
+    };
+    if result.evidence.is_empty() {
+        result.evidence = evidence(logs, FAILURE_NEEDLES);
+    }
+    result
+}
+
+fn instruction_failure(
+    index: u8,
+    error: &InstructionError,
+    logs: &[String],
+    context: &Context,
+) -> Diagnosis {
+    let message = &context.transaction.message;
+    let program = message
+        .instructions
+        .get(index as usize)
+        .and_then(|instruction| {
+            message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+        })
+        .copied();
+    let is_verifier = program.as_ref() == Some(context.program_id);
+
+    let mut result = if matches!(error, InstructionError::ComputationalBudgetExceeded)
+        || logs_contain(logs, &["exceeded cus meter"])
+    {
+        let limit = parse_compute_budget_instructions(context.transaction).0;
+        let mut result = diagnosis(
+            FailureCause::ComputeBudgetExceeded,
+            format!(
+                "Verification needs more than the {} CU limit",
+                format_number(limit as u64)
+            ),
+            "Raise the compute unit limit; 'zklense simulate --tune-cu' reports the limit verification needs",
+        );
+        if limit >= MAX_COMPUTE_UNITS {
+            result.fix = "Verification does not fit in the 1,400,000 CU a transaction may use; reduce the circuit's public inputs and commitments".to_string();
+        }
+        result.evidence = evidence(logs, &["exceeded cus meter", "consumed"]);
+        result
+    } else if matches!(error, InstructionError::UnsupportedProgramId)
+        || logs_contain(
+            logs,
+            &["program that does not exist", "program is not deployed"],
+        )
+    {
+        program_not_deployed(context)
+    } else if matches!(error, InstructionError::MissingRequiredSignature) {
+        diagnosis(
+            FailureCause::MissingSignature,
+            "An account the program expects to sign did not sign".to_string(),
+            "Mark the account as a signer with a keypair in .zklense/instruction.toml, or pass the fee payer with --keypair",
+        )
+    } else if heap_exhausted(logs) {
+        let mut result = diagnosis(
+            FailureCause::HeapExhausted,
+            "The program ran out of heap memory".to_string(),
+            "Find the heap frame verification needs with --measure-heap and request it with --heap-frame",
+        );
+        result.evidence = evidence(logs, HEAP_NEEDLES);
+        result
+    } else if logs_contain(logs, &["panicked at"]) {
+        let mut result = diagnosis(
+            FailureCause::ProgramPanicked,
+            "The program panicked".to_string(),
+            "See the panic message below; it usually points at malformed instruction data",
+        );
+        result.evidence = evidence(logs, &["panicked at"]);
+        result
+    } else if matches!(error, InstructionError::ProgramFailedToComplete) {
+        diagnosis(
+            FailureCause::ProgramFailedToComplete,
+            "The program stopped before finishing without returning an error or running out of compute units; it hit another runtime limit, such as the call depth or stack size, or aborted".to_string(),
+            "See the last log lines below; if the verifier aborts on a proof 'zklense verify' accepts, rebuild and redeploy it with 'zklense run'",
+        )
+    } else if is_verifier {
+        verifier_failure(error, context)
+    } else {
+        diagnosis(
+            FailureCause::Unknown,
+            format!("Instruction {} failed: {}", index, error),
+            "Review the program logs in the report",
+        )
+    };
+
+    result.instruction_index = Some(index);
+    result.program = program.map(|p| p.to_string());
+    if let InstructionError::Custom(code) = error {
+        result.custom_code = Some(*code);
+    }
+    result
+}
+
+/// The verifier rejected the instruction; the local artifacts tell whether
+/// the proof, the witness or the deployed program is at fault
+fn verifier_failure(error: &InstructionError, context: &Context) -> Diagnosis {
+    match &context.artifacts {
+        ArtifactCheck::WitnessMismatch { expected, actual } => diagnosis(
+            FailureCause::WitnessMismatch,
+            format!(
+                "The public witness has {} input(s) but the verifying key expects {}",
+                actual, expected
+            ),
+            "Regenerate the witness and proof for this circuit with 'zklense run --from execute'",
+        ),
+        ArtifactCheck::Invalid(reason) => diagnosis(
+            FailureCause::ProofRejected,
+            format!(
+                "The proof does not verify against the local verifying key either: {}",
+                reason
+            ),
+            "Regenerate the proof with 'zklense run --from prove' and check it with 'zklense verify'",
+        ),
+        ArtifactCheck::Valid if !matches!(error, InstructionError::InvalidInstructionData) => {
+            diagnosis(
+                FailureCause::VerifyingKeyMismatch,
+                "The proof verifies against the local verifying key but the program rejects it; it was likely built from another verifying key".to_string(),
+                "Rebuild and redeploy the verifier with 'zklense run' so it embeds the current verifying key",
+            )
+        }
+        _ => match error {
+            InstructionError::InvalidInstructionData | InstructionError::InvalidArgument => {
+                diagnosis(
+                    FailureCause::InvalidInstructionData,
+                    "The program could not decode the instruction data".to_string(),
+                    "If the program wraps the verifier, describe its instruction layout in .zklense/instruction.toml",
+                )
+            }
+            InstructionError::Custom(code) => match VERIFIER_ERRORS
+                .iter()
+                .find(|(known, _, _)| known == code)
+            {
+                Some((_, explanation, fix)) => diagnosis(
+                    FailureCause::CustomError,
+                    format!("{} (verifier error {})", explanation, code),
+                    fix,
+                ),
+                None => diagnosis(
+                    FailureCause::CustomError,
+                    format!("The verifier returned custom error {} ({:#x})", code, code),
+                    "Check the proof with 'zklense verify' and look the code up in the program's error enum",
+                ),
+            },
+            other => diagnosis(
+                FailureCause::Unknown,
+                format!("The verifier failed: {}", other),
+                "Check the proof with 'zklense verify' and review the program logs in the report",
+            ),
+        },
+    }
+}
+
+/// Print the cause, the fix and the matching log lines
+pub fn print_diagnosis(diagnosis: &Diagnosis) {
+    ui::section(emoji::BULB, "Diagnosis");
+    let mut items = vec![("Cause", diagnosis.explanation.clone())];
+    if let Some(code) = diagnosis.custom_code {
+        items.push(("Error Code", format!("{} ({:#x})", code, code)));
+    }
+    if let (Some(index), Some(program)) = (diagnosis.instruction_index, &diagnosis.program) {
+        items.push(("Instruction", format!("#{} ({})", index, program)));
+    }
+    items.push(("Fix", diagnosis.fix.clone()));
+    let items: Vec<(&str, &str)> = items.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    ui::print_tree(&items);
+    for line in &diagnosis.evidence {
+        ui_println!("    {}", style(line).dim());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::compute_budget;
+    use solana_sdk::instruction::Instruction;
+
+    #[test]
+    fn test_verifier_errors_are_matched_with_artifacts() {
+        let program_id = Pubkey::new_unique();
+        let transaction = Transaction::new_with_payer(
+            &[
+                compute_budget::set_compute_unit_limit(MAX_COMPUTE_UNITS),
+                Instruction::new_with_bytes(program_id, &[], vec![]),
+            ],
+            Some(&Pubkey::new_unique()),
+        );
+        let mut context = Context {
+            transaction: &transaction,
+            program_id: &program_id,
+            network: "devnet",
+            artifacts: ArtifactCheck::WitnessMismatch {
+                expected: 2,
+                actual: 1,
+            },
+        };
+        let custom = UiTransactionError::from(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(3),
+        ));
+
+        let result = diagnose(&custom, &[], &context);
+        assert_eq!(result.cause, FailureCause::WitnessMismatch);
+        assert_eq!(result.custom_code, Some(3));
+        assert_eq!(result.program, Some(program_id.to_string()));
+
+        context.artifacts = ArtifactCheck::Unavailable;
+        let result = diagnose(&custom, &[], &context);
+        assert_eq!(result.cause, FailureCause::CustomError);
+        assert!(result.explanation.starts_with("The commitment proof"));
+        let unknown_code = UiTransactionError::from(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(99),
+        ));
+        let result = diagnose(&unknown_code, &[], &context);
+        assert_eq!(result.cause, FailureCause::CustomError);
+        assert!(result.explanation.contains("custom error 99 (0x63)"));
+
+        let logs = vec![format!(
+            "Program {} failed: exceeded CUs meter at BPF instruction",
+            program_id
+        )];
+        let exceeded = UiTransactionError::from(TransactionError::InstructionError(
+            1,
+            InstructionError::ProgramFailedToComplete,
+        ));
+        let result = diagnose(&exceeded, &logs, &context);
+        assert_eq!(result.cause, FailureCause::ComputeBudgetExceeded);
+        assert_eq!(result.evidence, logs);
+        assert_eq!(
+            diagnose(&exceeded, &[], &context).cause,
+            FailureCause::ProgramFailedToComplete
+        );
+
+        let missing = UiTransactionError::from(TransactionError::ProgramAccountNotFound);
+        assert_eq!(
+            diagnose(&missing, &[], &context).cause,
+            FailureCause::ProgramNotDeployed
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Compare two simulation reports
+
+use console::style;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::history::{format_timestamp, latest_report_path, list_reports, reports_dir};
+use super::report::{SimulationReport, Status};
+use super::simulate::format_number;
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// A report selected on the command line
+struct Loaded {
+    /// History ID, or the path the report was read from
+    label: String,
+    report: SimulationReport,
+}
+
+/// One compared value. Every metric is a cost, so lower is better.
+struct Metric {
+    name: &'static str,
+    old: f64,
+    new: f64,
+    /// Digits after the decimal point when displayed
+    precision: usize,
+    unit: &'static str,
+}
+
+impl Metric {
+    fn new(name: &'static str, old: f64, new: f64, precision: usize, unit: &'static str) -> Self {
+        Metric {
+            name,
+            old,
+            new,
+            precision,
+            unit,
+        }
+    }
+
+    fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+
+    /// Relative change in percent, `None` when the old value is zero
+    fn delta_percent(&self) -> Option<f64> {
+        (self.old != 0.0).then(|| self.delta() / self.old * 100.0)
+    }
+
+    fn format(&self, value: f64) -> String {
+        let number = if self.precision == 0 {
+            format_number(value as u64)
+        } else {
+            format!("{:.*}", self.precision, value)
+        };
+        match self.unit {
+            "" => number,
+            "%" => format!("{}%", number),
+            _ => format!("{} {}", number, self.unit),
+        }
+    }
+
+    /// Value for JSON output, an integer unless the metric has decimals
+    fn json_value(&self, value: f64) -> serde_json::Value {
+        if self.precision == 0 {
+            json!(value as i64)
+        } else {
+            json!(value)
+        }
+    }
+
+    /// Stable snake_case key for JSON output
+    fn key(&self) -> String {
+        self.name.to_lowercase().replace(' ', "_")
+    }
+
+    fn format_delta(&self) -> String {
+        let delta = self.delta();
+        if delta == 0.0 {
+            return "0".to_string();
+        }
+        let sign = if delta > 0.0 { "+" } else { "-" };
+        let magnitude = if self.precision == 0 {
+            format_number(delta.abs() as u64)
+        } else {
+            format!("{:.*}", self.precision, delta.abs())
+        };
+        match self.delta_percent() {
+            Some(percent) => format!("{}{} ({:+.1}%)", sign, magnitude, percent),
+            None => format!("{}{}", sign, magnitude),
+        }
+    }
+}
+
+fn metrics(old: &SimulationReport, new: &SimulationReport) -> Vec<Metric> {
+    let int = |v: u64| v as f64;
+    vec![
+        Metric::new(
+            "Compute units consumed",
+            int(old.compute_units.total_compute_units_consumed),
+            int(new.compute_units.total_compute_units_consumed),
+            0,
+            "CU",
+        ),
+        Metric::new(
+            "Budget used",
+            old.compute_units.percentage_of_compute_budget_used,
+            new.compute_units.percentage_of_compute_budget_used,
+            2,
+            "%",
+        ),
+        Metric::new(
+            "Transaction size",
+            old.transaction_size.transaction_size as f64,
+            new.transaction_size.transaction_size as f64,
+            0,
+            "bytes",
+        ),
+        Metric::new(
+            "Message size",
+            old.transaction_size.message_size as f64,
+            new.transaction_size.message_size as f64,
+            0,
+            "bytes",
+        ),
+        Metric::new(
+            "Proof size",
+            old.proof.proof_size as f64,
+            new.proof.proof_size as f64,
+            0,
+            "bytes",
+        ),
+        Metric::new(
+            "Witness size",
+            old.proof.witness_size as f64,
+            new.proof.witness_size as f64,
+            0,
+            "bytes",
+        ),
+        Metric::new(
+            "Base fee",
+            int(old.cost.base_fee),
+            int(new.cost.base_fee),
+            0,
+            "lamports",
+        ),
+        Metric::new(
+            "Priority fee",
+            int(old.cost.prioritization_fee),
+            int(new.cost.prioritization_fee),
+            0,
+            "lamports",
+        ),
+        Metric::new(
+            "Total fee",
+            int(old.cost.total_fee),
+            int(new.cost.total_fee),
+            0,
+            "lamports",
+        ),
+        Metric::new(
+            "Accounts",
+            old.accounts.total_accounts as f64,
+            new.accounts.total_accounts as f64,
+            0,
+            "",
+        ),
+        Metric::new(
+            "Writable accounts",
+            old.accounts.total_writable_accounts as f64,
+            new.accounts.total_writable_accounts as f64,
+            0,
+            "",
+        ),
+        Metric::new(
+            "Log lines",
+            old.transaction_logs.log_count as f64,
+            new.transaction_logs.log_count as f64,
+            0,
+            "",
+        ),
+    ]
+}
+
+/// Lines present in `a` but not in `b`, counting duplicates, in `a`'s order
+fn missing_lines<'a>(a: &'a [String], b: &[String]) -> Vec<&'a str> {
+    let mut available: HashMap<&str, usize> = HashMap::new();
+    for line in b {
+        *available.entry(line.as_str()).or_default() += 1;
+    }
+    a.iter()
+        .filter(|line| match available.get_mut(line.as_str()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .map(|line| line.as_str())
+        .collect()
+}
+
+/// Load a report given as a file path or history ID
+fn load_report(base_path: &Path, selector: &str) -> error::Result<Loaded> {
+    let as_path = base_path.join(selector);
+    let path = if as_path.is_file() {
+        as_path
+    } else {
+        let by_id = reports_dir(base_path).join(format!("{}.json", selector));
+        if !by_id.is_file() {
+            return Err(ZkLenseError::MissingArtifact(format!(
+                "No report file or history ID '{}'\nRun 'zklense history' to list recorded reports.",
+                selector
+            )));
+        }
+        by_id
+    };
+    read_report(selector.to_string(), &path)
+}
+
+fn read_report(label: String, path: &Path) -> error::Result<Loaded> {
+    let contents = fs::read_to_string(path)?;
+    let report = serde_json::from_str(&contents).map_err(|e| {
+        ZkLenseError::InvalidInput(format!("{} is not a valid report: {}", path.display(), e))
+    })?;
+    Ok(Loaded { label, report })
+}
+
+/// Pick the two reports to compare. With no arguments the two most recent
+/// history entries are used; with one, it is compared to the latest report.
+fn select_reports(
+    base_path: &Path,
+    old: Option<String>,
+    new: Option<String>,
+) -> error::Result<(Loaded, Loaded)> {
+    match (old, new) {
+        (Some(old), Some(new)) => {
+            Ok((load_report(base_path, &old)?, load_report(base_path, &new)?))
+        }
+        (Some(old), None) => {
+            let old = load_report(base_path, &old)?;
+            let latest = latest_report_path(base_path);
+            if !latest.is_file() {
+                return Err(ZkLenseError::MissingArtifact(format!(
+                    "No report found at {}\nRun 'zklense simulate' first to generate a report.",
+                    latest.display()
+                )));
+            }
+            Ok((old, read_report("latest".to_string(), &latest)?))
+        }
+        (None, _) => {
+            let mut reports = list_reports(base_path)?;
+            let count = reports.len();
+            match (reports.pop(), reports.pop()) {
+                (Some((new_label, new)), Some((old_label, old))) => Ok((
+                    Loaded {
+                        label: old_label,
+                        report: old,
+                    },
+                    Loaded {
+                        label: new_label,
+                        report: new,
+                    },
+                )),
+                _ => Err(ZkLenseError::MissingArtifact(format!(
+                    "Need at least two reports in {} to compare, found {}",
+                    reports_dir(base_path).display(),
+                    count
+                ))),
+            }
+        }
+    }
+}
+
+fn describe(loaded: &Loaded) -> String {
+    let report = &loaded.report;
+    let mut parts = vec![format_timestamp(report.generated_at)];
+    if let Some(metadata) = &report.metadata {
+        if let Some(circuit) = &metadata.circuit {
+            parts.push(circuit.clone());
+        }
+        if let Some(commit) = &metadata.git_commit {
+            parts.push(commit[..commit.len().min(8)].to_string());
+        }
+    }
+    parts.push(report.environment.network.clone());
+    format!("{} ({})", loaded.label, parts.join(", "))
+}
+
+fn status_label(status: Status) -> &'static str {
+    match status {
+        Status::Success => "success",
+        Status::Failed => "failed",
+    }
+}
+
+/// Run the diff command
+pub fn run_diff(old: Option<String>, new: Option<String>) -> error::Result<()> {
+    let base_path = std::env::current_dir()?;
+
+    ui::panel_header(
+        emoji::CHART,
+        "REPORT DIFF",
+        Some("Compare two simulation reports"),
+    );
+
+    let (old, new) = select_reports(&base_path, old, new)?;
+
+    ui::print_tree(&[("Old", &describe(&old)), ("New", &describe(&new))]);
+    ui::blank();
+
+    if old.report.program_id != new.report.program_id {
+        ui::warn(&format!(
+            "Reports target different programs: {} vs {}",
+            old.report.program_id, new.report.program_id
+        ));
+    }
+
+    let metrics = metrics(&old.report, &new.report);
+    let mut table = ui::create_table(&["Metric", "Old", "New", "Change"]);
+    for metric in &metrics {
+        table.add_row(vec![
+            comfy_table::Cell::new(metric.name),
+            comfy_table::Cell::new(metric.format(metric.old)),
+            comfy_table::Cell::new(metric.format(metric.new)),
+            ui::delta_cell(&metric.format_delta(), metric.delta()),
+        ]);
+    }
+    let old_status = old.report.transaction_status.status;
+    let new_status = new.report.transaction_status.status;
+    let status_change = match (old_status, new_status) {
+        (Status::Success, Status::Failed) => 1.0,
+        (Status::Failed, Status::Success) => -1.0,
+        _ => 0.0,
+    };
+    table.add_row(vec![
+        comfy_table::Cell::new("Status"),
+        comfy_table::Cell::new(status_label(old_status)),
+        comfy_table::Cell::new(status_label(new_status)),
+        ui::delta_cell(
+            if status_change == 0.0 {
+                "unchanged"
+            } else {
+                "changed"
+            },
+            status_change,
+        ),
+    ]);
+    ui_println!("{table}");
+
+    let removed = missing_lines(
+        &old.report.transaction_logs.logs,
+        &new.report.transaction_logs.logs,
+    );
+    let added = missing_lines(
+        &new.report.transaction_logs.logs,
+        &old.report.transaction_logs.logs,
+    );
+    ui::section(emoji::FILE, "Log Differences");
+    if removed.is_empty() && added.is_empty() {
+        ui_println!("  {}", style("Logs are identical").dim());
+    } else {
+        for line in &removed {
+            ui_println!("  {}", style(format!("- {}", line)).red());
+        }
+        for line in &added {
+            ui_println!("  {}", style(format!("+ {}", line)).green());
+        }
+    }
+    ui::blank();
+
+    ui::emit_json(&json!({
+        "command": "diff",
+        "old": { "id": old.label, "generated_at": old.report.generated_at },
+        "new": { "id": new.label, "generated_at": new.report.generated_at },
+        "metrics": metrics.iter().map(|m| json!({
+            "metric": m.key(),
+            "label": m.name,
+            "old": m.json_value(m.old),
+            "new": m.json_value(m.new),
+            "delta": m.json_value(m.delta()),
+            "delta_percent": m.delta_percent(),
+        })).collect::<Vec<_>>(),
+        "status": {
+            "old": old_status,
+            "new": new_status,
+        },
+        "logs": {
+            "removed": removed,
+            "added": added,
+        },
+    }));
+
+    let cu = &metrics[0];
+    let summary = match cu.delta() {
+        d if d < 0.0 => style(format!(
+            "On-chain verification got cheaper by {}",
+            cu.format(-d)
+        ))
+        .green(),
+        d if d > 0.0 => style(format!(
+            "On-chain verification got more expensive by {}",
+            cu.format(d)
+        ))
+        .red(),
+        _ => style("Compute units are unchanged".to_string()).dim(),
+    };
+    ui_println!("  {} {}", emoji::LIGHTNING, summary);
+    ui::blank();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_lines_counts_duplicates() {
+        let old: Vec<String> = ["invoke", "log", "log", "success"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let new: Vec<String> = ["invoke", "log", "consumed", "success"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(missing_lines(&old, &new), vec!["log"]);
+        assert_eq!(missing_lines(&new, &old), vec!["consumed"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Fee payer of simulated and submitted transactions
+//!
+//! The keypair comes from `--keypair`, the `keypair_path` setting in
+//! `.zklense/config.toml`, or the Solana CLI default
+//! `~/.config/solana/id.json`, in that order. Local simulations fall back to
+//! a throwaway keypair that zklense funds itself; remote ones fall back to an
+//! unsigned transaction with a placeholder payer.
+
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer, read_keypair_file},
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{self, ZkLenseError};
+
+/// Payer of unsigned simulations when no keypair is available
+const PLACEHOLDER_PAYER: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+/// Keypair used by the Solana CLI when none is configured
+pub fn default_keypair_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".config")
+            .join("solana")
+            .join("id.json")
+    })
+}
+
+/// Where the fee payer came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A keypair file
+    File(PathBuf),
+    /// Generated for this run and funded by zklense
+    Ephemeral,
+    /// No keypair; the transaction stays unsigned
+    Placeholder,
+}
+
+pub struct FeePayer {
+    pub pubkey: Pubkey,
+    keypair: Option<Keypair>,
+    pub source: Source,
+    /// Other signer accounts of the transaction
+    cosigners: Vec<Keypair>,
+}
+
+impl FeePayer {
+    /// Resolve the fee payer. A keypair given with `--keypair` or in the
+    /// config must exist; a missing Solana CLI default falls back to an
+    /// ephemeral keypair when `local`, or a placeholder otherwise.
+    pub fn resolve(
+        flag: Option<&str>,
+        configured: Option<&str>,
+        base_path: &Path,
+        local: bool,
+    ) -> error::Result<FeePayer> {
+        if let Some(path) = flag.or(configured) {
+            return FeePayer::load(base_path, path);
+        }
+        if let Some(path) = default_keypair_path().filter(|p| p.is_file()) {
+            return FeePayer::from_file(&path);
+        }
+        Ok(if local {
+            let keypair = Keypair::new();
+            FeePayer {
+                pubkey: keypair.pubkey(),
+                keypair: Some(keypair),
+                source: Source::Ephemeral,
+                cosigners: Vec::new(),
+            }
+        } else {
+            FeePayer {
+                pubkey: Pubkey::from_str(PLACEHOLDER_PAYER).unwrap(),
+                keypair: None,
+                source: Source::Placeholder,
+                cosigners: Vec::new(),
+            }
+        })
+    }
+
+    /// Load a keypair file given relative to `base_path` or with a leading `~/`
+    pub fn load(base_path: &Path, path: &str) -> error::Result<FeePayer> {
+        FeePayer::from_file(&base_path.join(expand_home(path)))
+    }
+
+    /// Load a fee payer from a Solana CLI keypair file
+    pub fn from_file(path: &Path) -> error::Result<FeePayer> {
+        if !path.is_file() {
+            return Err(ZkLenseError::Config(format!(
+                "Keypair file not found: {}",
+                path.display()
+            )));
+        }
+        let keypair = read_keypair_file(path).map_err(|e| {
+            ZkLenseError::Config(format!("Invalid keypair file {}: {}", path.display(), e))
+        })?;
+        Ok(FeePayer {
+            pubkey: keypair.pubkey(),
+            keypair: Some(keypair),
+            source: Source::File(path.to_path_buf()),
+            cosigners: Vec::new(),
+        })
+    }
+
+    /// Whether transactions from this payer are signed
+    pub fn signs(&self) -> bool {
+        self.keypair.is_some()
+    }
+
+    /// Also sign with the keypairs of other signer accounts, in the
+    /// transactions that require them
+    pub fn add_cosigners(&mut self, cosigners: Vec<Keypair>) {
+        self.cosigners.extend(cosigners);
+    }
+
+    /// Set `blockhash` on `transaction` and sign it, if a keypair is available.
+    /// Signer accounts without a keypair are left unsigned.
+    pub fn sign(&self, transaction: &mut Transaction, blockhash: Hash) {
+        match &self.keypair {
+            Some(keypair) => {
+                let required = transaction.message.header.num_required_signatures as usize;
+                let required = &transaction.message.account_keys[..required];
+                let signers: Vec<&Keypair> = std::iter::once(keypair)
+                    .chain(
+                        self.cosigners
+                            .iter()
+                            .filter(|c| required.contains(&c.pubkey())),
+                    )
+                    .collect();
+                transaction.partial_sign(&signers, blockhash);
+            }
+            None => transaction.message.recent_blockhash = blockhash,
+        }
+    }
+
+    /// Sign a versioned `transaction` whose message already carries its
+    /// blockhash, like `sign`
+    pub fn sign_versioned(&self, transaction: &mut VersionedTransaction) {
+        let Some(keypair) = &self.keypair else {
+            return;
+        };
+        let message = transaction.message.serialize();
+        let required = transaction.message.header().num_required_signatures as usize;
+        let keys = transaction.message.static_account_keys();
+        for signer in std::iter::once(keypair).chain(&self.cosigners) {
+            if let Some(index) = keys[..required].iter().position(|k| *k == signer.pubkey()) {
+                transaction.signatures[index] = signer.sign_message(&message);
+            }
+        }
+    }
+
+    /// Short description for the console and the report
+    pub fn describe(&self) -> String {
+        match &self.source {
+            Source::File(path) => path.display().to_string(),
+            Source::Ephemeral => "ephemeral keypair".to_string(),
+            Source::Placeholder => "placeholder (unsigned)".to_string(),
+        }
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::write_keypair_file;
+
+    #[test]
+    fn test_keypair_file_signs_transactions() {
+        let dir = std::env::temp_dir().join(format!("zklense-fee-payer-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keypair = Keypair::new();
+        let path = dir.join("payer.json");
+        write_keypair_file(&keypair, &path).unwrap();
+
+        let payer = FeePayer::resolve(Some("payer.json"), Some("other.json"), &dir, false).unwrap();
+        assert_eq!(payer.pubkey, keypair.pubkey());
+        let mut transaction = Transaction::new_with_payer(&[], Some(&payer.pubkey));
+        payer.sign(&mut transaction, Hash::new_unique());
+        assert!(transaction.verify().is_ok());
+
+        let missing = FeePayer::resolve(None, Some("missing.json"), &dir, true);
+        assert_eq!(missing.err().map(|e| e.exit_code()), Some(5));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::Context;
 use console::style;
-use dialoguer::{Input, Select, theme::ColorfulTheme};
+use dialoguer::{Input, theme::ColorfulTheme};
+use serde_json::json;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
 
+use crate::error::{self, ZkLenseError};
 use crate::ui::{self, emoji};
+use crate::ui_println;
 
 /// Template information with embedded content
 struct Template {
@@ -39,7 +42,7 @@ const TEMPLATES: &[Template] = &[
 ];
 
 /// Run the generate command
-pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()> {
+pub fn run_generate(name: Option<String>, template: Option<String>) -> error::Result<()> {
     // Header
     ui::panel_header(
         emoji::SPARKLES,
@@ -50,6 +53,9 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
     // Get project name
     let project_name = match name {
         Some(n) => n,
+        None if !ui::is_interactive() => {
+            return Err(ui::missing_input("Project name", "--name <NAME>").into());
+        }
         None => Input::<String>::with_theme(&ColorfulTheme::default())
             .with_prompt(format!("{} Project name", emoji::PACKAGE))
             .interact_text()
@@ -58,7 +64,9 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
 
     if project_name.is_empty() {
         ui::panel_error("INVALID INPUT", "Project name cannot be empty", None, None);
-        return Err(anyhow::anyhow!("Project name cannot be empty"));
+        return Err(ZkLenseError::InvalidInput(
+            "Project name cannot be empty".to_string(),
+        ));
     }
 
     // Build template selection options
@@ -88,12 +96,12 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
         None => {
             ui::blank();
             // Interactive selection
-            let selection = Select::with_theme(&ColorfulTheme::default())
-                .with_prompt(format!("{} Select a template", emoji::FILE))
-                .items(&template_options)
-                .default(0)
-                .interact()
-                .context("Failed to select template")?;
+            let selection = ui::select(
+                &format!("{} Select a template", emoji::FILE),
+                &template_options,
+                0,
+            )
+            .context("Failed to select template")?;
 
             if selection == 0 {
                 None // "None" selected
@@ -111,7 +119,12 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
     let nargo_output = Command::new("nargo")
         .args(["new", &project_name])
         .output()
-        .context("Failed to execute 'nargo new'. Is Nargo installed and in PATH?")?;
+        .map_err(|_| {
+            ui::spinner_error(&spinner, "Failed to run nargo");
+            ZkLenseError::MissingTool(
+                "Failed to execute 'nargo new'. Is Nargo installed and in PATH?".to_string(),
+            )
+        })?;
 
     if !nargo_output.status.success() {
         let stderr = String::from_utf8_lossy(&nargo_output.stderr);
@@ -125,7 +138,10 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
             Some(&["Make sure 'nargo' is installed and in PATH"]),
         );
 
-        return Err(anyhow::anyhow!("nargo new failed:\n{}\n{}", stdout, stderr));
+        return Err(ZkLenseError::CommandFailed(format!(
+            "nargo new failed:\n{}\n{}",
+            stdout, stderr
+        )));
     }
 
     ui::spinner_success(
@@ -165,27 +181,35 @@ pub fn run_generate(name: Option<String>, template: Option<String>) -> Result<()
         &format!("{} No, skip for now", emoji::CROSSMARK),
     )?;
 
+    let project_path = std::env::current_dir()?.join(&project_name);
     if should_init {
         ui::blank();
-        let project_path = std::env::current_dir()?.join(&project_name);
-        super::run_init(Some(project_path.to_string_lossy().to_string()));
+        super::init::initialize(Some(project_path.to_string_lossy().to_string()))?;
     }
 
+    ui::emit_json(&json!({
+        "command": "generate",
+        "project": project_name,
+        "path": project_path.display().to_string(),
+        "template": selected_template.map(|t| t.name),
+        "initialized": should_init,
+    }));
+
     // Next steps
     ui::section(emoji::BULB, "Next Steps");
-    println!();
-    println!(
+    ui_println!();
+    ui_println!(
         "  {} {}",
         style("1.").dim(),
         style(format!("cd {}", project_name)).cyan()
     );
-    println!(
+    ui_println!(
         "  {} {}",
         style("2.").dim(),
         style("nargo check").cyan().to_string()
             + &style("    # Verify the project compiles").dim().to_string()
     );
-    println!(
+    ui_println!(
         "  {} {}",
         style("3.").dim(),
         style("nargo prove").cyan().to_string()
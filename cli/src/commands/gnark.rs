@@ -0,0 +1,800 @@
+//! Readers for the gnark binary formats sunspot writes to `target/`
+//!
+//! Covers BN254 Groth16 verifying keys (`.vk`), proofs (`.proof`) and public
+//! witnesses (`.pw`), plus summaries of proving keys (`.pk`) and constraint
+//! systems (`.ccs`). Points may be stored compressed (`WriteTo`) or raw
+//! (`WriteRawTo`); like gnark, the encoding is detected per point from the
+//! flag bits of its first byte.
+
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::short_weierstrass::SWCurveConfig;
+use ark_ff::{AdditiveGroup, BigInteger, Field, PrimeField};
+use std::fmt;
+use std::io;
+
+/// Size of a serialized base or scalar field element
+pub const FIELD_SIZE: usize = 32;
+
+// Flag bits in the most significant byte of a serialized point
+const FLAG_MASK: u8 = 0b11 << 6;
+const FLAG_UNCOMPRESSED: u8 = 0b00 << 6;
+const FLAG_COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const FLAG_COMPRESSED_LARGEST: u8 = 0b11 << 6;
+const FLAG_COMPRESSED_INFINITY: u8 = 0b01 << 6;
+
+/// How the curve points in a file were serialized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointEncoding {
+    Compressed,
+    Raw,
+}
+
+impl fmt::Display for PointEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointEncoding::Compressed => write!(f, "compressed"),
+            PointEncoding::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+/// Pedersen commitment verifying key (BSB22 commitments)
+#[derive(Debug, Clone)]
+pub struct CommitmentKey {
+    pub g: G2Affine,
+    pub g_sigma_neg: G2Affine,
+}
+
+/// Groth16 verifying key
+#[derive(Debug, Clone)]
+pub struct VerifyingKey {
+    pub alpha_g1: G1Affine,
+    pub beta_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    pub gamma_g2: G2Affine,
+    pub delta_g1: G1Affine,
+    pub delta_g2: G2Affine,
+    /// `[Kvk]1`: one point for the constant wire, one per public input and
+    /// one per commitment
+    pub k: Vec<G1Affine>,
+    /// Public input indices (1-based) hashed into each commitment
+    pub public_and_commitment_committed: Vec<Vec<u64>>,
+    pub commitment_keys: Vec<CommitmentKey>,
+    pub encoding: PointEncoding,
+}
+
+impl VerifyingKey {
+    /// Number of public inputs the key expects in the public witness
+    pub fn nb_public_inputs(&self) -> usize {
+        self.k
+            .len()
+            .saturating_sub(1 + self.public_and_commitment_committed.len())
+    }
+}
+
+/// Groth16 proof
+#[derive(Debug, Clone)]
+pub struct Proof {
+    pub ar: G1Affine,
+    pub bs: G2Affine,
+    pub krs: G1Affine,
+    pub commitments: Vec<G1Affine>,
+    pub commitment_pok: G1Affine,
+    pub encoding: PointEncoding,
+}
+
+/// gnark witness file; `.pw` files only contain the public part
+#[derive(Debug, Clone)]
+pub struct Witness {
+    pub nb_public: u32,
+    pub nb_secret: u32,
+    pub values: Vec<Fr>,
+}
+
+/// Parse a gnark verifying key
+pub fn parse_verifying_key(bytes: &[u8]) -> io::Result<VerifyingKey> {
+    let mut reader = Reader::new(bytes);
+    let encoding = reader.peek_encoding()?;
+
+    let alpha_g1 = reader.g1().map_err(|e| context(e, "alpha"))?;
+    let beta_g1 = reader.g1().map_err(|e| context(e, "beta (G1)"))?;
+    let beta_g2 = reader.g2().map_err(|e| context(e, "beta (G2)"))?;
+    let gamma_g2 = reader.g2().map_err(|e| context(e, "gamma"))?;
+    let delta_g1 = reader.g1().map_err(|e| context(e, "delta (G1)"))?;
+    let delta_g2 = reader.g2().map_err(|e| context(e, "delta (G2)"))?;
+    let k = reader.g1_vec().map_err(|e| context(e, "K"))?;
+
+    // Keys written by gnark releases without commitment support end here
+    let mut public_and_commitment_committed = Vec::new();
+    let mut commitment_keys = Vec::new();
+    if !reader.is_empty() {
+        public_and_commitment_committed = reader
+            .u64_vec_vec()
+            .map_err(|e| context(e, "committed public inputs"))?;
+        let nb_keys = reader.u32()? as usize;
+        for _ in 0..nb_keys {
+            commitment_keys.push(CommitmentKey {
+                g: reader.g2().map_err(|e| context(e, "commitment key"))?,
+                g_sigma_neg: reader.g2().map_err(|e| context(e, "commitment key"))?,
+            });
+        }
+    }
+
+    Ok(VerifyingKey {
+        alpha_g1,
+        beta_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g1,
+        delta_g2,
+        k,
+        public_and_commitment_committed,
+        commitment_keys,
+        encoding,
+    })
+}
+
+/// Parse a gnark Groth16 proof
+pub fn parse_proof(bytes: &[u8]) -> io::Result<Proof> {
+    let mut reader = Reader::new(bytes);
+    let encoding = reader.peek_encoding()?;
+
+    let ar = reader.g1().map_err(|e| context(e, "Ar"))?;
+    let bs = reader.g2().map_err(|e| context(e, "Bs"))?;
+    let krs = reader.g1().map_err(|e| context(e, "Krs"))?;
+
+    // Proofs written by gnark releases without commitment support end here
+    let mut commitments = Vec::new();
+    let mut commitment_pok = G1Affine::identity();
+    if !reader.is_empty() {
+        commitments = reader.g1_vec().map_err(|e| context(e, "commitments"))?;
+        commitment_pok = reader
+            .g1()
+            .map_err(|e| context(e, "commitment proof of knowledge"))?;
+    }
+
+    Ok(Proof {
+        ar,
+        bs,
+        krs,
+        commitments,
+        commitment_pok,
+        encoding,
+    })
+}
+
+/// Parse a gnark witness (`nbPublic | nbSecret | len | elements`)
+pub fn parse_witness(bytes: &[u8]) -> io::Result<Witness> {
+    let mut reader = Reader::new(bytes);
+    let nb_public = reader.u32()?;
+    let nb_secret = reader.u32()?;
+    let len = reader.u32()? as usize;
+
+    if len != nb_public as usize + nb_secret as usize {
+        return Err(invalid(format!(
+            "header declares {} public and {} secret values but the vector holds {}",
+            nb_public, nb_secret, len
+        )));
+    }
+
+    let values = (0..len)
+        .map(|i| reader.fr().map_err(|e| context(e, &format!("value {}", i))))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    if !reader.is_empty() {
+        return Err(invalid(format!(
+            "{} unexpected trailing bytes",
+            reader.remaining()
+        )));
+    }
+
+    Ok(Witness {
+        nb_public,
+        nb_secret,
+        values,
+    })
+}
+
+/// One part of a serialized proving key
+#[derive(Debug, Clone)]
+pub struct KeySection {
+    pub name: &'static str,
+    /// Number of curve points or flags in the section
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// Layout of a Groth16 proving key, without decoding its points
+#[derive(Debug, Clone)]
+pub struct ProvingKeySummary {
+    pub domain_size: u64,
+    pub nb_wires: u64,
+    pub nb_infinity_a: u64,
+    pub nb_infinity_b: u64,
+    pub nb_commitment_keys: usize,
+    pub sections: Vec<KeySection>,
+    pub encoding: PointEncoding,
+}
+
+/// Summarize a gnark proving key.
+///
+/// Newer gnark-crypto releases write an extra flag after the FFT domain, so
+/// both layouts are tried.
+pub fn summarize_proving_key(bytes: &[u8]) -> io::Result<ProvingKeySummary> {
+    summarize_proving_key_with(bytes, true)
+        .or_else(|e| summarize_proving_key_with(bytes, false).map_err(|_| e))
+}
+
+fn summarize_proving_key_with(
+    bytes: &[u8],
+    domain_has_precompute_flag: bool,
+) -> io::Result<ProvingKeySummary> {
+    let mut reader = Reader::new(bytes);
+    let mut sections = Vec::new();
+    let mut section = |reader: &Reader, name: &'static str, count: usize, start: usize| {
+        sections.push(KeySection {
+            name,
+            count,
+            bytes: reader.pos - start,
+        });
+    };
+
+    // Cardinality, then CardinalityInv, Generator, GeneratorInv,
+    // FrMultiplicativeGen and FrMultiplicativeGenInv
+    let start = reader.pos;
+    let domain_size = reader.u64()?;
+    reader.take(5 * FIELD_SIZE)?;
+    if domain_has_precompute_flag {
+        reader.take(1)?;
+    }
+    section(&reader, "FFT domain", 0, start);
+
+    let encoding = reader.peek_encoding()?;
+    let start = reader.pos;
+    for name in ["alpha", "beta (G1)", "delta (G1)"] {
+        let _ = reader.g1().map_err(|e| context(e, name))?;
+    }
+    section(&reader, "[α]1, [β]1, [δ]1", 3, start);
+
+    for name in ["A", "B (G1)", "Z", "K"] {
+        let start = reader.pos;
+        let count = reader.skip_g1_vec().map_err(|e| context(e, name))?;
+        section(&reader, name, count, start);
+    }
+
+    let start = reader.pos;
+    for name in ["beta (G2)", "delta (G2)"] {
+        let _ = reader.g2().map_err(|e| context(e, name))?;
+    }
+    section(&reader, "[β]2, [δ]2", 2, start);
+
+    let start = reader.pos;
+    let count = reader.skip_g2_vec().map_err(|e| context(e, "B (G2)"))?;
+    section(&reader, "B (G2)", count, start);
+
+    let start = reader.pos;
+    let nb_wires = reader.u64()?;
+    let nb_infinity_a = reader.u64()?;
+    let nb_infinity_b = reader.u64()?;
+    let flags = reader.skip_bool_vec()? + reader.skip_bool_vec()?;
+    section(&reader, "Infinity flags", flags, start);
+
+    let start = reader.pos;
+    let nb_commitment_keys = reader.u32()? as usize;
+    let mut count = 0;
+    for _ in 0..nb_commitment_keys {
+        // Basis and BasisExpSigma
+        count += reader
+            .skip_g1_vec()
+            .map_err(|e| context(e, "commitment key"))?;
+        count += reader
+            .skip_g1_vec()
+            .map_err(|e| context(e, "commitment key"))?;
+    }
+    section(&reader, "Commitment keys", count, start);
+
+    if !reader.is_empty() {
+        return Err(invalid(format!(
+            "{} unexpected trailing bytes",
+            reader.remaining()
+        )));
+    }
+
+    Ok(ProvingKeySummary {
+        domain_size,
+        nb_wires,
+        nb_infinity_a,
+        nb_infinity_b,
+        nb_commitment_keys,
+        sections,
+        encoding,
+    })
+}
+
+/// Header fields of a gnark constraint system
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintSystemSummary {
+    pub gnark_version: Option<String>,
+    pub scalar_field: Option<String>,
+    pub system_type: Option<String>,
+    pub nb_constraints: Option<u64>,
+    pub nb_public: Option<usize>,
+    pub nb_secret: Option<usize>,
+    pub nb_internal: Option<u64>,
+    pub nb_commitments: Option<usize>,
+}
+
+impl ConstraintSystemSummary {
+    /// Public + secret + internal wires, when all three are known
+    pub fn nb_variables(&self) -> Option<u64> {
+        Some(self.nb_public? as u64 + self.nb_secret? as u64 + self.nb_internal?)
+    }
+}
+
+/// Summarize a gnark constraint system.
+///
+/// gnark stores the system's metadata as a CBOR map, preceded in newer
+/// releases by binary blocks of instructions. The map is located by its
+/// `NbInternalVariables` key and decoded on its own.
+pub fn summarize_constraint_system(bytes: &[u8]) -> io::Result<ConstraintSystemSummary> {
+    use ciborium::Value;
+
+    const KEY: &[u8] = b"\x73NbInternalVariables";
+    // How far before the key the start of the map is searched for
+    const MAX_LOOKBEHIND: usize = 4096;
+
+    let key_pos = bytes
+        .windows(KEY.len())
+        .position(|w| w == KEY)
+        .ok_or_else(|| invalid("no gnark constraint system metadata found".to_string()))?;
+
+    let map = (key_pos.saturating_sub(MAX_LOOKBEHIND)..=key_pos)
+        .rev()
+        // CBOR map headers
+        .filter(|&i| (0xa0..=0xbb).contains(&bytes[i]))
+        .find_map(|i| match ciborium::from_reader::<Value, _>(&bytes[i..]) {
+            Ok(Value::Map(entries))
+                if entries
+                    .iter()
+                    .any(|(k, _)| k.as_text() == Some("NbInternalVariables")) =>
+            {
+                Some(entries)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| invalid("could not decode constraint system metadata".to_string()))?;
+
+    let get = |name: &str| {
+        map.iter()
+            .find(|(k, _)| k.as_text() == Some(name))
+            .map(|(_, v)| v)
+    };
+    let as_u64 = |v: &Value| v.as_integer().and_then(|i| u64::try_from(i).ok());
+    let len = |v: &Value| v.as_array().map(|a| a.len());
+
+    Ok(ConstraintSystemSummary {
+        gnark_version: get("GnarkVersion")
+            .and_then(|v| v.as_text())
+            .map(String::from),
+        scalar_field: get("ScalarField")
+            .and_then(|v| v.as_text())
+            .map(String::from),
+        system_type: get("Type").and_then(as_u64).map(|t| match t {
+            1 => "R1CS (Groth16)".to_string(),
+            2 => "SparseR1CS (PLONK)".to_string(),
+            other => format!("unknown ({})", other),
+        }),
+        nb_constraints: get("NbConstraints").and_then(as_u64),
+        nb_public: get("Public").and_then(len),
+        nb_secret: get("Secret").and_then(len),
+        nb_internal: get("NbInternalVariables").and_then(as_u64),
+        nb_commitments: get("CommitmentInfo").and_then(len),
+    })
+}
+
+/// Uncompressed big-endian `X | Y` encoding of a G1 point, as gnark's `Marshal`
+pub fn g1_raw_bytes(point: &G1Affine) -> Vec<u8> {
+    let mut bytes = vec![0u8; 2 * FIELD_SIZE];
+    if !point.infinity {
+        bytes[..FIELD_SIZE].copy_from_slice(&point.x.into_bigint().to_bytes_be());
+        bytes[FIELD_SIZE..].copy_from_slice(&point.y.into_bigint().to_bytes_be());
+    }
+    bytes
+}
+
+/// Big-endian encoding of a scalar field element
+pub fn fr_bytes(value: &Fr) -> Vec<u8> {
+    value.into_bigint().to_bytes_be()
+}
+
+/// Decimal string of a scalar field element
+pub fn fr_to_decimal(value: &Fr) -> String {
+    value.into_bigint().to_string()
+}
+
+/// `0x`-prefixed hex of a base field element
+pub fn fq_to_hex(value: &Fq) -> String {
+    let hex: String = value
+        .into_bigint()
+        .to_bytes_be()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("0x{}", hex)
+}
+
+/// Affine coordinates of a G1 point as hex, `None` for the point at infinity
+pub fn g1_coordinates(point: &G1Affine) -> Option<(String, String)> {
+    (!point.infinity).then(|| (fq_to_hex(&point.x), fq_to_hex(&point.y)))
+}
+
+/// Affine coordinates of a G2 point as hex `(c0, c1)` pairs
+pub fn g2_coordinates(point: &G2Affine) -> Option<[(String, String); 2]> {
+    (!point.infinity).then(|| {
+        [
+            (fq_to_hex(&point.x.c0), fq_to_hex(&point.x.c1)),
+            (fq_to_hex(&point.y.c0), fq_to_hex(&point.y.c1)),
+        ]
+    })
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn context(e: io::Error, what: &str) -> io::Error {
+    invalid(format!("{}: {}", what, e))
+}
+
+/// gnark's "lexicographically largest" ordering used for point compression
+fn fq_is_largest(value: &Fq) -> bool {
+    value.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO
+}
+
+/// Like gnark-crypto's `E2.LexicographicallyLargest`, A0 decides unless it is zero
+fn fq2_is_largest(value: &Fq2) -> bool {
+    if value.c0 == Fq::ZERO {
+        fq_is_largest(&value.c1)
+    } else {
+        fq_is_largest(&value.c0)
+    }
+}
+
+/// Cursor over a byte slice with gnark's big-endian decoding rules
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.remaining() < n {
+            return Err(invalid(format!(
+                "unexpected end of data at byte {} (needed {} more bytes, {} left)",
+                self.pos,
+                n,
+                self.remaining()
+            )));
+        }
+        let slice = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn peek_encoding(&self) -> io::Result<PointEncoding> {
+        match self.bytes.get(self.pos) {
+            Some(b) if b & FLAG_MASK == FLAG_UNCOMPRESSED => Ok(PointEncoding::Raw),
+            Some(_) => Ok(PointEncoding::Compressed),
+            None => Err(invalid("file is empty".to_string())),
+        }
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64_vec_vec(&mut self) -> io::Result<Vec<Vec<u64>>> {
+        let outer = self.u32()? as usize;
+        (0..outer)
+            .map(|_| {
+                let inner = self.u32()? as usize;
+                (0..inner).map(|_| self.u64()).collect()
+            })
+            .collect()
+    }
+
+    /// A canonical base field element (must be below the modulus)
+    fn fq(&mut self, first_byte_mask: u8) -> io::Result<Fq> {
+        let mut bytes = [0u8; FIELD_SIZE];
+        bytes.copy_from_slice(self.take(FIELD_SIZE)?);
+        bytes[0] &= first_byte_mask;
+        let value = Fq::from_be_bytes_mod_order(&bytes);
+        if value.into_bigint().to_bytes_be() != bytes {
+            return Err(invalid(
+                "coordinate is not a canonical field element".to_string(),
+            ));
+        }
+        Ok(value)
+    }
+
+    /// A canonical scalar field element
+    fn fr(&mut self) -> io::Result<Fr> {
+        let bytes = self.take(FIELD_SIZE)?;
+        let value = Fr::from_be_bytes_mod_order(bytes);
+        if value.into_bigint().to_bytes_be() != bytes {
+            return Err(invalid(
+                "value is not a canonical field element".to_string(),
+            ));
+        }
+        Ok(value)
+    }
+
+    fn g1(&mut self) -> io::Result<G1Affine> {
+        let flag = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of data".to_string()))?
+            & FLAG_MASK;
+
+        let point = match flag {
+            FLAG_UNCOMPRESSED => {
+                let x = self.fq(!FLAG_MASK)?;
+                let y = self.fq(0xff)?;
+                if x == Fq::ZERO && y == Fq::ZERO {
+                    return Ok(G1Affine::identity());
+                }
+                G1Affine::new_unchecked(x, y)
+            }
+            FLAG_COMPRESSED_INFINITY => {
+                let x = self.fq(!FLAG_MASK)?;
+                if x != Fq::ZERO {
+                    return Err(invalid(
+                        "infinity point with non-zero coordinate".to_string(),
+                    ));
+                }
+                return Ok(G1Affine::identity());
+            }
+            FLAG_COMPRESSED_SMALLEST | FLAG_COMPRESSED_LARGEST => {
+                let x = self.fq(!FLAG_MASK)?;
+                let rhs = x.square() * x + ark_bn254::g1::Config::COEFF_B;
+                let mut y = rhs
+                    .sqrt()
+                    .ok_or_else(|| invalid("x coordinate is not on the curve".to_string()))?;
+                if fq_is_largest(&y) != (flag == FLAG_COMPRESSED_LARGEST) {
+                    y = -y;
+                }
+                G1Affine::new_unchecked(x, y)
+            }
+            _ => unreachable!("flag is masked to two bits"),
+        };
+
+        if !point.is_on_curve() {
+            return Err(invalid("G1 point is not on the curve".to_string()));
+        }
+        Ok(point)
+    }
+
+    fn g2(&mut self) -> io::Result<G2Affine> {
+        let flag = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of data".to_string()))?
+            & FLAG_MASK;
+
+        // Coordinates are stored as A1 | A0
+        let x_c1 = self.fq(!FLAG_MASK)?;
+        let x_c0 = self.fq(0xff)?;
+        let x = Fq2::new(x_c0, x_c1);
+
+        let point = match flag {
+            FLAG_UNCOMPRESSED => {
+                let y_c1 = self.fq(0xff)?;
+                let y_c0 = self.fq(0xff)?;
+                let y = Fq2::new(y_c0, y_c1);
+                if x == Fq2::ZERO && y == Fq2::ZERO {
+                    return Ok(G2Affine::identity());
+                }
+                G2Affine::new_unchecked(x, y)
+            }
+            FLAG_COMPRESSED_INFINITY => {
+                if x != Fq2::ZERO {
+                    return Err(invalid(
+                        "infinity point with non-zero coordinate".to_string(),
+                    ));
+                }
+                return Ok(G2Affine::identity());
+            }
+            FLAG_COMPRESSED_SMALLEST | FLAG_COMPRESSED_LARGEST => {
+                let rhs = x.square() * x + ark_bn254::g2::Config::COEFF_B;
+                let mut y = rhs
+                    .sqrt()
+                    .ok_or_else(|| invalid("x coordinate is not on the curve".to_string()))?;
+                if fq2_is_largest(&y) != (flag == FLAG_COMPRESSED_LARGEST) {
+                    y = -y;
+                }
+                G2Affine::new_unchecked(x, y)
+            }
+            _ => unreachable!("flag is masked to two bits"),
+        };
+
+        if !point.is_on_curve() {
+            return Err(invalid("G2 point is not on the curve".to_string()));
+        }
+        if !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(invalid(
+                "G2 point is not in the prime-order subgroup".to_string(),
+            ));
+        }
+        Ok(point)
+    }
+
+    fn g1_vec(&mut self) -> io::Result<Vec<G1Affine>> {
+        let len = self.u32()? as usize;
+        (0..len).map(|_| self.g1()).collect()
+    }
+
+    /// Skip a point without decoding it; only its flag is read to know its size
+    fn skip_point(&mut self, compressed_size: usize) -> io::Result<()> {
+        let flag = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of data".to_string()))?
+            & FLAG_MASK;
+        let size = if flag == FLAG_UNCOMPRESSED {
+            2 * compressed_size
+        } else {
+            compressed_size
+        };
+        self.take(size).map(|_| ())
+    }
+
+    fn skip_g1_vec(&mut self) -> io::Result<usize> {
+        let len = self.u32()? as usize;
+        for _ in 0..len {
+            self.skip_point(FIELD_SIZE)?;
+        }
+        Ok(len)
+    }
+
+    fn skip_g2_vec(&mut self) -> io::Result<usize> {
+        let len = self.u32()? as usize;
+        for _ in 0..len {
+            self.skip_point(2 * FIELD_SIZE)?;
+        }
+        Ok(len)
+    }
+
+    fn skip_bool_vec(&mut self) -> io::Result<usize> {
+        let len = self.u32()? as usize;
+        self.take(len)?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::G1Projective;
+    use ark_ec::{CurveGroup, PrimeGroup};
+    use ciborium::Value;
+
+    #[test]
+    fn test_summarize_proving_key_and_constraint_system() {
+        let g1 = G1Affine::from(G1Projective::generator());
+        let g2 = G2Affine::from(ark_bn254::G2Projective::generator());
+        let raw_g2 = |p: &G2Affine| -> Vec<u8> {
+            [p.x.c1, p.x.c0, p.y.c1, p.y.c0]
+                .iter()
+                .flat_map(|c| c.into_bigint().to_bytes_be())
+                .collect()
+        };
+        let g1_vec = |n: u32| -> Vec<u8> {
+            let mut bytes = n.to_be_bytes().to_vec();
+            for _ in 0..n {
+                bytes.extend(g1_raw_bytes(&(g1 * Fr::from(7u64)).into_affine()));
+            }
+            bytes
+        };
+
+        // Domain of size 4 with the trailing precompute flag
+        let mut pk = 4u64.to_be_bytes().to_vec();
+        pk.extend([0u8; 5 * FIELD_SIZE]);
+        pk.push(1);
+        for _ in 0..3 {
+            pk.extend(g1_raw_bytes(&g1));
+        }
+        for n in [3, 2, 4, 1] {
+            pk.extend(g1_vec(n));
+        }
+        pk.extend(raw_g2(&g2));
+        pk.extend(raw_g2(&g2));
+        pk.extend(1u32.to_be_bytes());
+        pk.extend(raw_g2(&g2));
+        for n in [3u64, 0, 1] {
+            pk.extend(n.to_be_bytes());
+        }
+        for _ in 0..2 {
+            pk.extend(3u32.to_be_bytes());
+            pk.extend([0, 0, 1]);
+        }
+        pk.extend(0u32.to_be_bytes());
+
+        let summary = summarize_proving_key(&pk).unwrap();
+        assert_eq!(summary.domain_size, 4);
+        assert_eq!(summary.nb_wires, 3);
+        assert_eq!(summary.encoding, PointEncoding::Raw);
+        let a = summary.sections.iter().find(|s| s.name == "A").unwrap();
+        assert_eq!((a.count, a.bytes), (3, 4 + 3 * 64));
+        let total: usize = summary.sections.iter().map(|s| s.bytes).sum();
+        assert_eq!(total, pk.len());
+
+        let text = |s: &str| Value::Text(s.to_string());
+        let metadata = Value::Map(vec![
+            (text("Type"), Value::Integer(1.into())),
+            (text("Public"), Value::Array(vec![text("1"), text("x")])),
+            (text("Secret"), Value::Array(vec![text("y")])),
+            (text("NbConstraints"), Value::Integer(12.into())),
+            (text("GnarkVersion"), text("v0.11.0")),
+            (text("NbInternalVariables"), Value::Integer(9.into())),
+        ]);
+        let mut ccs = vec![0xa5, 0x00, 0xff, 0x13];
+        ciborium::into_writer(&metadata, &mut ccs).unwrap();
+        ccs.extend([0xde, 0xad]);
+
+        let summary = summarize_constraint_system(&ccs).unwrap();
+        assert_eq!(summary.nb_constraints, Some(12));
+        assert_eq!(summary.nb_variables(), Some(12));
+        assert_eq!(summary.gnark_version.as_deref(), Some("v0.11.0"));
+        assert_eq!(summary.system_type.as_deref(), Some("R1CS (Groth16)"));
+    }
+
+    #[test]
+    fn test_decompress_g2_with_both_y_signs() {
+        let half = Fq::MODULUS_MINUS_ONE_DIV_TWO;
+        // gnark-crypto's `G2Affine.Bytes`: A1 | A0 of x, flag from y's A0 unless it is zero
+        let compress = |p: &G2Affine| -> Vec<u8> {
+            let sign = if p.y.c0 == Fq::ZERO { p.y.c1 } else { p.y.c0 };
+            let mut bytes: Vec<u8> = [p.x.c1, p.x.c0]
+                .iter()
+                .flat_map(|c| c.into_bigint().to_bytes_be())
+                .collect();
+            bytes[0] |= if sign.into_bigint() > half {
+                FLAG_COMPRESSED_LARGEST
+            } else {
+                FLAG_COMPRESSED_SMALLEST
+            };
+            bytes
+        };
+
+        let g2 = ark_bn254::G2Projective::generator();
+        let mut signs_disagree = false;
+        for n in 1..=8u64 {
+            let point = (g2 * Fr::from(n)).into_affine();
+            for p in [point, -point] {
+                signs_disagree |= (p.y.c0.into_bigint() > half) != (p.y.c1.into_bigint() > half);
+                let decoded = Reader::new(&compress(&p)).g2().unwrap();
+                assert_eq!(decoded, p);
+            }
+        }
+        // Points whose A0 and A1 disagree are the ones an A1-first check gets wrong
+        assert!(signs_disagree);
+    }
+}
@@ -0,0 +1,271 @@
+//! Simulation report history stored under `.zklense/reports/`
+//!
+//! Every simulation is written once to `.zklense/reports/<id>.json` and never
+//! modified afterwards. `.zklense/report.json` is a copy of the latest report
+//! so `zklense view` and existing tooling keep working.
+
+use console::style;
+use serde_json::json;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::init::get_zklense_dir;
+use super::report::SimulationReport;
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Directory holding one file per simulation report
+pub fn reports_dir(base_path: &Path) -> PathBuf {
+    get_zklense_dir(base_path).join("reports")
+}
+
+/// Path of the latest report
+pub fn latest_report_path(base_path: &Path) -> PathBuf {
+    get_zklense_dir(base_path).join("report.json")
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Split a Unix timestamp into UTC date and time components
+fn utc_components(timestamp: u64) -> (i64, u32, u32, u64, u64, u64) {
+    let (year, month, day) = civil_from_days((timestamp / 86_400) as i64);
+    let secs = timestamp % 86_400;
+    (year, month, day, secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+/// History ID for a report generated at `timestamp`: `YYYYMMDD-HHMMSS` (UTC)
+pub fn report_id(timestamp: u64) -> String {
+    let (y, mo, d, h, mi, s) = utc_components(timestamp);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", y, mo, d, h, mi, s)
+}
+
+/// Human-readable UTC time, e.g. `2025-01-31 14:05:09`
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (y, mo, d, h, mi, s) = utc_components(timestamp);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, mo, d, h, mi, s)
+}
+
+/// Store `report` in the history and make it the latest report.
+///
+/// The history ID is derived from `generated_at`; a numeric suffix is added
+/// if several reports are generated within the same second. Existing history
+/// files are never overwritten.
+pub fn save_report(base_path: &Path, report: &mut SimulationReport) -> io::Result<PathBuf> {
+    let dir = reports_dir(base_path);
+    fs::create_dir_all(&dir)?;
+
+    let base_id = report_id(report.generated_at);
+    let mut suffix = 0;
+    loop {
+        let id = if suffix == 0 {
+            base_id.clone()
+        } else {
+            format!("{}-{}", base_id, suffix)
+        };
+        if let Some(metadata) = report.metadata.as_mut() {
+            metadata.id = id.clone();
+        }
+        let path = dir.join(format!("{}.json", id));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                let contents = serde_json::to_string_pretty(report)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                file.write_all(contents.as_bytes())?;
+                fs::write(latest_report_path(base_path), &contents)?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// All reports in the history, oldest first. Files that cannot be parsed are
+/// skipped.
+pub fn list_reports(base_path: &Path) -> io::Result<Vec<(String, SimulationReport)>> {
+    let dir = reports_dir(base_path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut reports = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Ok(report) = serde_json::from_str::<SimulationReport>(&contents) {
+            reports.push((id.to_string(), report));
+        }
+    }
+    reports.sort_by(|(a_id, a), (b_id, b)| {
+        a.generated_at
+            .cmp(&b.generated_at)
+            .then_with(|| a_id.cmp(b_id))
+    });
+    Ok(reports)
+}
+
+/// Run the history command
+pub fn run_history(path: Option<String>, limit: Option<usize>) -> error::Result<()> {
+    let base_path = match path {
+        Some(p) => PathBuf::from(p),
+        None => std::env::current_dir()?,
+    };
+
+    ui::panel_header(
+        emoji::CHART,
+        "SIMULATION HISTORY",
+        Some("Past simulation reports, newest first"),
+    );
+
+    let reports = list_reports(&base_path).map_err(|e| {
+        ZkLenseError::Other(format!(
+            "Failed to read {}: {}",
+            reports_dir(&base_path).display(),
+            e
+        ))
+    })?;
+
+    // Deltas are computed against the previous run in chronological order,
+    // before the list is reversed and truncated for display
+    let mut rows = Vec::with_capacity(reports.len());
+    let mut previous_cu = None;
+    for (id, report) in &reports {
+        let consumed = report.compute_units.total_compute_units_consumed;
+        rows.push((id, report, previous_cu.map(|p| consumed as i64 - p as i64)));
+        previous_cu = Some(consumed);
+    }
+    rows.reverse();
+    if let Some(limit) = limit {
+        rows.truncate(limit);
+    }
+
+    ui::emit_json(&json!({
+        "command": "history",
+        "reports_dir": reports_dir(&base_path).display().to_string(),
+        "reports": rows.iter().map(|(id, report, delta)| {
+            let metadata = report.metadata.as_ref();
+            json!({
+                "id": id,
+                "generated_at": report.generated_at,
+                "circuit": metadata.and_then(|m| m.circuit.clone()),
+                "git_commit": metadata.and_then(|m| m.git_commit.clone()),
+                "network": report.environment.network,
+                "program_id": report.program_id,
+                "compute_units_consumed": report.compute_units.total_compute_units_consumed,
+                "compute_units_delta": delta,
+                "total_fee": report.cost.total_fee,
+                "message_size": report.transaction_size.message_size,
+                "status": report.transaction_status.status,
+            })
+        }).collect::<Vec<_>>(),
+    }));
+
+    if rows.is_empty() {
+        ui::info("No reports yet. Run 'zklense simulate' to record one.");
+        ui::blank();
+        return Ok(());
+    }
+
+    let mut table = ui::create_table(&[
+        "ID",
+        "Date (UTC)",
+        "Circuit",
+        "Commit",
+        "Network",
+        "CU",
+        "Δ CU",
+        "Fee",
+        "Msg Size",
+        "Status",
+    ]);
+    for &(id, report, delta) in &rows {
+        let metadata = report.metadata.as_ref();
+        let commit = match metadata.and_then(|m| m.git_commit.as_deref()) {
+            Some(commit) => {
+                let short = &commit[..commit.len().min(8)];
+                if metadata.and_then(|m| m.git_dirty) == Some(true) {
+                    format!("{}*", short)
+                } else {
+                    short.to_string()
+                }
+            }
+            None => "-".to_string(),
+        };
+        let status = match report.transaction_status.status {
+            super::report::Status::Success => style("success").green().to_string(),
+            super::report::Status::Failed => style("failed").red().to_string(),
+        };
+        let delta = match delta {
+            Some(d) if d < 0 => style(d).green().to_string(),
+            Some(d) if d > 0 => style(format!("+{}", d)).red().to_string(),
+            Some(_) => "0".to_string(),
+            None => "-".to_string(),
+        };
+        table.add_row(vec![
+            id.to_string(),
+            format_timestamp(report.generated_at),
+            metadata
+                .and_then(|m| m.circuit.clone())
+                .unwrap_or_else(|| "-".to_string()),
+            commit,
+            report.environment.network.clone(),
+            report
+                .compute_units
+                .total_compute_units_consumed
+                .to_string(),
+            delta,
+            format!("{} lamports", report.cost.total_fee),
+            format!("{} bytes", report.transaction_size.message_size),
+            status,
+        ]);
+    }
+    ui_println!("{table}");
+    ui::blank();
+    ui::info(&format!(
+        "{} of {} reports in {}",
+        rows.len(),
+        reports.len(),
+        reports_dir(&base_path).display()
+    ));
+    ui::blank();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_ids_are_utc_timestamps() {
+        assert_eq!(report_id(0), "19700101-000000");
+        assert_eq!(report_id(951_782_400), "20000229-000000");
+        assert_eq!(report_id(1_738_332_309), "20250131-140509");
+        assert_eq!(format_timestamp(1_738_332_309), "2025-01-31 14:05:09");
+    }
+}
@@ -1,17 +1,22 @@
 use console::style;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use super::budget::Budgets;
+use crate::error::{self, ZkLenseError};
 use crate::ui::{self, emoji};
 
 const ZKLENSE_DIR: &str = ".zklense";
 const CONFIG_FILE: &str = "config.toml";
 pub const DEFAULT_WEB_APP_URL: &str = "https://zklense.tech/report";
+/// Setting holding the fee payer keypair file
+pub const KEYPAIR_PATH_KEY: &str = "keypair_path";
 
 /// Solana network environment
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -21,6 +26,8 @@ pub enum SolanaNetwork {
     Devnet,
     Testnet,
     Mainnet,
+    /// Local `solana-test-validator`
+    Localnet,
 }
 
 impl SolanaNetwork {
@@ -30,6 +37,7 @@ impl SolanaNetwork {
             SolanaNetwork::Devnet,
             SolanaNetwork::Testnet,
             SolanaNetwork::Mainnet,
+            SolanaNetwork::Localnet,
         ]
     }
 
@@ -39,6 +47,7 @@ impl SolanaNetwork {
             SolanaNetwork::Devnet => "https://api.devnet.solana.com",
             SolanaNetwork::Testnet => "https://api.testnet.solana.com",
             SolanaNetwork::Mainnet => "https://api.mainnet-beta.solana.com",
+            SolanaNetwork::Localnet => "http://127.0.0.1:8899",
         }
     }
 
@@ -48,6 +57,7 @@ impl SolanaNetwork {
             SolanaNetwork::Devnet => "devnet",
             SolanaNetwork::Testnet => "testnet",
             SolanaNetwork::Mainnet => "mainnet",
+            SolanaNetwork::Localnet => "localnet",
         }
     }
 }
@@ -66,8 +76,9 @@ impl FromStr for SolanaNetwork {
             "devnet" => Ok(SolanaNetwork::Devnet),
             "testnet" => Ok(SolanaNetwork::Testnet),
             "mainnet" | "mainnet-beta" => Ok(SolanaNetwork::Mainnet),
+            "localnet" | "localhost" => Ok(SolanaNetwork::Localnet),
             _ => Err(format!(
-                "Invalid network '{}'. Valid options: devnet, testnet, mainnet",
+                "Invalid network '{}'. Valid options: devnet, testnet, mainnet, localnet",
                 s
             )),
         }
@@ -79,6 +90,9 @@ impl FromStr for SolanaNetwork {
 pub struct ZkLenseConfig {
     #[serde(default)]
     pub settings: HashMap<String, String>,
+    /// Limits enforced by `zklense simulate`
+    #[serde(default, skip_serializing_if = "Budgets::is_empty")]
+    pub budgets: Budgets,
 }
 
 impl ZkLenseConfig {
@@ -97,7 +111,10 @@ impl ZkLenseConfig {
             "solana_rpc_url".to_string(),
             default_network.rpc_url().to_string(),
         );
-        Self { settings }
+        Self {
+            settings,
+            budgets: Budgets::default(),
+        }
     }
 
     /// Get the current Solana network
@@ -147,7 +164,9 @@ impl ZkLenseConfig {
     /// Load configuration from file
     pub fn load(path: &Path) -> io::Result<Self> {
         let contents = fs::read_to_string(path)?;
-        toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        toml::from_str(&contents).map_err(|e| {
+            ZkLenseError::Config(format!("Invalid config file {}: {}", path.display(), e)).into()
+        })
     }
 }
 
@@ -177,10 +196,10 @@ pub fn config_exists(base_path: &Path) -> bool {
 pub fn read_config_value(base_path: &Path, key: &str) -> io::Result<Option<String>> {
     let config_path = get_config_path(base_path);
     if !config_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Config file not found. Run 'zklense init' first.",
-        ));
+        return Err(ZkLenseError::Config(
+            "Config file not found. Run 'zklense init' first.".to_string(),
+        )
+        .into());
     }
     let config = ZkLenseConfig::load(&config_path)?;
     Ok(config.get(key).cloned())
@@ -190,16 +209,15 @@ pub fn read_config_value(base_path: &Path, key: &str) -> io::Result<Option<Strin
 pub fn read_config(base_path: &Path) -> io::Result<ZkLenseConfig> {
     let config_path = get_config_path(base_path);
     if !config_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Config file not found. Run 'zklense init' first.",
-        ));
+        return Err(ZkLenseError::Config(
+            "Config file not found. Run 'zklense init' first.".to_string(),
+        )
+        .into());
     }
     ZkLenseConfig::load(&config_path)
 }
 
 /// Write a value to the config file
-#[allow(dead_code)]
 pub fn write_config_value(base_path: &Path, key: &str, value: &str) -> io::Result<()> {
     let config_path = get_config_path(base_path);
     let mut config = if config_path.exists() {
@@ -250,7 +268,7 @@ pub fn reset_solana_rpc_url(base_path: &Path) -> io::Result<String> {
 }
 
 /// Generate a simple timestamp string (without external chrono dependency)
-fn chrono_timestamp() -> String {
+pub fn chrono_timestamp() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let duration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -297,7 +315,7 @@ pub fn ensure_initialized(path: Option<&str>) -> io::Result<bool> {
     )?;
 
     if should_init {
-        run_init(Some(base_path.to_string_lossy().to_string()));
+        initialize(Some(base_path.to_string_lossy().to_string()))?;
         // Check if initialization succeeded
         if is_initialized(&base_path) {
             Ok(true)
@@ -322,28 +340,44 @@ fn resolve_path(path_str: &str) -> io::Result<PathBuf> {
 }
 
 /// Run the init command
-pub fn run_init(path: Option<String>) {
+pub fn run_init(path: Option<String>) -> error::Result<()> {
+    let outcome = initialize(path)?;
+
+    let config = ZkLenseConfig::load(&outcome.config_path)?;
+    let settings: BTreeMap<&String, &String> = config.settings.iter().collect();
+    ui::emit_json(&json!({
+        "command": "init",
+        "path": outcome.base_path.display().to_string(),
+        "config_path": outcome.config_path.display().to_string(),
+        "created": outcome.created,
+        "config": settings,
+    }));
+
+    Ok(())
+}
+
+/// Where zklense was initialized and whether anything had to be created
+pub struct InitOutcome {
+    pub base_path: PathBuf,
+    pub config_path: PathBuf,
+    pub created: bool,
+}
+
+/// Create `.zklense/` and its config if missing, without emitting a JSON document
+pub fn initialize(path: Option<String>) -> error::Result<InitOutcome> {
     let base_path = match path {
-        Some(p) => match resolve_path(&p) {
-            Ok(resolved) => resolved,
-            Err(e) => {
-                ui::error(&format!("Error resolving path: {}", e));
-                return;
-            }
-        },
-        None => match std::env::current_dir() {
-            Ok(cwd) => cwd,
-            Err(e) => {
-                ui::error(&format!("Error getting current directory: {}", e));
-                return;
-            }
-        },
+        Some(p) => resolve_path(&p)
+            .map_err(|e| ZkLenseError::Other(format!("Error resolving path: {}", e)))?,
+        None => std::env::current_dir()
+            .map_err(|e| ZkLenseError::Other(format!("Error getting current directory: {}", e)))?,
     };
 
     // Check if base path exists
     if !base_path.exists() {
-        ui::error(&format!("Path does not exist: {}", base_path.display()));
-        return;
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Path does not exist: {}",
+            base_path.display()
+        )));
     }
 
     let zklense_dir = get_zklense_dir(&base_path);
@@ -368,9 +402,17 @@ pub fn run_init(path: Option<String>) {
                     ));
                     print_config_summary(&config);
                     print_prover_toml_note(&base_path);
+                    return Ok(InitOutcome {
+                        base_path,
+                        config_path,
+                        created: true,
+                    });
                 }
                 Err(e) => {
-                    ui::error(&format!("Failed to recreate config file: {}", e));
+                    return Err(ZkLenseError::Config(format!(
+                        "Failed to recreate config file: {}",
+                        e
+                    )));
                 }
             }
         } else {
@@ -379,7 +421,11 @@ pub fn run_init(path: Option<String>) {
                 style(config_path.display()).dim()
             ));
         }
-        return;
+        return Ok(InitOutcome {
+            base_path,
+            config_path,
+            created: false,
+        });
     }
 
     // Create .zklense directory
@@ -393,7 +439,7 @@ pub fn run_init(path: Option<String>) {
         }
         Err(e) => {
             ui::spinner_error(&spinner, &format!("Failed to create directory: {}", e));
-            return;
+            return Err(e.into());
         }
     }
 
@@ -429,8 +475,18 @@ pub fn run_init(path: Option<String>) {
             ui::spinner_error(&spinner, &format!("Failed to create config file: {}", e));
             // Clean up the created directory
             let _ = fs::remove_dir(&zklense_dir);
+            return Err(ZkLenseError::Config(format!(
+                "Failed to create config file: {}",
+                e
+            )));
         }
     }
+
+    Ok(InitOutcome {
+        base_path,
+        config_path,
+        created: true,
+    })
 }
 
 /// Print a note reminding users to update Prover.toml before running zklense run
@@ -451,9 +507,7 @@ fn print_prover_toml_note(base_path: &Path) {
     } else {
         ui::panel_info(
             "IMPORTANT",
-            &format!(
-                "Before running 'zklense run', create and configure Prover.toml with the correct input values for your circuit.\n\nThe Prover.toml file should contain all the input parameters required by your Noir circuit's main function.\n\nExample for age_verifier template:\n  year_of_birth = \"1990\"\n  current_year = \"2024\"\n  age_threshold = \"21\""
-            ),
+            "Before running 'zklense run', create and configure Prover.toml with the correct input values for your circuit.\n\nThe Prover.toml file should contain all the input parameters required by your Noir circuit's main function.\n\nExample for age_verifier template:\n  year_of_birth = \"1990\"\n  current_year = \"2024\"\n  age_threshold = \"21\"",
         );
     }
 
@@ -0,0 +1,471 @@
+//! Decode and display the artifacts sunspot writes to `target/`
+
+use ark_bn254::{G1Affine, G2Affine};
+use console::style;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::gnark::{self, Proof, VerifyingKey, Witness};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Artifact extensions in pipeline order
+const ARTIFACT_EXTENSIONS: &[&str] = &["ccs", "pk", "vk", "proof", "pw"];
+
+/// A decoded artifact, kept around for cross-checks between files
+enum Decoded {
+    VerifyingKey(Box<VerifyingKey>),
+    Proof(Box<Proof>),
+    Witness(Witness),
+    Other,
+}
+
+/// Shorten a hex string for table display
+fn short_hex(hex: &str) -> String {
+    if hex.len() <= 20 {
+        hex.to_string()
+    } else {
+        format!("{}…{}", &hex[..10], &hex[hex.len() - 8..])
+    }
+}
+
+fn g1_json(point: &G1Affine) -> Value {
+    match gnark::g1_coordinates(point) {
+        Some((x, y)) => json!({ "x": x, "y": y }),
+        None => json!("infinity"),
+    }
+}
+
+fn g2_json(point: &G2Affine) -> Value {
+    match gnark::g2_coordinates(point) {
+        Some([(x0, x1), (y0, y1)]) => json!({ "x": [x0, x1], "y": [y0, y1] }),
+        None => json!("infinity"),
+    }
+}
+
+fn g1_row(name: &str, point: &G1Affine) -> Vec<String> {
+    match gnark::g1_coordinates(point) {
+        Some((x, y)) => vec![
+            name.to_string(),
+            "G1".to_string(),
+            short_hex(&x),
+            short_hex(&y),
+        ],
+        None => vec![
+            name.to_string(),
+            "G1".to_string(),
+            "∞".to_string(),
+            "∞".to_string(),
+        ],
+    }
+}
+
+fn g2_row(name: &str, point: &G2Affine) -> Vec<String> {
+    match gnark::g2_coordinates(point) {
+        Some([(x0, x1), (y0, y1)]) => vec![
+            name.to_string(),
+            "G2".to_string(),
+            format!("{}\n{}", short_hex(&x0), short_hex(&x1)),
+            format!("{}\n{}", short_hex(&y0), short_hex(&y1)),
+        ],
+        None => vec![
+            name.to_string(),
+            "G2".to_string(),
+            "∞".to_string(),
+            "∞".to_string(),
+        ],
+    }
+}
+
+fn print_points(rows: Vec<Vec<String>>) {
+    let mut table = ui::create_table(&["Point", "Group", "x", "y"]);
+    for row in rows {
+        table.add_row(row);
+    }
+    ui_println!("{table}");
+}
+
+fn inspect_proof(bytes: &[u8]) -> std::io::Result<(Value, Decoded)> {
+    let proof = gnark::parse_proof(bytes)?;
+
+    ui::print_tree(&[
+        ("Encoding", &proof.encoding.to_string()),
+        ("Commitments", &proof.commitments.len().to_string()),
+    ]);
+    ui::blank();
+
+    let mut rows = vec![
+        g1_row("A (Ar)", &proof.ar),
+        g2_row("B (Bs)", &proof.bs),
+        g1_row("C (Krs)", &proof.krs),
+    ];
+    for (i, commitment) in proof.commitments.iter().enumerate() {
+        rows.push(g1_row(&format!("Commitment {}", i), commitment));
+    }
+    if !proof.commitments.is_empty() {
+        rows.push(g1_row("Commitment PoK", &proof.commitment_pok));
+    }
+    print_points(rows);
+
+    let details = json!({
+        "kind": "proof",
+        "encoding": proof.encoding.to_string(),
+        "a": g1_json(&proof.ar),
+        "b": g2_json(&proof.bs),
+        "c": g1_json(&proof.krs),
+        "commitments": proof.commitments.iter().map(g1_json).collect::<Vec<_>>(),
+        "commitment_pok": g1_json(&proof.commitment_pok),
+    });
+    Ok((details, Decoded::Proof(Box::new(proof))))
+}
+
+fn inspect_witness(bytes: &[u8]) -> std::io::Result<(Value, Decoded)> {
+    let witness = gnark::parse_witness(bytes)?;
+
+    ui::print_tree(&[
+        ("Public values", &witness.nb_public.to_string()),
+        ("Secret values", &witness.nb_secret.to_string()),
+    ]);
+    ui::blank();
+
+    let values: Vec<String> = witness.values.iter().map(gnark::fr_to_decimal).collect();
+    if !values.is_empty() {
+        let mut table = ui::create_table(&["#", "Visibility", "Value"]);
+        for (i, value) in values.iter().enumerate() {
+            let visibility = if i < witness.nb_public as usize {
+                "public"
+            } else {
+                "secret"
+            };
+            table.add_row(vec![i.to_string(), visibility.to_string(), value.clone()]);
+        }
+        ui_println!("{table}");
+    }
+
+    let details = json!({
+        "kind": "public_witness",
+        "nb_public": witness.nb_public,
+        "nb_secret": witness.nb_secret,
+        "values": values,
+    });
+    Ok((details, Decoded::Witness(witness)))
+}
+
+fn inspect_verifying_key(bytes: &[u8]) -> std::io::Result<(Value, Decoded)> {
+    let vk = gnark::parse_verifying_key(bytes)?;
+
+    let committed: Vec<String> = vk
+        .public_and_commitment_committed
+        .iter()
+        .map(|indices| format!("{:?}", indices))
+        .collect();
+    ui::print_tree(&[
+        ("Encoding", &vk.encoding.to_string()),
+        ("IC length (K)", &vk.k.len().to_string()),
+        ("Public inputs", &vk.nb_public_inputs().to_string()),
+        (
+            "Commitments",
+            &vk.public_and_commitment_committed.len().to_string(),
+        ),
+        ("Committed public inputs", &committed.join(", ")),
+    ]);
+    ui::blank();
+
+    let mut rows = vec![
+        g1_row("α", &vk.alpha_g1),
+        g1_row("β", &vk.beta_g1),
+        g2_row("β", &vk.beta_g2),
+        g2_row("γ", &vk.gamma_g2),
+        g1_row("δ", &vk.delta_g1),
+        g2_row("δ", &vk.delta_g2),
+    ];
+    for (i, k) in vk.k.iter().enumerate() {
+        rows.push(g1_row(&format!("K[{}]", i), k));
+    }
+    print_points(rows);
+
+    let details = json!({
+        "kind": "verifying_key",
+        "encoding": vk.encoding.to_string(),
+        "ic_length": vk.k.len(),
+        "nb_public_inputs": vk.nb_public_inputs(),
+        "alpha_g1": g1_json(&vk.alpha_g1),
+        "beta_g1": g1_json(&vk.beta_g1),
+        "beta_g2": g2_json(&vk.beta_g2),
+        "gamma_g2": g2_json(&vk.gamma_g2),
+        "delta_g1": g1_json(&vk.delta_g1),
+        "delta_g2": g2_json(&vk.delta_g2),
+        "k": vk.k.iter().map(g1_json).collect::<Vec<_>>(),
+        "committed_public_inputs": vk.public_and_commitment_committed,
+        "commitment_keys": vk.commitment_keys.len(),
+    });
+    Ok((details, Decoded::VerifyingKey(Box::new(vk))))
+}
+
+fn inspect_constraint_system(bytes: &[u8]) -> std::io::Result<(Value, Decoded)> {
+    let ccs = gnark::summarize_constraint_system(bytes)?;
+
+    let show = |v: Option<String>| v.unwrap_or_else(|| "unknown".to_string());
+    ui::print_tree(&[
+        ("gnark version", &show(ccs.gnark_version.clone())),
+        ("Type", &show(ccs.system_type.clone())),
+        (
+            "Constraints",
+            &show(ccs.nb_constraints.map(|n| n.to_string())),
+        ),
+        (
+            "Variables",
+            &show(ccs.nb_variables().map(|n| n.to_string())),
+        ),
+        ("  Public", &show(ccs.nb_public.map(|n| n.to_string()))),
+        ("  Secret", &show(ccs.nb_secret.map(|n| n.to_string()))),
+        ("  Internal", &show(ccs.nb_internal.map(|n| n.to_string()))),
+        (
+            "Commitments",
+            &show(ccs.nb_commitments.map(|n| n.to_string())),
+        ),
+    ]);
+
+    let details = json!({
+        "kind": "constraint_system",
+        "gnark_version": ccs.gnark_version,
+        "scalar_field": ccs.scalar_field,
+        "type": ccs.system_type,
+        "nb_constraints": ccs.nb_constraints,
+        "nb_variables": ccs.nb_variables(),
+        "nb_public": ccs.nb_public,
+        "nb_secret": ccs.nb_secret,
+        "nb_internal": ccs.nb_internal,
+        "nb_commitments": ccs.nb_commitments,
+    });
+    Ok((details, Decoded::Other))
+}
+
+fn inspect_proving_key(bytes: &[u8]) -> std::io::Result<(Value, Decoded)> {
+    let pk = gnark::summarize_proving_key(bytes)?;
+
+    ui::print_tree(&[
+        ("Encoding", &pk.encoding.to_string()),
+        ("Domain size", &pk.domain_size.to_string()),
+        ("Wires", &pk.nb_wires.to_string()),
+        ("Infinity points in A", &pk.nb_infinity_a.to_string()),
+        ("Infinity points in B", &pk.nb_infinity_b.to_string()),
+        ("Commitment keys", &pk.nb_commitment_keys.to_string()),
+    ]);
+    ui::blank();
+
+    let total = bytes.len().max(1) as f64;
+    let mut table = ui::create_table(&["Section", "Entries", "Bytes", "Share"]);
+    for section in &pk.sections {
+        table.add_row(vec![
+            section.name.to_string(),
+            section.count.to_string(),
+            section.bytes.to_string(),
+            format!("{:.1}%", section.bytes as f64 / total * 100.0),
+        ]);
+    }
+    ui_println!("{table}");
+
+    let details = json!({
+        "kind": "proving_key",
+        "encoding": pk.encoding.to_string(),
+        "domain_size": pk.domain_size,
+        "nb_wires": pk.nb_wires,
+        "nb_infinity_a": pk.nb_infinity_a,
+        "nb_infinity_b": pk.nb_infinity_b,
+        "commitment_keys": pk.nb_commitment_keys,
+        "sections": pk.sections.iter().map(|s| json!({
+            "name": s.name,
+            "count": s.count,
+            "bytes": s.bytes,
+        })).collect::<Vec<_>>(),
+    });
+    Ok((details, Decoded::Other))
+}
+
+/// Compare what the verifying key expects with the proof and public witness
+fn consistency_checks(decoded: &[Decoded]) -> Vec<(String, bool)> {
+    let vk = decoded.iter().find_map(|d| match d {
+        Decoded::VerifyingKey(vk) => Some(vk),
+        _ => None,
+    });
+    let proof = decoded.iter().find_map(|d| match d {
+        Decoded::Proof(p) => Some(p),
+        _ => None,
+    });
+    let witness = decoded.iter().find_map(|d| match d {
+        Decoded::Witness(w) => Some(w),
+        _ => None,
+    });
+
+    let mut checks = Vec::new();
+    let Some(vk) = vk else {
+        return checks;
+    };
+    if let Some(witness) = witness {
+        checks.push((
+            format!(
+                "Public inputs: verifying key expects {}, public witness has {}",
+                vk.nb_public_inputs(),
+                witness.nb_public
+            ),
+            vk.nb_public_inputs() == witness.nb_public as usize,
+        ));
+    }
+    if let Some(proof) = proof {
+        let expected = vk.public_and_commitment_committed.len();
+        checks.push((
+            format!(
+                "Commitments: verifying key expects {}, proof has {}",
+                expected,
+                proof.commitments.len()
+            ),
+            expected == proof.commitments.len(),
+        ));
+    }
+    checks
+}
+
+/// Expand the command-line arguments into the artifact files to inspect.
+/// No argument or a directory means every artifact of that project's circuit.
+fn collect_artifacts(paths: Vec<String>) -> error::Result<Vec<PathBuf>> {
+    let current_dir = std::env::current_dir()?;
+    let paths = if paths.is_empty() {
+        vec![current_dir.clone()]
+    } else {
+        paths.iter().map(|p| current_dir.join(p)).collect()
+    };
+
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let circuit = super::run::read_circuit_name(&path)?;
+            let found: Vec<PathBuf> = ARTIFACT_EXTENSIONS
+                .iter()
+                .map(|ext| path.join(super::run::target_file(&circuit, ext)))
+                .filter(|p| p.is_file())
+                .collect();
+            if found.is_empty() {
+                return Err(ZkLenseError::MissingArtifact(format!(
+                    "No artifacts found in {}\nRun 'zklense run' first to generate them.",
+                    path.join("target").display()
+                )));
+            }
+            files.extend(found);
+        } else if path.is_file() {
+            files.push(path);
+        } else {
+            return Err(ZkLenseError::MissingArtifact(format!(
+                "File not found: {}",
+                path.display()
+            )));
+        }
+    }
+    Ok(files)
+}
+
+/// Run the inspect command
+pub fn run_inspect(paths: Vec<String>) -> error::Result<()> {
+    ui::panel_header(
+        emoji::SEARCH,
+        "INSPECT ARTIFACTS",
+        Some("Decode proofs, witnesses, keys and constraint systems"),
+    );
+
+    let files = collect_artifacts(paths)?;
+
+    let mut artifacts = Vec::new();
+    let mut decoded = Vec::new();
+    let mut failures = 0;
+    for path in &files {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let inspect: fn(&[u8]) -> std::io::Result<(Value, Decoded)> = match extension {
+            "proof" => inspect_proof,
+            "pw" => inspect_witness,
+            "vk" => inspect_verifying_key,
+            "ccs" => inspect_constraint_system,
+            "pk" => inspect_proving_key,
+            _ => {
+                return Err(ZkLenseError::InvalidInput(format!(
+                    "Don't know how to inspect {} (expected .proof, .pw, .vk, .ccs or .pk)",
+                    path.display()
+                )));
+            }
+        };
+
+        let bytes = fs::read(path)?;
+        ui::section(
+            emoji::FILE,
+            &format!("{} ({} bytes)", display_name(path), bytes.len()),
+        );
+
+        let mut entry = json!({
+            "path": path.display().to_string(),
+            "size_bytes": bytes.len(),
+        });
+        match inspect(&bytes) {
+            Ok((details, d)) => {
+                merge(&mut entry, details);
+                decoded.push(d);
+            }
+            Err(e) => {
+                failures += 1;
+                ui::warn(&format!("Could not decode {}: {}", display_name(path), e));
+                entry["error"] = json!(e.to_string());
+            }
+        }
+        artifacts.push(entry);
+        ui::blank();
+    }
+
+    let checks = consistency_checks(&decoded);
+    if !checks.is_empty() {
+        ui::section(emoji::CHECKMARK, "Consistency");
+        let items: Vec<(&str, &str, bool)> = checks
+            .iter()
+            .map(|(label, ok)| (label.as_str(), if *ok { "ok" } else { "mismatch" }, *ok))
+            .collect();
+        ui::print_tree_with_status(&items);
+        ui::blank();
+    }
+
+    ui::emit_json(&json!({
+        "command": "inspect",
+        "artifacts": artifacts,
+        "checks": checks.iter().map(|(label, ok)| json!({
+            "check": label,
+            "ok": ok,
+        })).collect::<Vec<_>>(),
+    }));
+
+    if failures > 0 {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "{} of {} artifacts could not be decoded",
+            failures,
+            files.len()
+        )));
+    }
+    if checks.iter().any(|(_, ok)| !ok) {
+        ui::warn(&format!(
+            "Artifacts are inconsistent; regenerate them with {}",
+            style("zklense run --force").cyan()
+        ));
+    }
+    Ok(())
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Copy the fields of `extra` into `target` (both JSON objects)
+fn merge(target: &mut Value, extra: Value) {
+    if let (Some(target), Value::Object(extra)) = (target.as_object_mut(), extra) {
+        target.extend(extra);
+    }
+}
@@ -0,0 +1,587 @@
+//! Custom verify instruction layouts from `.zklense/instruction.toml`
+//!
+//! Without a spec, the verify instruction is the bare sunspot verifier call:
+//! no accounts and `proof || public_witness` as data. Programs that wrap the
+//! verifier usually take accounts and a discriminator, which the spec
+//! describes so simulations exercise the production instruction:
+//!
+//! ```toml
+//! # Hex bytes, or "anchor:<name>" for an Anchor instruction discriminator
+//! discriminator = "anchor:verify"
+//!
+//! [[accounts]]
+//! name = "authority"
+//! pubkey = "payer"          # "payer", "program", "system" or an address
+//! signer = true
+//! writable = true
+//!
+//! [[accounts]]
+//! name = "nullifier"
+//! seeds = ["nullifier", "0x01"]   # PDA of the program; "0x" seeds are hex
+//! writable = true
+//!
+//! [[data]]
+//! type = "proof"
+//! prefix = "u32"            # Borsh-style length prefix: u8, u16, u32 or u64
+//!
+//! [[data]]
+//! type = "witness"
+//!
+//! [[data]]
+//! type = "u64"
+//! value = 42
+//! ```
+//!
+//! Fields are little-endian. Without `[[data]]`, the data is the proof
+//! followed by the public witness, after the discriminator. Signer accounts
+//! other than the fee payer sign with their `keypair` file, if given.
+
+use console::style;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer, read_keypair_file},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::init::get_zklense_dir;
+use super::report::{InstructionAccount, InstructionField, InstructionLayout};
+use super::simulate::{ProofResult, create_instruction_data, format_number};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+
+const SPEC_FILE: &str = "instruction.toml";
+
+/// Path of the instruction spec in a project
+pub fn spec_path(base_path: &Path) -> PathBuf {
+    get_zklense_dir(base_path).join(SPEC_FILE)
+}
+
+/// Declarative layout of the verify instruction
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstructionSpec {
+    #[serde(default)]
+    pub discriminator: Option<String>,
+    #[serde(default)]
+    pub accounts: Vec<AccountSpec>,
+    #[serde(default)]
+    pub data: Vec<DataField>,
+    /// Buffer account the proof is written to with `simulate --buffered`
+    #[serde(default)]
+    pub buffer: Option<BufferSpec>,
+}
+
+/// Buffer account written across several transactions before verification.
+/// Each write instruction takes the buffer (writable) and the fee payer
+/// (signer) as accounts and `discriminator || offset: u32 || len: u32 ||
+/// bytes` as data.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferSpec {
+    /// Program owning the buffer and handling writes (default: the verifier)
+    #[serde(default)]
+    pub program: Option<String>,
+    /// SBF program to load for `program` in offline simulations
+    #[serde(default)]
+    pub so: Option<String>,
+    /// Discriminator of the write instruction, in the format of the
+    /// instruction's `discriminator`
+    #[serde(default)]
+    pub write_discriminator: Option<String>,
+    /// Bytes reserved before the proof, e.g. for the buffer authority
+    #[serde(default)]
+    pub header_size: usize,
+    /// Bytes written per transaction (default: as many as fit)
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+}
+
+/// Account meta of the verify instruction. The address comes from exactly
+/// one of `pubkey`, `seeds` or `keypair`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub pubkey: Option<String>,
+    /// Seeds of a PDA of the verifier program
+    #[serde(default)]
+    pub seeds: Option<Vec<String>>,
+    /// Keypair file of the account, which then signs the transaction
+    #[serde(default)]
+    pub keypair: Option<String>,
+    #[serde(default)]
+    pub signer: bool,
+    #[serde(default)]
+    pub writable: bool,
+}
+
+/// Field of the instruction data
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DataField {
+    Proof {
+        #[serde(default)]
+        prefix: Option<LengthPrefix>,
+    },
+    Witness {
+        #[serde(default)]
+        prefix: Option<LengthPrefix>,
+    },
+    /// Hex-encoded bytes
+    Bytes {
+        value: String,
+        #[serde(default)]
+        prefix: Option<LengthPrefix>,
+    },
+    U8 {
+        value: u8,
+    },
+    U16 {
+        value: u16,
+    },
+    U32 {
+        value: u32,
+    },
+    U64 {
+        value: u64,
+    },
+    I64 {
+        value: i64,
+    },
+    Bool {
+        value: bool,
+    },
+    /// 32-byte address; accepts the same names as account `pubkey`
+    Pubkey {
+        value: String,
+    },
+}
+
+/// Integer length prepended to variable-size fields
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthPrefix {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl LengthPrefix {
+    fn encode(self, len: usize) -> error::Result<Vec<u8>> {
+        let too_long = || {
+            ZkLenseError::Config(format!(
+                "{} bytes do not fit a {:?} length prefix",
+                format_number(len as u64),
+                self
+            ))
+        };
+        Ok(match self {
+            LengthPrefix::U8 => vec![u8::try_from(len).map_err(|_| too_long())?],
+            LengthPrefix::U16 => u16::try_from(len)
+                .map_err(|_| too_long())?
+                .to_le_bytes()
+                .to_vec(),
+            LengthPrefix::U32 => u32::try_from(len)
+                .map_err(|_| too_long())?
+                .to_le_bytes()
+                .to_vec(),
+            LengthPrefix::U64 => (len as u64).to_le_bytes().to_vec(),
+        })
+    }
+}
+
+/// The verify instruction and the keypairs of its extra signer accounts
+pub struct VerifyInstruction {
+    pub instruction: Instruction,
+    pub signers: Vec<Keypair>,
+    /// Layout for the report, when built from a spec
+    pub layout: Option<InstructionLayout>,
+}
+
+impl InstructionSpec {
+    /// Load the project's spec, `None` when there is none
+    pub fn load(base_path: &Path) -> error::Result<Option<InstructionSpec>> {
+        let path = spec_path(base_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        toml::from_str(&contents).map(Some).map_err(|e| {
+            ZkLenseError::Config(format!(
+                "Invalid instruction spec {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Build the verify instruction for `proof_result`. With a `buffer`, the
+    /// proof is read from that account: accounts can refer to it as
+    /// `buffer`, and the data holds only the discriminator unless `[[data]]`
+    /// says otherwise.
+    pub fn build(
+        &self,
+        base_path: &Path,
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        buffer: Option<&Pubkey>,
+        proof_result: &ProofResult,
+    ) -> error::Result<VerifyInstruction> {
+        let addresses = Addresses {
+            program_id,
+            payer,
+            buffer,
+        };
+        let mut metas = Vec::new();
+        let mut accounts = Vec::new();
+        let mut signers = Vec::new();
+        for account in &self.accounts {
+            let (address, keypair) = account.resolve(base_path, &addresses)?;
+            if let Some(keypair) = keypair {
+                signers.push(keypair);
+            }
+            metas.push(if account.writable {
+                AccountMeta::new(address, account.signer)
+            } else {
+                AccountMeta::new_readonly(address, account.signer)
+            });
+            accounts.push(InstructionAccount {
+                name: account.name.clone(),
+                address: address.to_string(),
+                signer: account.signer,
+                writable: account.writable,
+            });
+        }
+
+        let discriminator = parse_discriminator(self.discriminator.as_deref())?;
+        let mut data = discriminator.clone();
+        let mut fields = Vec::new();
+        if !discriminator.is_empty() {
+            fields.push(InstructionField {
+                field: "discriminator".to_string(),
+                offset: 0,
+                size: discriminator.len(),
+            });
+        }
+        let default_layout = [
+            DataField::Proof { prefix: None },
+            DataField::Witness { prefix: None },
+        ];
+        let layout = if self.data.is_empty() {
+            &default_layout[..buffer.map_or(2, |_| 0)]
+        } else {
+            &self.data
+        };
+        for field in layout {
+            let offset = data.len();
+            data.extend(field.encode(&addresses, proof_result)?);
+            fields.push(InstructionField {
+                field: field.label(),
+                offset,
+                size: data.len() - offset,
+            });
+        }
+
+        Ok(VerifyInstruction {
+            instruction: Instruction {
+                program_id: *program_id,
+                accounts: metas,
+                data: data.clone(),
+            },
+            signers,
+            layout: Some(InstructionLayout {
+                spec: spec_path(base_path).display().to_string(),
+                discriminator: (!discriminator.is_empty()).then(|| encode_hex(&discriminator)),
+                accounts,
+                fields,
+                data_size: data.len(),
+            }),
+        })
+    }
+}
+
+/// Addresses the spec can refer to by name
+struct Addresses<'a> {
+    program_id: &'a Pubkey,
+    payer: &'a Pubkey,
+    buffer: Option<&'a Pubkey>,
+}
+
+impl Addresses<'_> {
+    /// `payer`, `program`, `system`, `buffer` or a base58 address
+    fn resolve(&self, value: &str) -> error::Result<Pubkey> {
+        match value {
+            "payer" => Ok(*self.payer),
+            "program" => Ok(*self.program_id),
+            "system" => Ok(solana_system_interface::program::ID),
+            "buffer" => self.buffer.copied().ok_or_else(|| {
+                ZkLenseError::Config(format!(
+                    "'buffer' in {} is only available with simulate --buffered",
+                    SPEC_FILE
+                ))
+            }),
+            address => Pubkey::from_str(address).map_err(|_| {
+                ZkLenseError::Config(format!(
+                    "Invalid address '{}' in {}; use an address, payer, program, system or buffer",
+                    address, SPEC_FILE
+                ))
+            }),
+        }
+    }
+}
+
+impl AccountSpec {
+    fn resolve(
+        &self,
+        base_path: &Path,
+        addresses: &Addresses,
+    ) -> error::Result<(Pubkey, Option<Keypair>)> {
+        let label = self.name.as_deref().unwrap_or("unnamed");
+        match (&self.pubkey, &self.seeds, &self.keypair) {
+            (Some(pubkey), None, None) => Ok((addresses.resolve(pubkey)?, None)),
+            (None, Some(seeds), None) => {
+                let seeds = seeds
+                    .iter()
+                    .map(|seed| {
+                        if seed.starts_with("0x") {
+                            decode_hex(seed)
+                        } else {
+                            Ok(seed.as_bytes().to_vec())
+                        }
+                    })
+                    .collect::<error::Result<Vec<_>>>()?;
+                let seeds: Vec<&[u8]> = seeds.iter().map(|s| s.as_slice()).collect();
+                Ok((
+                    Pubkey::find_program_address(&seeds, addresses.program_id).0,
+                    None,
+                ))
+            }
+            (None, None, Some(path)) => {
+                let path = base_path.join(path);
+                let keypair = read_keypair_file(&path).map_err(|e| {
+                    ZkLenseError::Config(format!(
+                        "Invalid keypair file {} for account '{}': {}",
+                        path.display(),
+                        label,
+                        e
+                    ))
+                })?;
+                let address = keypair.pubkey();
+                Ok((address, self.signer.then_some(keypair)))
+            }
+            _ => Err(ZkLenseError::Config(format!(
+                "Account '{}' in {} needs exactly one of pubkey, seeds or keypair",
+                label, SPEC_FILE
+            ))),
+        }
+    }
+}
+
+impl DataField {
+    fn encode(&self, addresses: &Addresses, proof_result: &ProofResult) -> error::Result<Vec<u8>> {
+        let prefixed = |bytes: &[u8], prefix: &Option<LengthPrefix>| -> error::Result<Vec<u8>> {
+            let mut encoded = match prefix {
+                Some(prefix) => prefix.encode(bytes.len())?,
+                None => Vec::new(),
+            };
+            encoded.extend_from_slice(bytes);
+            Ok(encoded)
+        };
+        match self {
+            DataField::Proof { prefix } => prefixed(&proof_result.proof, prefix),
+            DataField::Witness { prefix } => prefixed(&proof_result.public_witness, prefix),
+            DataField::Bytes { value, prefix } => prefixed(&decode_hex(value)?, prefix),
+            DataField::U8 { value } => Ok(vec![*value]),
+            DataField::U16 { value } => Ok(value.to_le_bytes().to_vec()),
+            DataField::U32 { value } => Ok(value.to_le_bytes().to_vec()),
+            DataField::U64 { value } => Ok(value.to_le_bytes().to_vec()),
+            DataField::I64 { value } => Ok(value.to_le_bytes().to_vec()),
+            DataField::Bool { value } => Ok(vec![*value as u8]),
+            DataField::Pubkey { value } => Ok(addresses.resolve(value)?.to_bytes().to_vec()),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            DataField::Proof { .. } => "proof",
+            DataField::Witness { .. } => "witness",
+            DataField::Bytes { .. } => "bytes",
+            DataField::U8 { .. } => "u8",
+            DataField::U16 { .. } => "u16",
+            DataField::U32 { .. } => "u32",
+            DataField::U64 { .. } => "u64",
+            DataField::I64 { .. } => "i64",
+            DataField::Bool { .. } => "bool",
+            DataField::Pubkey { .. } => "pubkey",
+        }
+        .to_string()
+    }
+}
+
+/// Build the verify instruction from the project's spec, or the bare
+/// verifier call without one
+pub fn verify_instruction(
+    base_path: &Path,
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    proof_result: &ProofResult,
+) -> error::Result<VerifyInstruction> {
+    match InstructionSpec::load(base_path)? {
+        Some(spec) => spec.build(base_path, program_id, payer, None, proof_result),
+        None => Ok(VerifyInstruction {
+            instruction: Instruction {
+                program_id: *program_id,
+                accounts: vec![],
+                data: create_instruction_data(proof_result),
+            },
+            signers: Vec::new(),
+            layout: None,
+        }),
+    }
+}
+
+/// Discriminator bytes: hex, or `anchor:<name>` for the first 8 bytes of
+/// `sha256("global:<name>")`
+pub fn parse_discriminator(value: Option<&str>) -> error::Result<Vec<u8>> {
+    match value {
+        None => Ok(Vec::new()),
+        Some(value) => match value.strip_prefix("anchor:") {
+            Some(name) => Ok(Sha256::digest(format!("global:{}", name))[..8].to_vec()),
+            None => decode_hex(value),
+        },
+    }
+}
+
+fn decode_hex(value: &str) -> error::Result<Vec<u8>> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    let invalid = || ZkLenseError::Config(format!("Invalid hex '{}' in {}", value, SPEC_FILE));
+    // Slicing by byte offset below needs single-byte characters
+    if !digits.is_ascii() || !digits.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Print the accounts and data fields of a custom verify instruction
+pub fn print_layout(layout: &InstructionLayout) {
+    ui::section(emoji::GEAR, "Verify Instruction");
+    let flags = |signer: bool, writable: bool| match (signer, writable) {
+        (true, true) => "signer, writable",
+        (true, false) => "signer",
+        (false, true) => "writable",
+        (false, false) => "readonly",
+    };
+    let accounts: Vec<(String, String)> = layout
+        .accounts
+        .iter()
+        .map(|account| {
+            (
+                account
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| "account".to_string()),
+                format!(
+                    "{} {}",
+                    account.address,
+                    style(format!("({})", flags(account.signer, account.writable))).dim()
+                ),
+            )
+        })
+        .chain(layout.fields.iter().map(|field| {
+            (
+                field.field.clone(),
+                format!(
+                    "{} bytes at offset {}",
+                    format_number(field.size as u64),
+                    field.offset
+                ),
+            )
+        }))
+        .collect();
+    let items: Vec<(&str, &str)> = accounts
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    ui::print_tree(&items);
+    ui::info(&format!(
+        "{} bytes of instruction data from {}",
+        format_number(layout.data_size as u64),
+        style(&layout.spec).dim()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_builds_discriminator_accounts_and_fields() {
+        let spec: InstructionSpec = toml::from_str(
+            r#"
+            discriminator = "0xaabb"
+
+            [[accounts]]
+            pubkey = "payer"
+            signer = true
+            writable = true
+
+            [[accounts]]
+            seeds = ["state"]
+
+            [[data]]
+            type = "witness"
+
+            [[data]]
+            type = "proof"
+            prefix = "u32"
+
+            [[data]]
+            type = "u16"
+            value = 7
+            "#,
+        )
+        .unwrap();
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let proof_result = ProofResult {
+            proof: vec![1, 2, 3],
+            public_witness: vec![9],
+        };
+
+        let verify = spec
+            .build(Path::new("."), &program_id, &payer, None, &proof_result)
+            .unwrap();
+        let instruction = verify.instruction;
+        assert_eq!(instruction.data, [0xaa, 0xbb, 9, 3, 0, 0, 0, 1, 2, 3, 7, 0]);
+        assert_eq!(instruction.accounts[0], AccountMeta::new(payer, true));
+        assert_eq!(
+            instruction.accounts[1].pubkey,
+            Pubkey::find_program_address(&[b"state"], &program_id).0
+        );
+        assert!(!instruction.accounts[1].is_writable);
+        let proof_field = &verify.layout.unwrap().fields[2];
+        assert_eq!((proof_field.offset, proof_field.size), (3, 7));
+    }
+
+    #[test]
+    fn test_decode_hex_rejects_non_ascii() {
+        assert_eq!(decode_hex("0xaabb").unwrap(), [0xaa, 0xbb]);
+        assert_eq!(decode_hex("aéb").unwrap_err().exit_code(), 5);
+        assert_eq!(decode_hex("abc").unwrap_err().exit_code(), 5);
+    }
+}
@@ -0,0 +1,205 @@
+//! Per-invocation compute unit breakdown from program logs
+//!
+//! The runtime logs `Program <id> invoke [<depth>]` when an instruction or
+//! CPI starts, `Program <id> consumed <n> of <m> compute units` and
+//! `Program <id> success` or `failed: <error>` when it ends, with the
+//! program's own `Program log:` and `Program data:` lines in between. These
+//! are folded into a tree so the compute units a wrapper program spends
+//! itself can be told apart from the verifier it calls.
+
+use super::compute_budget;
+use super::report::{Invocation, InvocationStatus};
+use super::simulate::format_number;
+use crate::ui::{self, emoji};
+
+/// Fold runtime log lines into the top-level invocations of a transaction
+pub fn parse(logs: &[String]) -> Vec<Invocation> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Invocation> = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(message) = rest.strip_prefix("log: ") {
+            if let Some(current) = stack.last_mut() {
+                current.logs.push(message.to_string());
+            }
+            continue;
+        }
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if let Some(current) = stack.last_mut() {
+                current.data.push(data.to_string());
+            }
+            continue;
+        }
+        let Some((program, event)) = rest.split_once(' ') else {
+            continue;
+        };
+
+        if let Some(depth) = event
+            .strip_prefix("invoke [")
+            .and_then(|depth| depth.strip_suffix(']'))
+        {
+            stack.push(Invocation {
+                program: program.to_string(),
+                depth: depth.parse().unwrap_or(stack.len() + 1),
+                units_consumed: None,
+                compute_limit: None,
+                exclusive_units: None,
+                status: InvocationStatus::Incomplete,
+                error: None,
+                logs: Vec::new(),
+                data: Vec::new(),
+                children: Vec::new(),
+            });
+        } else if let Some(units) = event.strip_prefix("consumed ") {
+            // `<n> of <m> compute units`
+            let mut parts = units.split_whitespace();
+            if let Some(current) = stack.last_mut().filter(|c| c.program == program) {
+                current.units_consumed = parts.next().and_then(|n| n.parse().ok());
+                current.compute_limit = parts.nth(1).and_then(|m| m.parse().ok());
+            }
+        } else if event == "success" || event.starts_with("failed") {
+            let Some(mut invocation) = stack.pop() else {
+                continue;
+            };
+            if event == "success" {
+                invocation.status = InvocationStatus::Success;
+            } else {
+                invocation.status = InvocationStatus::Failed;
+                invocation.error = event
+                    .strip_prefix("failed: ")
+                    .map(|error| error.to_string());
+            }
+            finish(invocation, &mut stack, &mut roots);
+        }
+    }
+
+    // Truncated logs leave invocations open
+    while let Some(invocation) = stack.pop() {
+        finish(invocation, &mut stack, &mut roots);
+    }
+    roots
+}
+
+/// Attach a finished invocation to its caller, or to the top level
+fn finish(mut invocation: Invocation, stack: &mut [Invocation], roots: &mut Vec<Invocation>) {
+    // A caller's consumed units include those of the programs it invoked
+    invocation.exclusive_units = invocation.units_consumed.map(|units| {
+        let children: u64 = invocation
+            .children
+            .iter()
+            .filter_map(|child| child.units_consumed)
+            .sum();
+        units.saturating_sub(children)
+    });
+    match stack.last_mut() {
+        Some(caller) => caller.children.push(invocation),
+        None => roots.push(invocation),
+    }
+}
+
+/// Known program names, or the shortened address
+fn program_label(program: &str) -> String {
+    if program == compute_budget::program_id().to_string() {
+        return "ComputeBudget".to_string();
+    }
+    if program == solana_system_interface::program::ID.to_string() {
+        return "System".to_string();
+    }
+    match (
+        program.get(..4),
+        program.get(program.len().saturating_sub(4)..),
+    ) {
+        (Some(head), Some(tail)) if program.len() > 8 => format!("{}…{}", head, tail),
+        _ => program.to_string(),
+    }
+}
+
+fn flatten(invocations: &[Invocation], items: &mut Vec<(String, String)>) {
+    for invocation in invocations {
+        let indent = "  ".repeat(invocation.depth.saturating_sub(1));
+        let label = format!("{}{}", indent, program_label(&invocation.program));
+        let mut value = match invocation.units_consumed {
+            Some(units) => format!("{} CU", format_number(units)),
+            None => "-".to_string(),
+        };
+        if !invocation.children.is_empty()
+            && let Some(own) = invocation.exclusive_units
+        {
+            value.push_str(&format!(" (own {})", format_number(own)));
+        }
+        match invocation.status {
+            InvocationStatus::Success => {}
+            InvocationStatus::Failed => value.push_str(&format!(
+                " {} {}",
+                emoji::ERROR,
+                invocation.error.as_deref().unwrap_or("failed")
+            )),
+            InvocationStatus::Incomplete => value.push_str(" (log truncated)"),
+        }
+        items.push((label, value));
+        flatten(&invocation.children, items);
+    }
+}
+
+/// Print every invocation with its compute units, callees indented under
+/// their caller
+pub fn print_invocations(invocations: &[Invocation]) {
+    if invocations.is_empty() {
+        return;
+    }
+    ui::section(emoji::LIGHTNING, "Compute Units by Invocation");
+    let mut items = Vec::new();
+    flatten(invocations, &mut items);
+    let items: Vec<(&str, &str)> = items
+        .iter()
+        .map(|(label, value)| (label.as_str(), value.as_str()))
+        .collect();
+    ui::print_tree(&items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpi_units_are_split_from_caller() {
+        let logs: Vec<String> = [
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+            "Program ComputeBudget111111111111111111111111111111 success",
+            "Program Wrap111111111111111111111111111111111111 invoke [1]",
+            "Program log: Instruction: Verify",
+            "Program Veri111111111111111111111111111111111111 invoke [2]",
+            "Program log: pairing ok",
+            "Program Veri111111111111111111111111111111111111 consumed 140000 of 1399000 compute units",
+            "Program Veri111111111111111111111111111111111111 success",
+            "Program data: AQID",
+            "Program Wrap111111111111111111111111111111111111 consumed 150000 of 1399850 compute units",
+            "Program Wrap111111111111111111111111111111111111 failed: custom program error: 0x1",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        let invocations = parse(&logs);
+        assert_eq!(invocations.len(), 2);
+        assert_eq!(invocations[0].status, InvocationStatus::Success);
+        assert_eq!(invocations[0].units_consumed, None);
+
+        let wrapper = &invocations[1];
+        assert_eq!(wrapper.units_consumed, Some(150_000));
+        assert_eq!(wrapper.compute_limit, Some(1_399_850));
+        assert_eq!(wrapper.exclusive_units, Some(10_000));
+        assert_eq!(wrapper.status, InvocationStatus::Failed);
+        assert_eq!(wrapper.error.as_deref(), Some("custom program error: 0x1"));
+        assert_eq!(wrapper.logs, vec!["Instruction: Verify"]);
+        assert_eq!(wrapper.data, vec!["AQID"]);
+
+        let verifier = &wrapper.children[0];
+        assert_eq!(verifier.depth, 2);
+        assert_eq!(verifier.units_consumed, Some(140_000));
+        assert_eq!(verifier.logs, vec!["pairing ok"]);
+    }
+}
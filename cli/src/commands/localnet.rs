@@ -0,0 +1,241 @@
+//! Local `solana-test-validator` for hermetic simulation
+//!
+//! With the `localnet` network, `zklense simulate` attaches to a validator
+//! already listening on the configured RPC URL, or starts one with the
+//! circuit's verifier program preloaded via `--bpf-program`.
+
+use console::style;
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::init::get_zklense_dir;
+use super::run::command_exists;
+use crate::error::{self, ZkLenseError};
+use crate::ui;
+
+const VALIDATOR: &str = "solana-test-validator";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Balance the simulation fee payer is topped up to
+const FEE_PAYER_FUNDING: u64 = 10 * LAMPORTS_PER_SOL;
+
+/// Program ID the verifier is loaded at. Derived from the circuit name so it
+/// is the same on every run.
+pub fn program_id(circuit: &str) -> Pubkey {
+    let digest = Sha256::digest(format!("zklense-localnet:{}", circuit));
+    Pubkey::new_from_array(digest.into())
+}
+
+/// Ledger directory of validators started by zklense
+pub fn ledger_dir(base_path: &Path) -> PathBuf {
+    get_zklense_dir(base_path).join("test-ledger")
+}
+
+/// Validator output, kept next to the ledger
+pub fn log_path(base_path: &Path) -> PathBuf {
+    get_zklense_dir(base_path).join("test-validator.log")
+}
+
+/// A running local validator. One started by zklense is stopped when this
+/// is dropped, unless [`LocalValidator::keep_running`] is called.
+pub struct LocalValidator {
+    child: Option<Child>,
+}
+
+impl LocalValidator {
+    /// Whether zklense started this validator rather than attaching to it
+    pub fn started(&self) -> bool {
+        self.child.is_some()
+    }
+
+    /// Leave the validator running after zklense exits
+    pub fn keep_running(mut self) -> Option<u32> {
+        self.child.take().map(|child| child.id())
+    }
+}
+
+impl Drop for LocalValidator {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// RPC port of a local URL such as `http://127.0.0.1:8899`, or `None` if the
+/// URL does not point at this machine
+fn local_rpc_port(rpc_url: &str) -> Option<u16> {
+    let rest = rpc_url
+        .strip_prefix("http://")
+        .or_else(|| rpc_url.strip_prefix("https://"))?;
+    let authority = rest.split('/').next()?;
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, 80),
+    };
+    matches!(host, "127.0.0.1" | "localhost" | "0.0.0.0").then_some(port)
+}
+
+/// Last lines of the validator log, for error messages
+fn log_tail(base_path: &Path) -> String {
+    let log = fs::read_to_string(log_path(base_path)).unwrap_or_default();
+    let lines: Vec<&str> = log.lines().collect();
+    lines[lines.len().saturating_sub(10)..].join("\n")
+}
+
+/// Attach to the validator at `rpc_url`, or start one with `so_path`
+/// loaded at `program_id`
+pub async fn start_or_attach(
+    base_path: &Path,
+    rpc_url: &str,
+    program_id: &Pubkey,
+    so_path: &Path,
+) -> error::Result<LocalValidator> {
+    let client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+
+    if client.get_health().await.is_ok() {
+        ui::success(&format!(
+            "Attached to local validator at {}",
+            style(rpc_url).dim()
+        ));
+        if client.get_account(program_id).await.is_err() {
+            ui::warn(&format!(
+                "Program {} is not loaded on this validator. Stop it so zklense can start one with the program preloaded.",
+                program_id
+            ));
+        }
+        return Ok(LocalValidator { child: None });
+    }
+
+    let Some(port) = local_rpc_port(rpc_url) else {
+        return Err(ZkLenseError::Rpc(format!(
+            "No validator responding at {} and it is not a local address, so zklense cannot start one",
+            rpc_url
+        )));
+    };
+    if !so_path.is_file() {
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "Solana program not found at {}\nRun 'zklense run' first to build it.",
+            so_path.display()
+        )));
+    }
+    if !command_exists(VALIDATOR) {
+        ui::panel_error(
+            "SOLANA TEST VALIDATOR NOT FOUND",
+            "solana-test-validator is required to simulate on localnet.",
+            None,
+            Some(&["Install from: https://docs.solana.com/cli/install-solana-cli-tools"]),
+        );
+        return Err(ZkLenseError::MissingTool(
+            "solana-test-validator not found".to_string(),
+        ));
+    }
+
+    let start = Instant::now();
+    let spinner = ui::spinner(&format!("Starting {} on port {}...", VALIDATOR, port));
+    let log = fs::File::create(log_path(base_path))?;
+    let child = Command::new(VALIDATOR)
+        .arg("--ledger")
+        .arg(ledger_dir(base_path))
+        .arg("--reset")
+        .args(["--rpc-port", &port.to_string()])
+        .arg("--bpf-program")
+        .arg(program_id.to_string())
+        .arg(so_path)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()
+        .map_err(|e| {
+            ui::spinner_error(&spinner, "Failed to start validator");
+            ZkLenseError::CommandFailed(format!("Failed to start {}: {}", VALIDATOR, e))
+        })?;
+    let mut validator = LocalValidator { child: Some(child) };
+
+    loop {
+        if client.get_health().await.is_ok() {
+            break;
+        }
+        let exited = validator
+            .child
+            .as_mut()
+            .and_then(|child| child.try_wait().ok().flatten());
+        if let Some(status) = exited {
+            ui::spinner_error(&spinner, "Validator exited");
+            return Err(ZkLenseError::CommandFailed(format!(
+                "{} exited with {}\n{}",
+                VALIDATOR,
+                status,
+                log_tail(base_path)
+            )));
+        }
+        if start.elapsed() > STARTUP_TIMEOUT {
+            ui::spinner_error(&spinner, "Validator did not start");
+            return Err(ZkLenseError::CommandFailed(format!(
+                "{} did not become healthy within {}s (log: {})",
+                VALIDATOR,
+                STARTUP_TIMEOUT.as_secs(),
+                log_path(base_path).display()
+            )));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    ui::spinner_success_with_duration(
+        &spinner,
+        &format!("Local validator running with program {}", program_id),
+        start.elapsed().as_millis(),
+    );
+    Ok(validator)
+}
+
+/// Airdrop to `fee_payer` so simulated transactions can pay fees
+pub async fn fund_fee_payer(client: &RpcClient, fee_payer: &Pubkey) -> error::Result<()> {
+    let balance = client.get_balance(fee_payer).await.unwrap_or(0);
+    if balance >= FEE_PAYER_FUNDING / 2 {
+        return Ok(());
+    }
+
+    let spinner = ui::spinner("Funding fee payer...");
+    let signature = client
+        .request_airdrop(fee_payer, FEE_PAYER_FUNDING)
+        .await
+        .map_err(|e| ZkLenseError::Rpc(format!("Airdrop to {} failed: {}", fee_payer, e)))?;
+    let start = Instant::now();
+    while !client
+        .confirm_transaction(&signature)
+        .await
+        .unwrap_or(false)
+    {
+        if start.elapsed() > STARTUP_TIMEOUT {
+            ui::spinner_error(&spinner, "Airdrop not confirmed");
+            return Err(ZkLenseError::Rpc(format!(
+                "Airdrop {} was not confirmed",
+                signature
+            )));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    ui::spinner_success(&spinner, &format!("Funded fee payer {}", fee_payer));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_rpc_port_and_program_id() {
+        assert_eq!(local_rpc_port("http://127.0.0.1:8899"), Some(8899));
+        assert_eq!(local_rpc_port("http://localhost:9000/"), Some(9000));
+        assert_eq!(local_rpc_port("https://api.devnet.solana.com"), None);
+        assert_eq!(program_id("age_verifier"), program_id("age_verifier"));
+        assert_ne!(program_id("age_verifier"), program_id("range_proof"));
+    }
+}
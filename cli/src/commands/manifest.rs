@@ -0,0 +1,217 @@
+//! Build manifest for incremental pipeline runs
+//!
+//! Each pipeline step records SHA-256 hashes of the files it read and wrote
+//! in `.zklense/build-manifest.json`. On the next `zklense run`, a step is
+//! skipped when its command, inputs and outputs all still match the record.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::init::{chrono_timestamp, get_zklense_dir};
+
+const MANIFEST_FILE: &str = "build-manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+/// Map of project-relative file path to hex-encoded SHA-256 digest
+pub type Fingerprint = BTreeMap<String, String>;
+
+/// What a pipeline step consumed and produced the last time it succeeded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub command: String,
+    pub inputs: Fingerprint,
+    pub outputs: Fingerprint,
+    pub completed_at: String,
+}
+
+/// Per-project record of completed pipeline steps
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildManifest {
+    pub version: u32,
+    #[serde(default)]
+    pub steps: BTreeMap<String, StepRecord>,
+}
+
+impl Default for BuildManifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            steps: BTreeMap::new(),
+        }
+    }
+}
+
+impl BuildManifest {
+    /// Load the manifest for a project. A missing, unreadable or outdated
+    /// manifest yields an empty one so every step runs.
+    pub fn load(base_path: &Path) -> Self {
+        fs::read_to_string(manifest_path(base_path))
+            .ok()
+            .and_then(|contents| serde_json::from_str::<BuildManifest>(&contents).ok())
+            .filter(|manifest| manifest.version == MANIFEST_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Save the manifest to `.zklense/build-manifest.json`
+    pub fn save(&self, base_path: &Path) -> io::Result<()> {
+        let zklense_dir = get_zklense_dir(base_path);
+        fs::create_dir_all(&zklense_dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(manifest_path(base_path), json)
+    }
+
+    /// Check whether a step can be skipped: it must have run before with the
+    /// same command and inputs, and its outputs must be untouched since.
+    pub fn is_fresh(
+        &self,
+        base_path: &Path,
+        step: &str,
+        command: &str,
+        inputs: &Fingerprint,
+        outputs: &[String],
+    ) -> io::Result<bool> {
+        let Some(record) = self.steps.get(step) else {
+            return Ok(false);
+        };
+
+        if record.command != command || &record.inputs != inputs {
+            return Ok(false);
+        }
+
+        // Outputs that were never produced can't be trusted as up to date
+        if outputs.iter().any(|o| !record.outputs.contains_key(o)) {
+            return Ok(false);
+        }
+
+        Ok(fingerprint(base_path, outputs)? == record.outputs)
+    }
+
+    /// Record a successful step run
+    pub fn record(&mut self, step: &str, command: &str, inputs: Fingerprint, outputs: Fingerprint) {
+        self.steps.insert(
+            step.to_string(),
+            StepRecord {
+                command: command.to_string(),
+                inputs,
+                outputs,
+                completed_at: chrono_timestamp(),
+            },
+        );
+    }
+
+    /// Forget a step so it runs again next time
+    pub fn invalidate(&mut self, step: &str) {
+        self.steps.remove(step);
+    }
+}
+
+/// Get the path to the build manifest
+pub fn manifest_path(base_path: &Path) -> std::path::PathBuf {
+    get_zklense_dir(base_path).join(MANIFEST_FILE)
+}
+
+/// Hash the given project-relative paths. Directories are walked recursively
+/// and missing paths are left out of the result.
+pub fn fingerprint(base_path: &Path, paths: &[String]) -> io::Result<Fingerprint> {
+    let mut hashes = Fingerprint::new();
+    for rel in paths {
+        collect_hashes(base_path, &base_path.join(rel), &mut hashes)?;
+    }
+    Ok(hashes)
+}
+
+fn collect_hashes(base_path: &Path, path: &Path, hashes: &mut Fingerprint) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        entries.sort();
+        for entry in entries {
+            collect_hashes(base_path, &entry, hashes)?;
+        }
+    } else if path.is_file() {
+        let rel = path
+            .strip_prefix(base_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        hashes.insert(rel, hash_file(path)?);
+    }
+    Ok(())
+}
+
+/// Hex-encoded SHA-256 digest of a file's contents
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let contents = fs::read(path)?;
+    Ok(hash_bytes(&contents))
+}
+
+/// Hex-encoded SHA-256 digest of a byte slice
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_freshness_tracks_inputs_and_outputs() {
+        let temp_dir = std::env::temp_dir().join("zklense_test_manifest");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("src")).unwrap();
+        fs::create_dir_all(temp_dir.join("target")).unwrap();
+        fs::write(temp_dir.join("src/main.nr"), "fn main() {}").unwrap();
+        fs::write(temp_dir.join("target/out.json"), "{}").unwrap();
+
+        let inputs = vec!["src".to_string()];
+        let outputs = vec!["target/out.json".to_string()];
+
+        let mut manifest = BuildManifest::default();
+        let before = fingerprint(&temp_dir, &inputs).unwrap();
+        assert!(before.contains_key("src/main.nr"));
+        assert!(
+            !manifest
+                .is_fresh(&temp_dir, "Execute", "nargo execute", &before, &outputs)
+                .unwrap()
+        );
+
+        manifest.record(
+            "Execute",
+            "nargo execute",
+            before.clone(),
+            fingerprint(&temp_dir, &outputs).unwrap(),
+        );
+        manifest.save(&temp_dir).unwrap();
+        let manifest = BuildManifest::load(&temp_dir);
+        assert!(
+            manifest
+                .is_fresh(&temp_dir, "Execute", "nargo execute", &before, &outputs)
+                .unwrap()
+        );
+
+        // A modified output invalidates the step
+        fs::write(temp_dir.join("target/out.json"), "{\"changed\":true}").unwrap();
+        assert!(
+            !manifest
+                .is_fresh(&temp_dir, "Execute", "nargo execute", &before, &outputs)
+                .unwrap()
+        );
+
+        // So does a modified input
+        fs::write(temp_dir.join("src/main.nr"), "fn main() { assert(true); }").unwrap();
+        let after = fingerprint(&temp_dir, &inputs).unwrap();
+        assert_ne!(before, after);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+//! Priority fee tiers from `getRecentPrioritizationFees`
+//!
+//! Recent fees are sampled for the writable accounts of the verifier
+//! instruction, since those accounts' fee market decides how quickly the
+//! transaction lands. The bare sunspot verifier writes no accounts, in which
+//! case the samples cover the whole cluster.
+
+use comfy_table::Cell;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, transaction::Transaction};
+
+use super::compute_budget::prioritization_fee;
+use super::report::{PrioritizationFeeSample, PriorityFeeEstimates, PriorityFeeTier};
+use super::simulate::format_number;
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Percentiles of the sampled fees offered as tiers
+pub const PERCENTILES: [u8; 4] = [25, 50, 75, 95];
+
+/// Nearest-rank percentile of ascending `sorted` values, 0 when empty
+pub fn percentile(sorted: &[u64], percentile: u8) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percentile as usize * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// Writable accounts passed to `program_id`'s instructions, in message order
+pub fn writable_accounts(transaction: &Transaction, program_id: &Pubkey) -> Vec<Pubkey> {
+    let message = &transaction.message;
+    let mut accounts = Vec::new();
+    for instruction in &message.instructions {
+        if message
+            .account_keys
+            .get(instruction.program_id_index as usize)
+            != Some(program_id)
+        {
+            continue;
+        }
+        for &index in &instruction.accounts {
+            let index = index as usize;
+            if message.is_maybe_writable(index, None) {
+                let key = message.account_keys[index];
+                if !accounts.contains(&key) {
+                    accounts.push(key);
+                }
+            }
+        }
+    }
+    accounts
+}
+
+/// Fee tiers for the sampled prioritization fees, `None` without samples
+pub fn estimate(
+    samples: &[PrioritizationFeeSample],
+    accounts: &[Pubkey],
+    units_consumed: u64,
+    recommended_cu_limit: u32,
+    base_fee: u64,
+) -> Option<PriorityFeeEstimates> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut fees: Vec<u64> = samples.iter().map(|s| s.prioritization_fee).collect();
+    fees.sort_unstable();
+    let consumed_limit = u32::try_from(units_consumed).unwrap_or(u32::MAX);
+
+    let tiers = PERCENTILES
+        .iter()
+        .map(|&p| {
+            let price = percentile(&fees, p);
+            let at_consumed = prioritization_fee(consumed_limit, price);
+            let at_recommended = prioritization_fee(recommended_cu_limit, price);
+            PriorityFeeTier {
+                percentile: p,
+                cu_price_microlamports: price,
+                prioritization_fee_at_consumed: at_consumed,
+                total_fee_at_consumed: base_fee + at_consumed,
+                prioritization_fee_at_recommended: at_recommended,
+                total_fee_at_recommended: base_fee + at_recommended,
+            }
+        })
+        .collect();
+
+    Some(PriorityFeeEstimates {
+        accounts: accounts.iter().map(|a| a.to_string()).collect(),
+        sample_count: samples.len(),
+        units_consumed,
+        recommended_cu_limit,
+        tiers,
+    })
+}
+
+/// Fee suggestion for the report, based on the median and p75 tiers
+pub fn suggestion(estimates: &PriorityFeeEstimates) -> String {
+    let price = |p: u8| {
+        estimates
+            .tiers
+            .iter()
+            .find(|t| t.percentile == p)
+            .map_or(0, |t| t.cu_price_microlamports)
+    };
+    match (price(50), price(75)) {
+        (0, 0) => {
+            "Recent blocks show no priority fee competition; the base fee is enough".to_string()
+        }
+        (median, p75) => format!(
+            "Set a compute unit price of {} micro-lamports/CU (p75) for fast confirmation; the median is {}",
+            format_number(p75),
+            format_number(median)
+        ),
+    }
+}
+
+fn sol(lamports: u64) -> String {
+    format!("{:.9} SOL", lamports as f64 / LAMPORTS_PER_SOL as f64)
+}
+
+/// Print the fee tiers as a table
+pub fn print_estimates(estimates: &PriorityFeeEstimates) {
+    ui::section(emoji::MONEY, "Priority Fee Tiers");
+    let scope = if estimates.accounts.is_empty() {
+        "all accounts".to_string()
+    } else {
+        format!("{} writable account(s)", estimates.accounts.len())
+    };
+    ui::print_tree(&[
+        (
+            "Samples",
+            &format!("{} recent slots, {}", estimates.sample_count, scope),
+        ),
+        (
+            "CU Basis",
+            &format!(
+                "{} CU consumed, {} CU recommended limit",
+                format_number(estimates.units_consumed),
+                format_number(estimates.recommended_cu_limit as u64)
+            ),
+        ),
+    ]);
+
+    let mut table = ui::create_table(&[
+        "Tier",
+        "CU Price (µlamports)",
+        "Total @ Consumed",
+        "Total @ Recommended",
+        "SOL @ Recommended",
+    ]);
+    for tier in &estimates.tiers {
+        table.add_row(vec![
+            Cell::new(format!("p{}", tier.percentile)),
+            Cell::new(format_number(tier.cu_price_microlamports)),
+            Cell::new(format!(
+                "{} lamports",
+                format_number(tier.total_fee_at_consumed)
+            )),
+            Cell::new(format!(
+                "{} lamports",
+                format_number(tier.total_fee_at_recommended)
+            )),
+            Cell::new(sol(tier.total_fee_at_recommended)),
+        ]);
+    }
+    ui_println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_use_nearest_rank() {
+        let fees: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&fees, 25), 5);
+        assert_eq!(percentile(&fees, 50), 10);
+        assert_eq!(percentile(&fees, 95), 19);
+        assert_eq!(percentile(&[7], 25), 7);
+        assert_eq!(percentile(&[], 50), 0);
+
+        let samples: Vec<PrioritizationFeeSample> = [0, 1_000, 10_000, 100_000]
+            .iter()
+            .enumerate()
+            .map(|(slot, &fee)| PrioritizationFeeSample {
+                slot: slot as u64,
+                prioritization_fee: fee,
+            })
+            .collect();
+        let estimates = estimate(&samples, &[], 150_000, 200_000, 5000).unwrap();
+        let p75 = &estimates.tiers[2];
+        assert_eq!(p75.cu_price_microlamports, 10_000);
+        assert_eq!(p75.total_fee_at_consumed, 5000 + 1500);
+        assert_eq!(p75.total_fee_at_recommended, 5000 + 2000);
+    }
+}
@@ -0,0 +1,610 @@
+//! Simulation report written to `.zklense/reports/` and `.zklense/report.json`
+//!
+//! The report shape is versioned with [`SCHEMA_VERSION`]; bump it whenever a
+//! field is renamed, removed or changes type. `zklense report schema` prints
+//! the matching JSON Schema, also published as `docs/report.schema.json`.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error;
+use crate::ui;
+
+/// Version of the report layout. Reports without a `schema_version` field
+/// predate versioning.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Result of simulating proof verification on Solana
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SimulationReport {
+    /// Report layout version
+    pub schema_version: u32,
+    /// Unix timestamp (seconds) when the report was generated
+    pub generated_at: u64,
+    /// Verifier program the transaction was simulated against
+    pub program_id: String,
+    /// Whether the numbers come from a simulation or a landed transaction
+    #[serde(default)]
+    pub mode: ReportMode,
+    /// Signature and confirmation of a transaction sent by `zklense submit`
+    #[serde(default)]
+    pub submission: Option<Submission>,
+    pub environment: Environment,
+    pub compute_units: ComputeUnits,
+    pub proof: ProofSizes,
+    pub heap_usage: HeapUsage,
+    pub cost: Cost,
+    pub transaction_status: TransactionStatus,
+    pub transaction_size: TransactionSize,
+    pub transaction_logs: TransactionLogs,
+    pub accounts: Accounts,
+    pub deserialization: Deserialization,
+    /// Recent prioritization fees reported by the RPC node, newest first
+    pub recent_prioritization_fees: Option<Vec<PrioritizationFeeSample>>,
+    /// Tight compute unit limit, when simulated with `--tune-cu`
+    #[serde(default)]
+    pub cu_limit_recommendation: Option<CuLimitRecommendation>,
+    /// Total cost at percentiles of the recent prioritization fees
+    #[serde(default)]
+    pub priority_fee_estimates: Option<PriorityFeeEstimates>,
+    /// Account that paid for the transaction
+    #[serde(default)]
+    pub fee_payer: Option<FeePayerInfo>,
+    /// Layout of the verify instruction, when built from `.zklense/instruction.toml`
+    #[serde(default)]
+    pub instruction: Option<InstructionLayout>,
+    /// Transactions writing the proof to a buffer, with `--buffered`
+    #[serde(default)]
+    pub buffered_delivery: Option<BufferedDelivery>,
+    /// Legacy and v0 sizes of the verify transaction, with `--v0`
+    #[serde(default)]
+    pub transaction_encodings: Option<TransactionEncodings>,
+    /// Statistics over repeated simulations, with `--runs`
+    #[serde(default)]
+    pub runs: Option<RunStatistics>,
+    /// Every proof and witness pair simulated, with `--batch`
+    #[serde(default)]
+    pub batch: Option<BatchReport>,
+    /// Where the report came from, used by `zklense history`
+    #[serde(default)]
+    pub metadata: Option<ReportMetadata>,
+}
+
+/// How a report was produced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportMode {
+    /// `zklense simulate`
+    #[default]
+    Simulated,
+    /// `zklense submit`; compute units, fee and logs are from the confirmed
+    /// transaction
+    Submitted,
+}
+
+/// A transaction sent and confirmed by `zklense submit`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Submission {
+    pub signature: String,
+    /// Slot the transaction landed in
+    pub slot: u64,
+    /// Commitment level the transaction was confirmed at
+    pub commitment: String,
+    /// Times the transaction was sent, including retries
+    pub attempts: u32,
+    /// Fee charged by the cluster, in lamports
+    pub fee: u64,
+}
+
+/// Provenance of a report stored under `.zklense/reports/`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReportMetadata {
+    /// History ID, also the file name under `.zklense/reports/`
+    pub id: String,
+    /// Circuit name from Nargo.toml
+    pub circuit: Option<String>,
+    /// Git commit of the project, if it is a git repository
+    pub git_commit: Option<String>,
+    /// Whether the working tree had uncommitted changes
+    pub git_dirty: Option<bool>,
+    /// SHA-256 of the proof file
+    pub proof_hash: String,
+    /// SHA-256 of the public witness file
+    pub public_witness_hash: String,
+    /// SHA-256 of the verifying key, when found next to the proof
+    pub vk_hash: Option<String>,
+}
+
+/// Network the simulation ran on
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Environment {
+    pub network: String,
+    pub rpc_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeUnits {
+    pub total_compute_units_consumed: u64,
+    /// Compute unit limit requested by the transaction
+    pub compute_budget: u64,
+    pub max_compute_units: u32,
+    /// Share of the compute budget used, in percent (0-100)
+    pub percentage_of_compute_budget_used: f64,
+    pub warning: Option<String>,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProofSizes {
+    pub proof_size: usize,
+    pub witness_size: usize,
+    pub total_proof_witness_size: usize,
+    /// Compute units consumed per byte of proof and witness
+    pub cu_per_proof_size: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HeapUsage {
+    /// Heap frame available to the program in bytes, 32 KiB unless requested
+    pub heap_size: u64,
+    pub suggestion: String,
+    /// Whether the program ran out of heap
+    #[serde(default)]
+    pub exhausted: bool,
+    /// Search for the smallest heap frame verification succeeds with
+    #[serde(default)]
+    pub search: Option<HeapSearch>,
+}
+
+/// Result of binary-searching the heap frame
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HeapSearch {
+    /// Smallest heap frame in bytes verification succeeds with; `None` when
+    /// it fails even with the largest frame
+    pub minimum_heap_frame: Option<u32>,
+    /// Heap frames simulated, in bytes, in the order they were tried
+    pub heap_frames_tried: Vec<u32>,
+}
+
+/// Transaction fees, in lamports unless stated otherwise
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Cost {
+    pub base_fee_per_signature: u64,
+    pub num_signatures: u64,
+    pub base_fee: u64,
+    pub cu_limit: u32,
+    pub cu_price_microlamports: u64,
+    pub prioritization_fee: u64,
+    pub total_fee: u64,
+    /// `total_fee` in SOL
+    pub cost_in_sol: f64,
+    /// Prioritization fee per requested compute unit
+    pub priority: f64,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Status {
+    Success,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransactionStatus {
+    pub status: Status,
+    pub error: Option<String>,
+    pub suggestion: String,
+    /// Likely cause of a failure and how to fix it
+    #[serde(default)]
+    pub diagnosis: Option<Diagnosis>,
+}
+
+/// Known causes of failed verify transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCause {
+    ComputeBudgetExceeded,
+    HeapExhausted,
+    ProgramNotDeployed,
+    FeePayerNotFunded,
+    MissingSignature,
+    SignatureVerification,
+    BlockhashNotFound,
+    LookupTableInvalid,
+    /// The public witness has a different number of inputs than the VK expects
+    WitnessMismatch,
+    /// The proof does not verify against the local VK either
+    ProofRejected,
+    /// The proof verifies against the local VK but not on-chain
+    VerifyingKeyMismatch,
+    InvalidInstructionData,
+    ProgramPanicked,
+    /// The program stopped without an error and without running out of CUs
+    ProgramFailedToComplete,
+    CustomError,
+    Unknown,
+}
+
+/// Decoded transaction error with the logs it was matched against
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Diagnosis {
+    pub cause: FailureCause,
+    /// Index of the failing instruction in the transaction
+    pub instruction_index: Option<u8>,
+    /// Program the failing instruction invoked
+    pub program: Option<String>,
+    /// Code of a `Custom` instruction error
+    pub custom_code: Option<u32>,
+    pub explanation: String,
+    pub fix: String,
+    /// Log lines pointing at the cause
+    pub evidence: Vec<String>,
+}
+
+/// Serialized sizes in bytes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransactionSize {
+    pub transaction_size: usize,
+    pub message_size: usize,
+    pub max_message_size: usize,
+    pub message_within_size: bool,
+    pub message: String,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransactionLogs {
+    pub logs: Vec<String>,
+    pub log_count: usize,
+    /// Top-level instructions parsed from the logs, with the programs they
+    /// invoked nested under them
+    #[serde(default)]
+    pub invocations: Vec<Invocation>,
+}
+
+/// Outcome of one program invocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InvocationStatus {
+    Success,
+    Failed,
+    /// The logs end before the invocation does, e.g. when truncated
+    Incomplete,
+}
+
+/// A program invocation, a top-level instruction or a CPI
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Invocation {
+    pub program: String,
+    /// Invocation depth, 1 for top-level instructions
+    pub depth: usize,
+    /// Compute units consumed, including the programs it invoked
+    pub units_consumed: Option<u64>,
+    /// Compute units left when it was invoked
+    pub compute_limit: Option<u64>,
+    /// Compute units consumed by the program itself
+    pub exclusive_units: Option<u64>,
+    pub status: InvocationStatus,
+    pub error: Option<String>,
+    /// `Program log:` messages
+    pub logs: Vec<String>,
+    /// Base64 `Program data:` payloads
+    pub data: Vec<String>,
+    /// Programs it invoked, in order
+    pub children: Vec<Invocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Accounts {
+    pub total_accounts: usize,
+    pub writable_signed_accounts: usize,
+    pub writable_unsigned_accounts: usize,
+    pub total_writable_accounts: usize,
+    pub readonly_signed_accounts: u8,
+    pub readonly_unsigned_accounts: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Deserialization {
+    pub success: bool,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrioritizationFeeSample {
+    pub slot: u64,
+    /// Micro-lamports per compute unit
+    pub prioritization_fee: u64,
+}
+
+/// Compute unit limit found by re-simulating with consumed units plus a
+/// safety margin
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CuLimitRecommendation {
+    /// Limit to request with `SetComputeUnitLimit` in production transactions
+    pub recommended_cu_limit: u32,
+    /// Margin added to the consumed compute units, in percent
+    pub safety_margin_percent: f64,
+    /// Whether verification succeeded when re-simulated with the recommended limit
+    pub confirmed: bool,
+    /// Compute units consumed by the confirming simulation
+    pub units_consumed: u64,
+    /// Error of the confirming simulation, if it failed
+    pub error: Option<String>,
+    /// Compute unit price the fees are calculated with: the transaction's own
+    /// price, or the median recent prioritization fee
+    pub cu_price_microlamports: u64,
+    /// Prioritization fee in lamports when requesting the maximum limit
+    pub prioritization_fee_at_max: u64,
+    /// Prioritization fee in lamports when requesting the recommended limit
+    pub prioritization_fee_at_recommended: u64,
+    /// Lamports saved per transaction by requesting the recommended limit
+    pub fee_savings: u64,
+}
+
+/// Fee payer of the simulated transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FeePayerInfo {
+    pub address: String,
+    /// Keypair file, or how the payer was chosen without one
+    pub source: String,
+    /// Whether the transaction was signed by the fee payer
+    pub signed: bool,
+    /// Whether the node verified the signature
+    pub sig_verify: bool,
+    /// Whether the node replaced the blockhash with its latest one
+    pub replace_recent_blockhash: bool,
+    /// Balance in lamports before the simulation, for keypair files on RPC
+    pub balance: Option<u64>,
+}
+
+/// Verify instruction built from a custom instruction spec
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstructionLayout {
+    /// Spec file the instruction was built from
+    pub spec: String,
+    /// Hex-encoded discriminator prefix, if any
+    pub discriminator: Option<String>,
+    pub accounts: Vec<InstructionAccount>,
+    /// Instruction data fields in order, discriminator included
+    pub fields: Vec<InstructionField>,
+    /// Total instruction data size in bytes
+    pub data_size: usize,
+}
+
+/// Account passed to the verify instruction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstructionAccount {
+    pub name: Option<String>,
+    pub address: String,
+    pub signer: bool,
+    pub writable: bool,
+}
+
+/// Field of the verify instruction data
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstructionField {
+    /// Field type, e.g. `proof` or `u64`
+    pub field: String,
+    /// Byte offset in the instruction data
+    pub offset: usize,
+    /// Size in bytes, length prefix included
+    pub size: usize,
+}
+
+/// Proof delivered through a buffer account across several transactions
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BufferedDelivery {
+    pub buffer: String,
+    /// Program owning the buffer
+    pub program: String,
+    /// Buffer account size in bytes, header included
+    pub buffer_size: usize,
+    /// Lamports deposited to make the buffer rent exempt
+    pub rent_lamports: u64,
+    /// Proof bytes written per transaction
+    pub chunk_size: usize,
+    /// Every transaction in order, the verify transaction last
+    pub transactions: Vec<DeliveryTransaction>,
+    /// Compute units consumed across all transactions
+    pub total_compute_units: u64,
+    /// Fees across all transactions in lamports, rent excluded
+    pub total_fee: u64,
+}
+
+/// One transaction of a buffered delivery
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeliveryTransaction {
+    /// `create`, `write` or `verify`
+    pub step: String,
+    pub transaction_size: usize,
+    pub units_consumed: u64,
+    pub fee: u64,
+    pub error: Option<String>,
+}
+
+/// Repeated simulations of the verify transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RunStatistics {
+    pub runs: usize,
+    pub succeeded: usize,
+    /// Runs whose transaction failed, or whose RPC request kept failing
+    pub failed: usize,
+    /// Share of failed runs, from 0 to 1
+    pub failure_rate: f64,
+    /// RPC requests retried across all runs
+    pub retries: u32,
+    /// Over the successful runs; `None` when none succeeded
+    pub compute_units: Option<ComputeUnitStatistics>,
+    /// Every run in order, the first being the simulation in this report
+    pub samples: Vec<RunSample>,
+}
+
+/// Distribution of the compute units consumed across runs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComputeUnitStatistics {
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+    pub mean: f64,
+    /// Population variance
+    pub variance: f64,
+    pub std_deviation: f64,
+}
+
+/// One simulation run
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RunSample {
+    /// Run number, starting at 1
+    pub run: usize,
+    pub proof_path: String,
+    pub witness_path: String,
+    pub units_consumed: Option<u64>,
+    /// Transaction or RPC error
+    pub error: Option<String>,
+    /// RPC requests retried in this run
+    pub retries: u32,
+    pub duration_ms: u64,
+}
+
+/// Simulations of several proof and witness pairs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchReport {
+    /// Directory or file pattern the pairs were found with
+    pub source: String,
+    pub inputs: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Over the successful inputs; `None` when none succeeded
+    pub compute_units: Option<ComputeUnitStatistics>,
+    /// Whether every successful input consumed the same compute units
+    pub constant_cost: bool,
+    /// Deviation from the median compute units beyond which an input is an
+    /// outlier, in percent
+    pub outlier_threshold_percent: f64,
+    pub outliers: usize,
+    /// Every input in the order simulated; the first is the simulation in
+    /// this report
+    pub entries: Vec<BatchEntry>,
+}
+
+/// One simulated proof and witness pair
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchEntry {
+    pub proof_path: String,
+    pub witness_path: String,
+    pub status: Status,
+    pub units_consumed: Option<u64>,
+    /// Deviation from the median compute units, in percent
+    pub deviation_percent: Option<f64>,
+    pub outlier: bool,
+    pub transaction_size: usize,
+    /// Fee at the transaction's compute unit limit, in lamports
+    pub fee: u64,
+    /// Fee with the limit tightened to the consumed units plus 10%, in
+    /// lamports
+    pub fee_at_recommended_limit: Option<u64>,
+    /// Transaction or RPC error
+    pub error: Option<String>,
+    /// RPC requests retried for this input
+    pub retries: u32,
+}
+
+/// The verify transaction encoded as a legacy and as a v0 transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransactionEncodings {
+    /// Address lookup tables the v0 transaction resolves accounts through
+    pub lookup_tables: Vec<String>,
+    pub legacy: EncodedSize,
+    pub v0: EncodedSize,
+    /// Bytes saved by the v0 encoding; negative when it is larger
+    pub size_savings: i64,
+}
+
+/// Size of one encoding of the verify transaction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EncodedSize {
+    pub transaction_size: usize,
+    pub message_size: usize,
+    /// Account keys listed in the message itself
+    pub static_account_keys: usize,
+    /// Accounts resolved through lookup tables
+    pub looked_up_accounts: usize,
+}
+
+/// Fee tiers from percentiles of the recent prioritization fees
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PriorityFeeEstimates {
+    /// Writable accounts of the verifier instruction the fees were sampled
+    /// for; empty when sampled across the cluster
+    pub accounts: Vec<String>,
+    /// Number of recent slots sampled
+    pub sample_count: usize,
+    /// Compute units consumed by the simulation
+    pub units_consumed: u64,
+    /// Compute unit limit the `*_at_recommended` fees assume
+    pub recommended_cu_limit: u32,
+    /// One tier per percentile: p25, p50, p75 and p95
+    pub tiers: Vec<PriorityFeeTier>,
+}
+
+/// Fees in lamports when paying one percentile of the recent fees
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PriorityFeeTier {
+    /// Percentile of the sampled fees, e.g. 75 for p75
+    pub percentile: u8,
+    pub cu_price_microlamports: u64,
+    /// Prioritization fee with the limit set to the consumed compute units
+    pub prioritization_fee_at_consumed: u64,
+    /// Base plus prioritization fee with the limit set to the consumed compute units
+    pub total_fee_at_consumed: u64,
+    /// Prioritization fee with the recommended compute unit limit
+    pub prioritization_fee_at_recommended: u64,
+    /// Base plus prioritization fee with the recommended compute unit limit
+    pub total_fee_at_recommended: u64,
+}
+
+/// JSON Schema describing [`SimulationReport`]
+pub fn report_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(SimulationReport))
+        .expect("report schema is valid JSON")
+}
+
+/// Actions for the report command
+pub enum ReportAction {
+    Schema,
+}
+
+/// Run the report command
+pub fn run_report(action: ReportAction) -> error::Result<()> {
+    match action {
+        ReportAction::Schema => {
+            let schema = report_schema();
+            if ui::is_json_output() {
+                ui::emit_json(&schema);
+            } else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&schema).unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_schema_is_up_to_date() {
+        let published: serde_json::Value =
+            serde_json::from_str(include_str!("../../docs/report.schema.json")).unwrap();
+        assert_eq!(
+            published,
+            report_schema(),
+            "docs/report.schema.json is stale; regenerate it with `zklense report schema`"
+        );
+    }
+}
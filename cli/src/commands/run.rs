@@ -1,14 +1,20 @@
 use console::style;
 use serde::Deserialize;
+use serde_json::json;
 use std::fs;
 use std::io;
 use std::path::Path;
 use std::process::{Command, Stdio};
 use std::time::Instant;
 
+use super::manifest::{BuildManifest, fingerprint};
+use crate::error::{self, ZkLenseError};
 use crate::ui::{self, emoji};
+use crate::ui_println;
 
 const NARGO_TOML: &str = "Nargo.toml";
+const PROVER_TOML: &str = "Prover.toml";
+const SRC_DIR: &str = "src";
 const TARGET_DIR: &str = "target";
 
 /// Structure to parse Nargo.toml
@@ -27,7 +33,7 @@ struct NargoPackage {
 }
 
 /// Check if a command exists in PATH
-fn command_exists(cmd: &str) -> bool {
+pub fn command_exists(cmd: &str) -> bool {
     Command::new("which")
         .arg(cmd)
         .stdout(Stdio::null())
@@ -38,26 +44,20 @@ fn command_exists(cmd: &str) -> bool {
 }
 
 /// Read and parse Nargo.toml to get the circuit name
-fn read_circuit_name(base_path: &Path) -> io::Result<String> {
+pub fn read_circuit_name(base_path: &Path) -> io::Result<String> {
     let nargo_path = base_path.join(NARGO_TOML);
 
     if !nargo_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!(
-                "Nargo.toml not found at: {}\nMake sure you are in a Noir project directory.",
-                nargo_path.display()
-            ),
-        ));
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "Nargo.toml not found at: {}\nMake sure you are in a Noir project directory.",
+            nargo_path.display()
+        ))
+        .into());
     }
 
     let contents = fs::read_to_string(&nargo_path)?;
-    let nargo_toml: NargoToml = toml::from_str(&contents).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Failed to parse Nargo.toml: {}", e),
-        )
-    })?;
+    let nargo_toml: NargoToml = toml::from_str(&contents)
+        .map_err(|e| ZkLenseError::Config(format!("Failed to parse Nargo.toml: {}", e)))?;
 
     Ok(nargo_toml.package.name)
 }
@@ -87,15 +87,21 @@ fn run_command_with_spinner(
         if !stderr.is_empty() {
             ui::blank();
             for line in stderr.lines().take(10) {
-                println!("    {}", style(line).red().dim());
+                ui_println!("    {}", style(line).red().dim());
             }
         }
 
-        return Err(io::Error::other(format!(
+        let mut message = format!(
             "Command '{}' failed with exit code: {:?}",
             cmd,
             output.status.code()
-        )));
+        );
+        // Without the terminal output, keep the tool's stderr in the error itself
+        if ui::is_json_output() && !stderr.is_empty() {
+            message.push('\n');
+            message.push_str(stderr.trim_end());
+        }
+        return Err(ZkLenseError::CommandFailed(message).into());
     }
 
     ui::spinner_success_with_duration(&spinner, &message.replace("...", ""), duration);
@@ -117,12 +123,13 @@ fn run_command_capture(cmd: &str, args: &[&str], working_dir: &Path) -> io::Resu
             &spinner,
             &format!("Command failed: {} {}", cmd, args.join(" ")),
         );
-        return Err(io::Error::other(format!(
+        return Err(ZkLenseError::CommandFailed(format!(
             "Command '{}' failed with exit code: {:?}\n{}",
             cmd,
             output.status.code(),
             stderr
-        )));
+        ))
+        .into());
     }
 
     ui::spinner_success(&spinner, &format!("{} {}", cmd, args.join(" ")));
@@ -136,6 +143,15 @@ struct PipelineStep {
     command: &'static str,
     args_fn: fn(&str) -> Vec<String>,
     working_dir_is_target: bool,
+    /// Files (or directories) the step reads, relative to the project root
+    inputs_fn: fn(&str) -> Vec<String>,
+    /// Files the step writes, relative to the project root
+    outputs_fn: fn(&str) -> Vec<String>,
+}
+
+/// Path of a circuit artifact relative to the project root
+pub fn target_file(circuit: &str, extension: &str) -> String {
+    format!("{}/{}.{}", TARGET_DIR, circuit, extension)
 }
 
 /// Get all pipeline steps
@@ -147,6 +163,14 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
             command: "nargo",
             args_fn: |_| vec!["execute".to_string()],
             working_dir_is_target: false,
+            inputs_fn: |_| {
+                vec![
+                    NARGO_TOML.to_string(),
+                    PROVER_TOML.to_string(),
+                    SRC_DIR.to_string(),
+                ]
+            },
+            outputs_fn: |circuit| vec![target_file(circuit, "json"), target_file(circuit, "gz")],
         },
         PipelineStep {
             name: "Compile",
@@ -154,6 +178,8 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
             command: "sunspot",
             args_fn: |circuit| vec!["compile".to_string(), format!("{}.json", circuit)],
             working_dir_is_target: true,
+            inputs_fn: |circuit| vec![target_file(circuit, "json")],
+            outputs_fn: |circuit| vec![target_file(circuit, "ccs")],
         },
         PipelineStep {
             name: "Setup",
@@ -161,6 +187,8 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
             command: "sunspot",
             args_fn: |circuit| vec!["setup".to_string(), format!("{}.ccs", circuit)],
             working_dir_is_target: true,
+            inputs_fn: |circuit| vec![target_file(circuit, "ccs")],
+            outputs_fn: |circuit| vec![target_file(circuit, "pk"), target_file(circuit, "vk")],
         },
         PipelineStep {
             name: "Prove",
@@ -176,6 +204,15 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
                 ]
             },
             working_dir_is_target: true,
+            inputs_fn: |circuit| {
+                vec![
+                    target_file(circuit, "json"),
+                    target_file(circuit, "gz"),
+                    target_file(circuit, "ccs"),
+                    target_file(circuit, "pk"),
+                ]
+            },
+            outputs_fn: |circuit| vec![target_file(circuit, "proof"), target_file(circuit, "pw")],
         },
         PipelineStep {
             name: "Verify",
@@ -190,6 +227,14 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
                 ]
             },
             working_dir_is_target: true,
+            inputs_fn: |circuit| {
+                vec![
+                    target_file(circuit, "vk"),
+                    target_file(circuit, "proof"),
+                    target_file(circuit, "pw"),
+                ]
+            },
+            outputs_fn: |_| vec![],
         },
         PipelineStep {
             name: "Deploy",
@@ -197,30 +242,144 @@ fn get_pipeline_steps() -> Vec<PipelineStep> {
             command: "sunspot",
             args_fn: |circuit| vec!["deploy".to_string(), format!("{}.vk", circuit)],
             working_dir_is_target: true,
+            inputs_fn: |circuit| vec![target_file(circuit, "vk")],
+            outputs_fn: |circuit| vec![target_file(circuit, "so")],
         },
     ]
 }
 
-/// Check prerequisites before running the pipeline
-fn check_prerequisites() -> io::Result<()> {
-    ui::section(emoji::SEARCH, "Checking Prerequisites");
+/// Which pipeline steps `zklense run` should execute, by step name
+#[derive(Debug, Default)]
+pub struct StepSelection {
+    /// First step to run (inclusive)
+    pub from: Option<String>,
+    /// Last step to run (inclusive)
+    pub to: Option<String>,
+    /// Run exactly these steps, in pipeline order
+    pub only: Vec<String>,
+}
 
-    let mut missing = Vec::new();
+/// Find the index of a step by name (case-insensitive)
+fn step_index(steps: &[PipelineStep], name: &str) -> io::Result<usize> {
+    steps
+        .iter()
+        .position(|step| step.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let valid: Vec<&str> = steps.iter().map(|step| step.name).collect();
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown pipeline step '{}'. Valid steps: {}",
+                    name,
+                    valid.join(", ")
+                ),
+            )
+        })
+}
 
-    // Check nargo
-    if command_exists("nargo") {
-        println!("  {} {} found", emoji::SUCCESS, style("nargo").green());
+/// Narrow the pipeline down to the steps requested on the command line
+fn select_steps(
+    steps: Vec<PipelineStep>,
+    selection: &StepSelection,
+) -> io::Result<Vec<PipelineStep>> {
+    let selected: Vec<bool> = if !selection.only.is_empty() {
+        let mut selected = vec![false; steps.len()];
+        for name in &selection.only {
+            selected[step_index(&steps, name)?] = true;
+        }
+        selected
     } else {
-        println!("  {} {} not found", emoji::ERROR, style("nargo").red());
-        missing.push("nargo");
+        let from = match &selection.from {
+            Some(name) => step_index(&steps, name)?,
+            None => 0,
+        };
+        let to = match &selection.to {
+            Some(name) => step_index(&steps, name)?,
+            None => steps.len() - 1,
+        };
+        if from > to {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "--from {} comes after --to {} in the pipeline",
+                    steps[from].name, steps[to].name
+                ),
+            ));
+        }
+        (0..steps.len()).map(|i| i >= from && i <= to).collect()
+    };
+
+    Ok(steps
+        .into_iter()
+        .zip(selected)
+        .filter_map(|(step, keep)| keep.then_some(step))
+        .collect())
+}
+
+/// Check that every artifact a selected step needs from an unselected
+/// upstream step is already present in `target/`
+fn check_upstream_artifacts(
+    base_path: &Path,
+    circuit_name: &str,
+    selected: &[PipelineStep],
+) -> io::Result<()> {
+    let all_steps = get_pipeline_steps();
+    let mut produced: Vec<String> = Vec::new();
+
+    for step in selected {
+        for input in (step.inputs_fn)(circuit_name) {
+            let producer = all_steps
+                .iter()
+                .find(|s| (s.outputs_fn)(circuit_name).contains(&input));
+            let Some(producer) = producer else {
+                continue;
+            };
+            if produced.contains(&input) || base_path.join(&input).is_file() {
+                continue;
+            }
+
+            let missing = base_path.join(&input);
+            ui::panel_error(
+                "MISSING ARTIFACT",
+                &format!(
+                    "Step '{}' requires {}\nwhich is produced by the '{}' step.",
+                    step.name,
+                    missing.display(),
+                    producer.name
+                ),
+                None,
+                Some(&[&format!("Include it: zklense run --from {}", producer.name)]),
+            );
+            return Err(ZkLenseError::MissingArtifact(format!(
+                "Missing artifact for step '{}': {} (run the '{}' step first)",
+                step.name,
+                missing.display(),
+                producer.name
+            ))
+            .into());
+        }
+        produced.extend((step.outputs_fn)(circuit_name));
     }
 
-    // Check sunspot
-    if command_exists("sunspot") {
-        println!("  {} {} found", emoji::SUCCESS, style("sunspot").green());
-    } else {
-        println!("  {} {} not found", emoji::ERROR, style("sunspot").red());
-        missing.push("sunspot");
+    Ok(())
+}
+
+/// Check that the commands used by the selected steps are installed
+fn check_prerequisites(steps: &[PipelineStep]) -> io::Result<()> {
+    ui::section(emoji::SEARCH, "Checking Prerequisites");
+
+    let mut missing = Vec::new();
+
+    for cmd in ["nargo", "sunspot"] {
+        if !steps.iter().any(|step| step.command == cmd) {
+            continue;
+        }
+        if command_exists(cmd) {
+            ui_println!("  {} {} found", emoji::SUCCESS, style(cmd).green());
+        } else {
+            ui_println!("  {} {} not found", emoji::ERROR, style(cmd).red());
+            missing.push(cmd);
+        }
     }
 
     ui::blank();
@@ -248,10 +407,11 @@ fn check_prerequisites() -> io::Result<()> {
             Some(&suggestions.iter().map(|s| s.as_ref()).collect::<Vec<_>>()),
         );
 
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Missing required commands: {}", missing.join(", ")),
-        ));
+        return Err(ZkLenseError::MissingTool(format!(
+            "Missing required commands: {}",
+            missing.join(", ")
+        ))
+        .into());
     }
 
     Ok(())
@@ -274,16 +434,18 @@ fn handle_missing_sunspot() -> io::Result<()> {
     if !should_install {
         ui::info("You can install Sunspot manually from: https://github.com/reilabs/sunspot");
         ui::blank();
-        println!("  {} Installation steps:", emoji::BULB);
-        println!("     1. git clone https://github.com/reilabs/sunspot.git ~/sunspot");
-        println!("     2. cd ~/sunspot/go && go build -o sunspot .");
-        println!("     3. sudo mv sunspot /usr/local/bin/");
-        println!("     4. export GNARK_VERIFIER_BIN=\"$HOME/sunspot/gnark-solana/crates/verifier-bin\"");
+        ui_println!("  {} Installation steps:", emoji::BULB);
+        ui_println!("     1. git clone https://github.com/reilabs/sunspot.git ~/sunspot");
+        ui_println!("     2. cd ~/sunspot/go && go build -o sunspot .");
+        ui_println!("     3. sudo mv sunspot /usr/local/bin/");
+        ui_println!(
+            "     4. export GNARK_VERIFIER_BIN=\"$HOME/sunspot/gnark-solana/crates/verifier-bin\""
+        );
         ui::blank();
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Sunspot is required. Install it and try again.",
-        ));
+        return Err(ZkLenseError::MissingTool(
+            "Sunspot is required. Install it and try again.".to_string(),
+        )
+        .into());
     }
 
     ui::blank();
@@ -306,10 +468,10 @@ fn handle_missing_sunspot() -> io::Result<()> {
             "INSTALLATION COMPLETE",
             "Sunspot has been built. Please restart your terminal or run:\n\n  source ~/.zshrc  (or ~/.bashrc)\n\nThen run 'zklense run' again.",
         );
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Please restart your terminal and try again.",
-        ))
+        Err(ZkLenseError::MissingTool(
+            "Sunspot is not on PATH yet. Please restart your terminal and try again.".to_string(),
+        )
+        .into())
     }
 }
 
@@ -321,25 +483,25 @@ fn check_sunspot_prerequisites() -> io::Result<()> {
 
     // Check Go (required for sunspot)
     if command_exists("go") {
-        println!("  {} {} found", emoji::SUCCESS, style("go").green());
+        ui_println!("  {} {} found", emoji::SUCCESS, style("go").green());
     } else {
-        println!("  {} {} not found", emoji::ERROR, style("go").red());
+        ui_println!("  {} {} not found", emoji::ERROR, style("go").red());
         missing_prereqs.push("go");
     }
 
     // Check Rust/Cargo (required for gnark-solana verifier)
     if command_exists("cargo") {
-        println!("  {} {} found", emoji::SUCCESS, style("cargo/rust").green());
+        ui_println!("  {} {} found", emoji::SUCCESS, style("cargo/rust").green());
     } else {
-        println!("  {} {} not found", emoji::ERROR, style("cargo/rust").red());
+        ui_println!("  {} {} not found", emoji::ERROR, style("cargo/rust").red());
         missing_prereqs.push("rust");
     }
 
     // Check git (required for cloning)
     if command_exists("git") {
-        println!("  {} {} found", emoji::SUCCESS, style("git").green());
+        ui_println!("  {} {} found", emoji::SUCCESS, style("git").green());
     } else {
-        println!("  {} {} not found", emoji::ERROR, style("git").red());
+        ui_println!("  {} {} not found", emoji::ERROR, style("git").red());
         missing_prereqs.push("git");
     }
 
@@ -375,25 +537,33 @@ fn install_sunspot_prerequisites(missing: &[&str]) -> io::Result<()> {
         for prereq in missing {
             match *prereq {
                 "go" => {
-                    println!("  {} Go (1.24+): https://go.dev/doc/install", emoji::ARROW_RIGHT);
-                    println!("     Or on macOS: brew install go");
+                    ui_println!(
+                        "  {} Go (1.24+): https://go.dev/doc/install",
+                        emoji::ARROW_RIGHT
+                    );
+                    ui_println!("     Or on macOS: brew install go");
                 }
                 "rust" => {
-                    println!("  {} Rust: https://rustup.rs/", emoji::ARROW_RIGHT);
-                    println!("     Run: curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh");
+                    ui_println!("  {} Rust: https://rustup.rs/", emoji::ARROW_RIGHT);
+                    ui_println!(
+                        "     Run: curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh"
+                    );
                 }
                 "git" => {
-                    println!("  {} Git: https://git-scm.com/downloads", emoji::ARROW_RIGHT);
-                    println!("     Or on macOS: xcode-select --install");
+                    ui_println!(
+                        "  {} Git: https://git-scm.com/downloads",
+                        emoji::ARROW_RIGHT
+                    );
+                    ui_println!("     Or on macOS: xcode-select --install");
                 }
                 _ => {}
             }
         }
         ui::blank();
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Missing prerequisites. Install them and try again.",
-        ));
+        return Err(ZkLenseError::MissingTool(
+            "Missing prerequisites. Install them and try again.".to_string(),
+        )
+        .into());
     }
 
     ui::blank();
@@ -413,10 +583,10 @@ fn install_sunspot_prerequisites(missing: &[&str]) -> io::Result<()> {
                         "Download: https://git-scm.com/downloads",
                     ]),
                 );
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Git is required. Please install it manually.",
-                ));
+                return Err(ZkLenseError::MissingTool(
+                    "Git is required. Please install it manually.".to_string(),
+                )
+                .into());
             }
             _ => {}
         }
@@ -451,6 +621,13 @@ fn install_go() -> io::Result<()> {
         "Please install Go (1.24+) from:\n\nhttps://go.dev/doc/install\n\nOr use your package manager:\n  • macOS: brew install go\n  • Ubuntu: sudo apt install golang-go\n  • Fedora: sudo dnf install golang",
     );
 
+    if !ui::is_interactive() {
+        return Err(ZkLenseError::MissingTool(
+            "Go is not installed. Install it and run again.".to_string(),
+        )
+        .into());
+    }
+
     // Wait for user to confirm they've installed Go
     ui::info("Press Enter after installing Go to continue...");
     let mut input = String::new();
@@ -460,10 +637,10 @@ fn install_go() -> io::Result<()> {
         ui::success("Go is now available");
         Ok(())
     } else {
-        Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Go is still not found. Please install it and try again.",
-        ))
+        Err(ZkLenseError::MissingTool(
+            "Go is still not found. Please install it and try again.".to_string(),
+        )
+        .into())
     }
 }
 
@@ -497,10 +674,10 @@ fn install_rust() -> io::Result<()> {
             "Please install Rust from:\n\nhttps://rustup.rs/\n\nRun:\n  curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh",
         );
 
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to install Rust. Please install it manually.",
-        ))
+        Err(ZkLenseError::CommandFailed(
+            "Failed to install Rust. Please install it manually.".to_string(),
+        )
+        .into())
     }
 }
 
@@ -522,10 +699,11 @@ fn install_sunspot() -> io::Result<()> {
         if !output.status.success() {
             ui::spinner_error(&spinner, "Failed to clone Sunspot repository");
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to clone repository: {}", stderr),
-            ));
+            return Err(ZkLenseError::CommandFailed(format!(
+                "Failed to clone repository: {}",
+                stderr
+            ))
+            .into());
         }
 
         ui::spinner_success(&spinner, "Cloned Sunspot repository");
@@ -552,10 +730,9 @@ fn install_sunspot() -> io::Result<()> {
     if !output.status.success() {
         ui::spinner_error(&spinner, "Failed to build Sunspot");
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to build Sunspot: {}", stderr),
-        ));
+        return Err(
+            ZkLenseError::CommandFailed(format!("Failed to build Sunspot: {}", stderr)).into(),
+        );
     }
 
     ui::spinner_success(&spinner, "Built Sunspot");
@@ -632,10 +809,10 @@ fn add_to_shell_path(dir: &str) -> io::Result<()> {
     let export_line = format!("\n# Added by zklense for Sunspot\nexport PATH=\"{}:$PATH\"\n", dir);
 
     // Check if already in config
-    if let Ok(contents) = fs::read_to_string(&config_file) {
-        if contents.contains(dir) {
-            return Ok(());
-        }
+    if let Ok(contents) = fs::read_to_string(&config_file)
+        && contents.contains(dir)
+    {
+        return Ok(());
     }
 
     // Append to config file
@@ -666,10 +843,10 @@ fn set_gnark_verifier_bin_env(verifier_path: &str) -> io::Result<()> {
     let export_line = format!("\n# Sunspot GNARK verifier path (added by zklense)\nexport GNARK_VERIFIER_BIN=\"{}\"\n", verifier_path);
 
     // Check if already in config
-    if let Ok(contents) = fs::read_to_string(&config_file) {
-        if contents.contains("GNARK_VERIFIER_BIN") {
-            return Ok(());
-        }
+    if let Ok(contents) = fs::read_to_string(&config_file)
+        && contents.contains("GNARK_VERIFIER_BIN")
+    {
+        return Ok(());
     }
 
     // Append to config file
@@ -685,8 +862,15 @@ fn set_gnark_verifier_bin_env(verifier_path: &str) -> io::Result<()> {
     Ok(())
 }
 
-/// Run the full proof generation pipeline
-pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
+/// Run the proof generation pipeline.
+///
+/// Only the steps in `selection` are run, and of those, steps whose inputs
+/// and outputs match the build manifest are skipped unless `force` is set.
+pub fn run_pipeline(
+    path: Option<String>,
+    force: bool,
+    selection: StepSelection,
+) -> error::Result<()> {
     // Resolve base path
     let base_path = match path {
         Some(p) => {
@@ -707,10 +891,10 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
             None,
             None,
         );
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Path does not exist: {}", base_path.display()),
-        ));
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Path does not exist: {}",
+            base_path.display()
+        )));
     }
 
     // Check for Nargo.toml and read circuit name
@@ -727,24 +911,31 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
         )),
     );
 
+    // Get the selected pipeline steps
+    let steps = select_steps(get_pipeline_steps(), &selection).inspect_err(|e| {
+        ui::panel_error("INVALID STEP SELECTION", &e.to_string(), None, None);
+    })?;
+    let total_steps = steps.len();
+    let runs_deploy = steps.iter().any(|step| step.name == "Deploy");
+
     // Check prerequisites
-    check_prerequisites()?;
+    check_prerequisites(&steps)?;
+    check_upstream_artifacts(&base_path, &circuit_name, &steps)?;
 
     // Ensure target directory exists (will be created by nargo execute)
     let target_dir = base_path.join(TARGET_DIR);
 
-    // Get pipeline steps
-    let steps = get_pipeline_steps();
-    let total_steps = steps.len();
-
     // Print pipeline overview
-    ui::section(
-        emoji::PIN,
-        &format!("Build Pipeline ({} steps)", total_steps),
-    );
+    let pipeline_len = get_pipeline_steps().len();
+    let overview = if total_steps == pipeline_len {
+        format!("Build Pipeline ({} steps)", total_steps)
+    } else {
+        format!("Build Pipeline ({} of {} steps)", total_steps, pipeline_len)
+    };
+    ui::section(emoji::PIN, &overview);
 
     for (i, step) in steps.iter().enumerate() {
-        println!(
+        ui_println!(
             "  {} [{}] {}",
             emoji::PENDING,
             style(format!("{}/{}", i + 1, total_steps)).dim(),
@@ -756,6 +947,9 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
     // Execute pipeline
     ui::divider();
     let mut step_durations: Vec<(&str, u128)> = Vec::new();
+    let mut step_results: Vec<serde_json::Value> = Vec::new();
+    let mut manifest = BuildManifest::load(&base_path);
+    let mut skipped_steps = 0;
 
     for (i, step) in steps.iter().enumerate() {
         let step_num = i + 1;
@@ -769,13 +963,10 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
                     None,
                     Some(&["Run 'nargo execute' first"]),
                 );
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!(
-                        "Target directory not found: {}\nRun 'nargo execute' first.",
-                        target_dir.display()
-                    ),
-                ));
+                return Err(ZkLenseError::MissingArtifact(format!(
+                    "Target directory not found: {}\nRun 'nargo execute' first.",
+                    target_dir.display()
+                )));
             }
             target_dir.clone()
         } else {
@@ -784,11 +975,52 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
 
         let args_vec = (step.args_fn)(&circuit_name);
         let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+        let command_line = format!("{} {}", step.command, args.join(" "));
+
+        // Skip the step if nothing it depends on has changed since the last run
+        let inputs = fingerprint(&base_path, &(step.inputs_fn)(&circuit_name))?;
+        let outputs = (step.outputs_fn)(&circuit_name);
+        if !force && manifest.is_fresh(&base_path, step.name, &command_line, &inputs, &outputs)? {
+            ui::step_skipped(&format!(
+                "[{}/{}] {}",
+                step_num, total_steps, step.description
+            ));
+            skipped_steps += 1;
+            step_results.push(json!({
+                "name": step.name,
+                "command": command_line,
+                "status": "up_to_date",
+                "duration_ms": 0,
+            }));
+            continue;
+        }
 
         let step_message = format!("[{}/{}] {}...", step_num, total_steps, step.description);
 
-        let duration = run_command_with_spinner(step.command, &args, &working_dir, &step_message)?;
+        manifest.invalidate(step.name);
+        let duration = run_command_with_spinner(step.command, &args, &working_dir, &step_message);
+        let duration = match duration {
+            Ok(duration) => duration,
+            Err(e) => {
+                manifest.save(&base_path)?;
+                return Err(e.into());
+            }
+        };
         step_durations.push((step.name, duration));
+        step_results.push(json!({
+            "name": step.name,
+            "command": command_line,
+            "status": "ran",
+            "duration_ms": duration,
+        }));
+
+        manifest.record(
+            step.name,
+            &command_line,
+            inputs,
+            fingerprint(&base_path, &outputs)?,
+        );
+        manifest.save(&base_path)?;
     }
 
     ui::divider();
@@ -796,123 +1028,201 @@ pub fn run_pipeline(path: Option<String>) -> io::Result<()> {
 
     // Success panel
     let total_duration: u128 = step_durations.iter().map(|(_, d)| d).sum();
-    ui::panel_success(
-        "BUILD COMPLETE",
-        &format!(
+    let summary = if skipped_steps > 0 {
+        format!(
+            "Pipeline completed successfully in {:.2}s\n{} of {} steps up to date (use --force to rebuild)",
+            total_duration as f64 / 1000.0,
+            skipped_steps,
+            total_steps
+        )
+    } else {
+        format!(
             "Pipeline completed successfully in {:.2}s",
             total_duration as f64 / 1000.0
-        ),
-    );
+        )
+    };
+    ui::panel_success("BUILD COMPLETE", &summary);
 
     // Generated files section
     ui::section(emoji::FOLDER, "Generated Files");
 
-    let file_ccs = format!("{}.ccs", circuit_name);
-    let file_pk = format!("{}.pk", circuit_name);
-    let file_vk = format!("{}.vk", circuit_name);
-    let file_proof = format!("{}.proof", circuit_name);
-    let file_pw = format!("{}.pw", circuit_name);
-    let file_so = format!("{}.so", circuit_name);
-
-    let files: Vec<(&str, &str)> = vec![
-        (&file_ccs, "Compiled circuit"),
-        (&file_pk, "Proving key"),
-        (&file_vk, "Verifying key"),
-        (&file_proof, "Groth16 proof"),
-        (&file_pw, "Public witness"),
-        (&file_so, "Solana program"),
+    let files: [(&str, &str); 6] = [
+        ("ccs", "Compiled circuit"),
+        ("pk", "Proving key"),
+        ("vk", "Verifying key"),
+        ("proof", "Groth16 proof"),
+        ("pw", "Public witness"),
+        ("so", "Solana program"),
     ];
 
-    for (file, desc) in &files {
-        let file_path = target_dir.join(file);
-        let exists = file_path.exists();
+    let mut artifacts: Vec<serde_json::Value> = Vec::new();
+    for (extension, desc) in &files {
+        let file = format!("{}.{}", circuit_name, extension);
+        let file_path = target_dir.join(&file);
+        let size = fs::metadata(&file_path).ok().map(|m| m.len());
+        let exists = size.is_some();
         let icon = if exists {
             emoji::SUCCESS
         } else {
             emoji::PENDING
         };
         let file_style = if exists {
-            style(*file).green().to_string()
+            style(&file).green().to_string()
         } else {
-            style(*file).dim().to_string()
+            style(&file).dim().to_string()
         };
-        println!("  {} {:<20} {}", icon, file_style, style(*desc).dim());
+        ui_println!("  {} {:<20} {}", icon, file_style, style(*desc).dim());
+        artifacts.push(json!({
+            "file": file,
+            "description": desc,
+            "path": file_path.display().to_string(),
+            "exists": exists,
+            "size_bytes": size,
+        }));
     }
     ui::blank();
 
-    // Prompt user to deploy the Solana program
+    // Deployment is part of the Deploy step; partial runs without it skip it
+    let program_id = if runs_deploy {
+        deploy_program(&circuit_name, &target_dir)?
+    } else {
+        None
+    };
+
+    ui::emit_json(&json!({
+        "command": "run",
+        "status": "success",
+        "circuit": circuit_name,
+        "path": base_path.display().to_string(),
+        "steps": step_results,
+        "steps_up_to_date": skipped_steps,
+        "total_duration_ms": total_duration,
+        "artifacts": artifacts,
+        "program_id": program_id,
+    }));
+
+    Ok(())
+}
+
+/// Offer to deploy the generated Solana program. Returns the program ID if
+/// it was deployed.
+fn deploy_program(circuit_name: &str, target_dir: &Path) -> error::Result<Option<String>> {
     let program_path = target_dir.join(format!("{}.so", circuit_name));
 
-    if program_path.exists() {
-        ui::section(emoji::ROCKET, "Solana Program Deployment");
-        println!(
-            "  {} Program file: {}",
-            emoji::FILE,
+    if !program_path.exists() {
+        ui::warn(&format!(
+            "No .so file found at: {}",
             style(program_path.display()).dim()
+        ));
+        return Ok(None);
+    }
+
+    ui::section(emoji::ROCKET, "Solana Program Deployment");
+    ui_println!(
+        "  {} Program file: {}",
+        emoji::FILE,
+        style(program_path.display()).dim()
+    );
+    ui::blank();
+
+    // Interactive selection for deployment
+    let should_deploy = ui::confirm_custom(
+        "Deploy the Solana program?",
+        &format!("{} Yes, deploy now", emoji::CHECKMARK),
+        &format!("{} No, skip deployment", emoji::CROSSMARK),
+    )?;
+
+    if !should_deploy {
+        ui::info("Deployment skipped. You can deploy later with:");
+        ui_println!(
+            "  {} solana program deploy {}",
+            emoji::ARROW_RIGHT,
+            style(program_path.display()).cyan()
         );
         ui::blank();
+        return Ok(None);
+    }
 
-        // Interactive selection for deployment
-        let should_deploy = ui::confirm_custom(
-            "Deploy the Solana program?",
-            &format!("{} Yes, deploy now", emoji::CHECKMARK),
-            &format!("{} No, skip deployment", emoji::CROSSMARK),
-        )?;
+    ui::blank();
 
-        if should_deploy {
-            ui::blank();
+    // Check if solana CLI exists
+    if !command_exists("solana") {
+        ui::panel_error(
+            "SOLANA CLI NOT FOUND",
+            "The Solana CLI is required to deploy programs.",
+            None,
+            Some(&["Install from: https://docs.solana.com/cli/install-solana-cli-tools"]),
+        );
+        return Err(ZkLenseError::MissingTool(
+            "Solana CLI not found".to_string(),
+        ));
+    }
 
-            // Check if solana CLI exists
-            if !command_exists("solana") {
-                ui::panel_error(
-                    "SOLANA CLI NOT FOUND",
-                    "The Solana CLI is required to deploy programs.",
-                    None,
-                    Some(&["Install from: https://docs.solana.com/cli/install-solana-cli-tools"]),
-                );
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Solana CLI not found",
-                ));
-            }
+    let output = run_command_capture(
+        "solana",
+        &["program", "deploy", program_path.to_str().unwrap()],
+        target_dir,
+    )?;
 
-            let output = run_command_capture(
-                "solana",
-                &["program", "deploy", program_path.to_str().unwrap()],
-                &target_dir,
-            )?;
+    // Parse Program ID from output (format: "Program Id: <address>")
+    let program_id = output
+        .lines()
+        .find(|line| line.contains("Program Id:"))
+        .and_then(|line| line.split(':').nth(1))
+        .map(|id| id.trim())
+        .unwrap_or("Unknown");
 
-            // Parse Program ID from output (format: "Program Id: <address>")
-            let program_id = output
-                .lines()
-                .find(|line| line.contains("Program Id:"))
-                .and_then(|line| line.split(':').nth(1))
-                .map(|id| id.trim())
-                .unwrap_or("Unknown");
+    ui::blank();
+    ui::panel_success(
+        "DEPLOYED",
+        &format!(
+            "Solana program deployed successfully!\n\nProgram ID:\n{}",
+            program_id
+        ),
+    );
 
-            ui::blank();
-            ui::panel_success(
-                "DEPLOYED",
-                &format!(
-                    "Solana program deployed successfully!\n\nProgram ID:\n{}",
-                    program_id
-                ),
-            );
-        } else {
-            ui::info("Deployment skipped. You can deploy later with:");
-            println!(
-                "  {} solana program deploy {}",
-                emoji::ARROW_RIGHT,
-                style(program_path.display()).cyan()
-            );
-            ui::blank();
-        }
-    } else {
-        ui::warn(&format!(
-            "No .so file found at: {}",
-            style(program_path.display()).dim()
-        ));
+    Ok(Some(program_id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected_names(selection: StepSelection) -> io::Result<Vec<&'static str>> {
+        Ok(select_steps(get_pipeline_steps(), &selection)?
+            .iter()
+            .map(|step| step.name)
+            .collect())
     }
 
-    Ok(())
+    #[test]
+    fn test_select_steps() {
+        assert_eq!(selected_names(StepSelection::default()).unwrap().len(), 6);
+
+        let range = StepSelection {
+            from: Some("prove".to_string()),
+            to: Some("Verify".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(selected_names(range).unwrap(), vec!["Prove", "Verify"]);
+
+        let only = StepSelection {
+            only: vec!["verify".to_string(), "execute".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(selected_names(only).unwrap(), vec!["Execute", "Verify"]);
+
+        let reversed = StepSelection {
+            from: Some("deploy".to_string()),
+            to: Some("setup".to_string()),
+            ..Default::default()
+        };
+        assert!(selected_names(reversed).is_err());
+
+        let unknown = StepSelection {
+            only: vec!["link".to_string()],
+            ..Default::default()
+        };
+        assert!(selected_names(unknown).is_err());
+    }
 }
@@ -0,0 +1,175 @@
+//! Statistics over repeated simulations
+//!
+//! `zklense simulate --runs N` simulates the verify transaction N times,
+//! optionally cycling through several `--proof`/`--witness` pairs, and
+//! summarizes the compute units consumed and how often runs failed. A single
+//! sample can be skewed by the slot it lands in or an RPC hiccup; transient
+//! RPC failures are retried with backoff before a run counts as failed.
+
+use comfy_table::{Cell, Color};
+use std::time::Duration;
+
+use super::report::{ComputeUnitStatistics, RunSample, RunStatistics};
+use super::simulate::format_number;
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Times an RPC request of a run is retried before the run counts as failed
+pub const MAX_RETRIES: u32 = 3;
+
+/// Delay before retry `attempt` (1-based): 0.5s, 1s, 2s, ...
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500 << (attempt.saturating_sub(1)).min(4))
+}
+
+/// Median of sorted values, the mean of the middle two for an even count
+fn median(sorted: &[u64]) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        n if n.is_multiple_of(2) => (sorted[n / 2 - 1] + sorted[n / 2]) / 2,
+        n => sorted[n / 2],
+    }
+}
+
+/// Distribution of compute units consumed, `None` without any
+pub fn compute_unit_statistics(mut units: Vec<u64>) -> Option<ComputeUnitStatistics> {
+    if units.is_empty() {
+        return None;
+    }
+    units.sort_unstable();
+    let count = units.len() as f64;
+    let mean = units.iter().sum::<u64>() as f64 / count;
+    let variance = units
+        .iter()
+        .map(|&u| (u as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    Some(ComputeUnitStatistics {
+        min: units[0],
+        median: median(&units),
+        max: units[units.len() - 1],
+        mean,
+        variance,
+        std_deviation: variance.sqrt(),
+    })
+}
+
+/// Summarize the runs in order
+pub fn summarize(samples: Vec<RunSample>) -> RunStatistics {
+    let failed = samples.iter().filter(|s| s.error.is_some()).count();
+    RunStatistics {
+        runs: samples.len(),
+        succeeded: samples.len() - failed,
+        failed,
+        failure_rate: if samples.is_empty() {
+            0.0
+        } else {
+            failed as f64 / samples.len() as f64
+        },
+        retries: samples.iter().map(|s| s.retries).sum(),
+        compute_units: compute_unit_statistics(
+            samples
+                .iter()
+                .filter(|sample| sample.error.is_none())
+                .filter_map(|sample| sample.units_consumed)
+                .collect(),
+        ),
+        samples,
+    }
+}
+
+/// Print every run and the statistics over them
+pub fn print_statistics(statistics: &RunStatistics) {
+    ui::section(emoji::CHART, "Runs");
+    let mut table = ui::create_table(&["#", "Proof", "Compute Units", "Retries", "Status"]);
+    for sample in &statistics.samples {
+        let proof = std::path::Path::new(&sample.proof_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| sample.proof_path.clone());
+        let status = match &sample.error {
+            None => Cell::new("ok").fg(Color::Green),
+            Some(error) => Cell::new(error).fg(Color::Red),
+        };
+        table.add_row(vec![
+            Cell::new(sample.run),
+            Cell::new(proof),
+            Cell::new(
+                sample
+                    .units_consumed
+                    .map(format_number)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Cell::new(sample.retries),
+            status,
+        ]);
+    }
+    ui_println!("{table}");
+
+    let failures = format!(
+        "{} of {} ({:.1}%)",
+        statistics.failed,
+        statistics.runs,
+        statistics.failure_rate * 100.0
+    );
+    let mut items = Vec::new();
+    if let Some(cu) = &statistics.compute_units {
+        items.push(("Min CU", format_number(cu.min)));
+        items.push(("Median CU", format_number(cu.median)));
+        items.push(("Max CU", format_number(cu.max)));
+        items.push(("Mean CU", format_number(cu.mean.round() as u64)));
+        items.push((
+            "Variance",
+            format!("{:.1} (std dev {:.1})", cu.variance, cu.std_deviation),
+        ));
+    }
+    items.push(("Failures", failures));
+    items.push(("Retries", statistics.retries.to_string()));
+    let items: Vec<(&str, &str)> = items.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    ui::print_tree(&items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(run: usize, units_consumed: Option<u64>, error: Option<&str>) -> RunSample {
+        RunSample {
+            run,
+            proof_path: "target/demo.proof".to_string(),
+            witness_path: "target/demo.pw".to_string(),
+            units_consumed,
+            error: error.map(|e| e.to_string()),
+            retries: 1,
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_statistics_skip_failed_runs() {
+        let statistics = summarize(vec![
+            sample(1, Some(100), None),
+            sample(2, Some(300), None),
+            sample(3, Some(999_999), Some("ProgramFailedToComplete")),
+            sample(4, Some(200), None),
+            sample(5, Some(400), None),
+        ]);
+        assert_eq!(statistics.runs, 5);
+        assert_eq!(statistics.failed, 1);
+        assert_eq!(statistics.failure_rate, 0.2);
+        assert_eq!(statistics.retries, 5);
+
+        let cu = statistics.compute_units.unwrap();
+        assert_eq!((cu.min, cu.median, cu.max), (100, 250, 400));
+        assert_eq!(cu.mean, 250.0);
+        assert_eq!(cu.variance, 12_500.0);
+
+        assert!(
+            summarize(vec![sample(1, None, Some("rpc"))])
+                .compute_units
+                .is_none()
+        );
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+    }
+}
@@ -3,26 +3,55 @@ use console::style;
 use dialoguer::Input;
 use serde_json::json;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
 use solana_commitment_config::CommitmentConfig;
 use solana_sdk::{
-    instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
-    transaction::Transaction,
+    hash::Hash,
+    instruction::Instruction,
+    message::AddressLookupTableAccount,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, VersionedTransaction},
 };
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Instant;
 
-use super::init::{get_solana_network, get_solana_rpc_url};
+use super::batch;
+use super::budget::{self, Budgets};
+use super::buffer::{self, DeliveryPlan};
+use super::compute_budget::{self, MAX_COMPUTE_UNITS};
+use super::diagnose;
+use super::fee_payer::{self, FeePayer};
+use super::history;
+use super::init::{KEYPAIR_PATH_KEY, SolanaNetwork, ZkLenseConfig, chrono_timestamp, read_config};
+use super::instruction_spec::{self, InstructionSpec};
+use super::invocations;
+use super::localnet;
+use super::manifest::{hash_bytes, hash_file};
+use super::priority_fee;
+use super::report::{
+    Accounts, BatchEntry, ComputeUnits, Cost, CuLimitRecommendation, Deserialization, Environment,
+    FeePayerInfo, HeapSearch, HeapUsage, PrioritizationFeeSample, ProofSizes, ReportMetadata,
+    ReportMode, RunSample, SCHEMA_VERSION, SimulationReport, Status, TransactionLogs,
+    TransactionSize, TransactionStatus,
+};
+use super::runs;
+use super::svm;
+use super::versioned;
+use crate::error::{self, ZkLenseError};
 use crate::ui::{self, emoji};
+use crate::ui_println;
 
 // Solana constants
 const LAMPORTS_PER_SIGNATURE: u64 = 5000;
-const MAX_COMPUTE_UNITS: u32 = 1_400_000;
 const DEFAULT_COMPUTE_UNITS: u32 = 200_000;
-const MAX_TRANSACTION_SIZE: usize = 1232;
-/// Check if an error is a 403 Forbidden error and show helpful message
-fn handle_rpc_error(error: anyhow::Error, rpc_url: &str) -> anyhow::Error {
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+/// Turn an RPC failure into a typed error, showing extra help for 403 Forbidden errors
+pub fn handle_rpc_error(error: anyhow::Error, rpc_url: &str) -> ZkLenseError {
     let error_msg = error.to_string().to_lowercase();
     let error_chain = format!("{:?}", error).to_lowercase();
     
@@ -53,13 +82,13 @@ fn handle_rpc_error(error: anyhow::Error, rpc_url: &str) -> anyhow::Error {
         );
         ui::blank();
     }
-    
-    error
+
+    ZkLenseError::Rpc(format!("{:#}", error))
 }
 
-struct ProofResult {
-    proof: Vec<u8>,
-    public_witness: Vec<u8>,
+pub struct ProofResult {
+    pub proof: Vec<u8>,
+    pub public_witness: Vec<u8>,
 }
 
 fn find_file_by_extension(extension: &str) -> Result<PathBuf> {
@@ -110,13 +139,14 @@ fn find_file_by_extension(extension: &str) -> Result<PathBuf> {
         }
     }
 
-    Err(anyhow::anyhow!(
-        "Could not find file with extension .{}",
+    Err(ZkLenseError::MissingArtifact(format!(
+        "Could not find file with extension .{}. Run 'zklense run' first to generate it.",
         extension
     ))
+    .into())
 }
 
-fn read_proof_files() -> Result<(ProofResult, PathBuf, PathBuf)> {
+pub fn read_proof_files() -> Result<(ProofResult, PathBuf, PathBuf)> {
     let spinner = ui::spinner("Searching for proof files...");
 
     let proof_path = find_file_by_extension("proof")?;
@@ -124,6 +154,14 @@ fn read_proof_files() -> Result<(ProofResult, PathBuf, PathBuf)> {
 
     ui::spinner_success(&spinner, "Found proof files");
 
+    read_proof_pair(proof_path, witness_path)
+}
+
+/// Read a proof and its public witness
+pub fn read_proof_pair(
+    proof_path: PathBuf,
+    witness_path: PathBuf,
+) -> Result<(ProofResult, PathBuf, PathBuf)> {
     let proof = fs::read(&proof_path)
         .with_context(|| format!("Failed to read proof file: {}", proof_path.display()))?;
     let public_witness = fs::read(&witness_path)
@@ -139,57 +177,147 @@ fn read_proof_files() -> Result<(ProofResult, PathBuf, PathBuf)> {
     ))
 }
 
-fn create_instruction_data(proof_result: &ProofResult) -> Vec<u8> {
+pub fn create_instruction_data(proof_result: &ProofResult) -> Vec<u8> {
     let mut instruction_data = proof_result.proof.clone();
     instruction_data.extend_from_slice(&proof_result.public_witness);
     instruction_data
 }
 
-fn parse_compute_budget_instructions(transaction: &Transaction) -> (u32, u64) {
+/// Data of the compute budget instructions in `transaction`
+fn compute_budget_data(transaction: &Transaction) -> impl Iterator<Item = &[u8]> {
+    let compute_budget_program_id = compute_budget::program_id();
+    transaction
+        .message
+        .instructions
+        .iter()
+        .filter(move |instruction| {
+            // Get program_id from account_keys using program_id_index
+            transaction
+                .message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                == Some(&compute_budget_program_id)
+        })
+        .map(|instruction| instruction.data.as_slice())
+}
+
+/// Compute unit limit and price (micro-lamports per CU) set by `transaction`
+pub fn parse_compute_budget_instructions(transaction: &Transaction) -> (u32, u64) {
     let mut cu_limit = DEFAULT_COMPUTE_UNITS; // Default CU limit
     let mut cu_price = 0u64; // Default CU price (microlamports per CU)
 
-    let compute_budget_program_id =
-        Pubkey::from_str("ComputeBudget111111111111111111111111111111").unwrap();
-
-    for instruction in &transaction.message.instructions {
-        // Get program_id from account_keys using program_id_index
-        let program_id = transaction
-            .message
-            .account_keys
-            .get(instruction.program_id_index as usize);
-        if let Some(&pid) = program_id
-            && pid == compute_budget_program_id
-        {
-            let data = &instruction.data;
-            if data.len() >= 4 {
-                let instruction_type = data[0];
-                if instruction_type == 2 && data.len() >= 8 {
-                    // setComputeUnitLimit
-                    cu_limit = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
-                } else if instruction_type == 3 && data.len() >= 12 {
-                    // setComputeUnitPrice
-                    cu_price = u64::from_le_bytes([
-                        data[4], data[5], data[6], data[7], data[8], data[9], data[10], data[11],
-                    ]);
-                }
+    for data in compute_budget_data(transaction) {
+        // Borsh encoding: a one-byte variant tag followed by the value
+        match data.first() {
+            Some(2) if data.len() >= 5 => {
+                // setComputeUnitLimit
+                cu_limit = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
             }
+            Some(3) if data.len() >= 9 => {
+                // setComputeUnitPrice
+                cu_price = u64::from_le_bytes([
+                    data[1], data[2], data[3], data[4], data[5], data[6], data[7], data[8],
+                ]);
+            }
+            _ => {}
         }
     }
 
     (cu_limit, cu_price)
 }
 
-fn create_simulation_json(
-    sim_result: &solana_client::rpc_response::RpcSimulateTransactionResult,
+/// Heap frame in bytes requested by `transaction`, or the default
+pub fn requested_heap_frame(transaction: &Transaction) -> u32 {
+    compute_budget_data(transaction)
+        .filter_map(|data| match data {
+            [1, bytes @ ..] if bytes.len() >= 4 => {
+                Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            _ => None,
+        })
+        .last()
+        .unwrap_or(compute_budget::DEFAULT_HEAP_FRAME)
+}
+
+/// Base plus prioritization fee of `transaction` in lamports
+pub fn estimated_fee(transaction: &Transaction) -> u64 {
+    let (cu_limit, cu_price_microlamports) = parse_compute_budget_instructions(transaction);
+    let num_signatures = transaction.signatures.len().max(1) as u64;
+    num_signatures * LAMPORTS_PER_SIGNATURE
+        + compute_budget::prioritization_fee(cu_limit, cu_price_microlamports)
+}
+
+/// Heap section of the report for a transaction requesting `heap_frame`
+/// bytes, with the result of a search for the smallest frame if one ran
+fn heap_report(heap_frame: u32, exhausted: bool, search: Option<HeapSearch>) -> HeapUsage {
+    let kib = |bytes: u32| bytes / compute_budget::HEAP_FRAME_GRANULARITY;
+    let suggestion = match search.as_ref().map(|s| s.minimum_heap_frame) {
+        Some(Some(minimum)) if minimum > compute_budget::DEFAULT_HEAP_FRAME => format!(
+            "Verification needs a heap frame of at least {} KiB; request it with --heap-frame {}",
+            kib(minimum),
+            minimum
+        ),
+        Some(Some(_)) => "Verification fits in the default 32 KiB heap".to_string(),
+        Some(None) => format!(
+            "Verification runs out of heap even with the largest {} KiB frame",
+            kib(compute_budget::MAX_HEAP_FRAME)
+        ),
+        None if exhausted => format!(
+            "The program ran out of its {} KiB heap; find the frame it needs with --measure-heap",
+            kib(heap_frame)
+        ),
+        None => format!("Verification fits in a {} KiB heap", kib(heap_frame)),
+    };
+    HeapUsage {
+        heap_size: heap_frame as u64,
+        suggestion,
+        exhausted,
+        search,
+    }
+}
+
+/// Size section of the report for an encoded transaction
+fn size_report(transaction_size: usize, message_size: usize) -> TransactionSize {
+    let message_within_size = message_size <= MAX_TRANSACTION_SIZE;
+    TransactionSize {
+        transaction_size,
+        message_size,
+        max_message_size: MAX_TRANSACTION_SIZE,
+        message_within_size,
+        message: if message_within_size {
+            format!(
+                "Success: Message size ({}) is within limits ({})",
+                message_size, MAX_TRANSACTION_SIZE
+            )
+        } else {
+            format!(
+                "Fail: Message size ({}) exceeds maximum ({})",
+                message_size, MAX_TRANSACTION_SIZE
+            )
+        },
+        suggestion: if message_within_size {
+            format!("Transaction size ({}) is within limits", message_size)
+        } else {
+            format!(
+                "Transaction size ({}) exceeds maximum ({}); deliver the proof through a buffer account with --buffered",
+                message_size, MAX_TRANSACTION_SIZE
+            )
+        },
+    }
+}
+
+/// Build the typed simulation report
+#[allow(clippy::too_many_arguments)]
+pub fn create_simulation_report(
+    sim_result: &RpcSimulateTransactionResult,
     transaction: &Transaction,
     proof_size: usize,
     witness_size: usize,
-    recent_prioritization_fees: Option<serde_json::Value>,
+    recent_prioritization_fees: Option<Vec<PrioritizationFeeSample>>,
     program_id: &Pubkey,
-    network: &super::init::SolanaNetwork,
+    network: &str,
     rpc_url: &str,
-) -> serde_json::Value {
+) -> SimulationReport {
     // Extract compute units
     let units_consumed = sim_result.units_consumed.unwrap_or(0);
 
@@ -217,20 +345,19 @@ fn create_simulation_json(
     let message_size = bincode::serialize(&transaction.message)
         .unwrap_or_default()
         .len();
-    let message_within_size = message_size <= MAX_TRANSACTION_SIZE;
 
     // Extract logs
-    let logs = sim_result
-        .logs
-        .as_ref()
-        .map(|l| l.iter().map(|s| s.as_str()).collect::<Vec<_>>())
-        .unwrap_or_default();
+    let logs = sim_result.logs.clone().unwrap_or_default();
+
+    // Heap frame and whether the program ran out of it
+    let heap_frame = requested_heap_frame(transaction);
+    let heap_exhausted = sim_result.err.is_some() && diagnose::heap_exhausted(&logs);
 
     // Extract error if any
     let transaction_status = if sim_result.err.is_some() {
-        "Failed"
+        Status::Failed
     } else {
-        "Success"
+        Status::Success
     };
 
     // Calculate CU per proof size
@@ -241,12 +368,10 @@ fn create_simulation_json(
         0.0
     };
 
-    // Fee calculations - FIXED
     let num_signatures = transaction.signatures.len().max(1) as u64;
     let base_fee = num_signatures * LAMPORTS_PER_SIGNATURE;
-
-    // Calculate prioritization fee (convert from microlamports to lamports)
-    let prioritization_fee_lamports = (cu_limit as u64 * cu_price_microlamports) / 1_000_000;
+    let prioritization_fee_lamports =
+        compute_budget::prioritization_fee(cu_limit, cu_price_microlamports);
     let total_fee = base_fee + prioritization_fee_lamports;
     let cost_in_sol = total_fee as f64 / LAMPORTS_PER_SOL as f64;
 
@@ -260,7 +385,7 @@ fn create_simulation_json(
         .saturating_sub(header.num_readonly_unsigned_accounts as usize);
     let total_writable_accounts = writable_signed + writable_unsigned;
 
-    // Calculate priority - FIXED (simplified to match Solana's actual priority calculation)
+    // Simplified to match Solana's priority calculation
     let priority = if compute_budget > 0 {
         prioritization_fee_lamports as f64 / compute_budget as f64
     } else {
@@ -278,123 +403,138 @@ fn create_simulation_json(
         "Compute usage is within acceptable range".to_string()
     };
 
-    let size_suggestion = if !message_within_size {
-        format!(
-            "Transaction size ({}) exceeds maximum ({})",
-            message_size, MAX_TRANSACTION_SIZE
-        )
-    } else {
-        format!("Transaction size ({}) is within limits", message_size)
-    };
-
     let fee_suggestion = if prioritization_fee_lamports == 0 {
         "Consider adding priority fee for faster confirmation"
     } else {
         "Priority fee is set"
     };
 
-    json!({
-        "compute_units": {
-            "total_compute_units_consumed": units_consumed,
-            "total_cu": units_consumed,
-            "compute_budget": compute_budget,
-            "max_compute_units": MAX_COMPUTE_UNITS,
-            "percentage_of_compute_budget_used": format!("{:.2}%", compute_budget_percentage),
-            "warning": cu_limit_warning,
-            "suggestion": compute_suggestion
+    let is_success = transaction_status == Status::Success;
+    SimulationReport {
+        schema_version: SCHEMA_VERSION,
+        generated_at: chrono_timestamp().parse().unwrap_or_default(),
+        program_id: program_id.to_string(),
+        mode: ReportMode::Simulated,
+        submission: None,
+        environment: Environment {
+            network: network.to_string(),
+            rpc_url: rpc_url.to_string(),
         },
-        "proof": {
-            "proof_size": proof_size,
-            "witness_size": witness_size,
-            "total_proof_witness_size": total_proof_witness_size,
-            "cu_per_proof_size": format!("{:.4}", cu_per_proof_size)
+        compute_units: ComputeUnits {
+            total_compute_units_consumed: units_consumed,
+            compute_budget,
+            max_compute_units: MAX_COMPUTE_UNITS,
+            percentage_of_compute_budget_used: compute_budget_percentage,
+            warning: cu_limit_warning,
+            suggestion: compute_suggestion,
         },
-        "heap_usage": {
-            "heap_size": sim_result.return_data.as_ref().map(|_| 0).unwrap_or(0),
-            "suggestion": "Monitor heap usage in program execution"
+        proof: ProofSizes {
+            proof_size,
+            witness_size,
+            total_proof_witness_size,
+            cu_per_proof_size,
         },
-        "cost": {
-            "cost_in_sol": format!("{:.9}", cost_in_sol),
-            "cost_in_lamports": total_fee,
-            "base_fee_per_signature": LAMPORTS_PER_SIGNATURE,
-            "num_signatures": num_signatures,
-            "base_fee": base_fee,
-            "cu_limit": cu_limit,
-            "cu_price_microlamports": cu_price_microlamports,
-            "prioritization_fee": prioritization_fee_lamports,
-            "priority_fee": prioritization_fee_lamports,
-            "total_fee": total_fee,
-            "priority": format!("{:.9}", priority),
-            "suggestion": fee_suggestion
+        heap_usage: heap_report(heap_frame, heap_exhausted, None),
+        cost: Cost {
+            base_fee_per_signature: LAMPORTS_PER_SIGNATURE,
+            num_signatures,
+            base_fee,
+            cu_limit,
+            cu_price_microlamports,
+            prioritization_fee: prioritization_fee_lamports,
+            total_fee,
+            cost_in_sol,
+            priority,
+            suggestion: fee_suggestion.to_string(),
         },
-        "transaction_status": {
-            "status": transaction_status,
-            "error": sim_result.err.as_ref().map(|e| format!("{:?}", e)),
-            "suggestion": if transaction_status == "Success" {
+        transaction_status: TransactionStatus {
+            status: transaction_status,
+            error: sim_result.err.as_ref().map(|e| format!("{:?}", e)),
+            suggestion: if is_success {
                 "Transaction simulation successful"
             } else {
                 "Review transaction error and fix issues"
             }
+            .to_string(),
+            diagnosis: None,
         },
-        "transaction_size": {
-            "transaction_size": transaction_size,
-            "message_size": message_size,
-            "proof_size": proof_size,
-            "witness_size": witness_size,
-            "total_proof_witness_size": total_proof_witness_size,
-            "max_message_size": MAX_TRANSACTION_SIZE,
-            "message_within_size": message_within_size,
-            "message": if message_within_size {
-                format!("Success: Message size ({}) is within limits ({})", message_size, MAX_TRANSACTION_SIZE)
-            } else {
-                format!("Fail: Message size ({}) exceeds maximum ({})", message_size, MAX_TRANSACTION_SIZE)
-            },
-            "suggestion": size_suggestion
-        },
-        "transaction_logs": {
-            "logs": logs,
-            "log_count": logs.len()
+        transaction_size: size_report(transaction_size, message_size),
+        transaction_logs: TransactionLogs {
+            log_count: logs.len(),
+            invocations: invocations::parse(&logs),
+            logs,
         },
-        "accounts": {
-            "total_accounts": total_accounts,
-            "writable_signed_accounts": writable_signed,
-            "writable_unsigned_accounts": writable_unsigned,
-            "total_writable_accounts": total_writable_accounts,
-            "readonly_signed_accounts": header.num_readonly_signed_accounts,
-            "readonly_unsigned_accounts": header.num_readonly_unsigned_accounts
+        accounts: Accounts {
+            total_accounts,
+            writable_signed_accounts: writable_signed,
+            writable_unsigned_accounts: writable_unsigned,
+            total_writable_accounts,
+            readonly_signed_accounts: header.num_readonly_signed_accounts,
+            readonly_unsigned_accounts: header.num_readonly_unsigned_accounts,
         },
-        "fee_recommendation": {
-            "base_fee_per_signature": LAMPORTS_PER_SIGNATURE,
-            "num_signatures": num_signatures,
-            "base_fee": base_fee,
-            "cu_limit": cu_limit,
-            "cu_price_microlamports": cu_price_microlamports,
-            "prioritization_fee": prioritization_fee_lamports,
-            "priority_fee": prioritization_fee_lamports,
-            "total_fee": total_fee,
-            "priority": format!("{:.9}", priority),
-            "suggestion": fee_suggestion
-        },
-        "deserialization": {
-            "success": transaction_status == "Success",
-            "suggestion": if transaction_status == "Success" {
+        deserialization: Deserialization {
+            success: is_success,
+            suggestion: if is_success {
                 "Transaction deserialized successfully"
             } else {
                 "Deserialization may have failed - check transaction structure"
             }
+            .to_string(),
         },
-        "recent_prioritization_fees": recent_prioritization_fees.unwrap_or(json!(null)),
-        "program_id": program_id.to_string(),
-        "environment": {
-            "network": network.to_string(),
-            "rpc_url": rpc_url.to_string(),
-        }
-    })
+        recent_prioritization_fees,
+        cu_limit_recommendation: None,
+        priority_fee_estimates: None,
+        fee_payer: None,
+        instruction: None,
+        buffered_delivery: None,
+        transaction_encodings: None,
+        runs: None,
+        batch: None,
+        metadata: None,
+    }
+}
+
+/// Run a git command in `base_path`, returning its trimmed stdout on success
+fn git_output(base_path: &Path, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(base_path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Provenance recorded with the report in the history
+pub fn report_metadata(
+    base_path: &Path,
+    proof_result: &ProofResult,
+    proof_path: &Path,
+) -> ReportMetadata {
+    let git_commit = git_output(base_path, &["rev-parse", "HEAD"]);
+    let git_dirty = git_commit
+        .as_ref()
+        .and_then(|_| git_output(base_path, &["status", "--porcelain"]))
+        .map(|status| !status.is_empty());
+    let vk_path = proof_path.with_extension("vk");
+
+    ReportMetadata {
+        // Assigned when the report is saved
+        id: String::new(),
+        circuit: super::run::read_circuit_name(base_path).ok(),
+        git_commit,
+        git_dirty,
+        proof_hash: hash_bytes(&proof_result.proof),
+        public_witness_hash: hash_bytes(&proof_result.public_witness),
+        vk_hash: hash_file(&vk_path).ok(),
+    }
 }
 
 /// Print formatted simulation results to the console
-fn print_simulation_results(
-    sim_result: &solana_client::rpc_response::RpcSimulateTransactionResult,
+pub fn print_simulation_results(
+    sim_result: &RpcSimulateTransactionResult,
     transaction: &Transaction,
     proof_size: usize,
     witness_size: usize,
@@ -418,10 +558,10 @@ fn print_simulation_results(
     let is_success = sim_result.err.is_none();
     let total_proof_witness_size = proof_size + witness_size;
 
-    // Fee calculations - FIXED
     let num_signatures = transaction.signatures.len().max(1) as u64;
     let base_fee = num_signatures * LAMPORTS_PER_SIGNATURE;
-    let prioritization_fee_lamports = (cu_limit as u64 * cu_price_microlamports) / 1_000_000;
+    let prioritization_fee_lamports =
+        compute_budget::prioritization_fee(cu_limit, cu_price_microlamports);
     let total_fee = base_fee + prioritization_fee_lamports;
     let cost_in_sol = total_fee as f64 / LAMPORTS_PER_SOL as f64;
 
@@ -440,7 +580,7 @@ fn print_simulation_results(
     // Add warning if CU limit exceeds maximum
     if cu_limit > MAX_COMPUTE_UNITS {
         ui::print_tree_with_status(cu_items);
-        println!(
+        ui_println!(
             "  {} {}",
             emoji::ERROR,
             style(format!(
@@ -453,6 +593,9 @@ fn print_simulation_results(
     } else {
         ui::print_tree_with_status(cu_items);
     }
+    invocations::print_invocations(&invocations::parse(
+        sim_result.logs.as_deref().unwrap_or_default(),
+    ));
 
     // Transaction Status Section
     ui::section(
@@ -464,19 +607,19 @@ fn print_simulation_results(
         "Transaction Status",
     );
     if is_success {
-        println!(
+        ui_println!(
             "  {} {}",
             emoji::SUCCESS,
             style("Simulation Successful").green().bold()
         );
     } else {
-        println!(
+        ui_println!(
             "  {} {}",
             emoji::ERROR,
             style("Simulation Failed").red().bold()
         );
         if let Some(err) = &sim_result.err {
-            println!("  {} Error: {:?}", emoji::TREE_END, style(err).red());
+            ui_println!("  {} Error: {:?}", emoji::TREE_END, style(err).red());
         }
     }
 
@@ -538,7 +681,7 @@ fn print_simulation_results(
 }
 
 /// Format a number with thousands separators
-fn format_number(n: u64) -> String {
+pub fn format_number(n: u64) -> String {
     let s = n.to_string();
     let mut result = String::new();
     for (i, c) in s.chars().rev().enumerate() {
@@ -550,18 +693,789 @@ fn format_number(n: u64) -> String {
     result
 }
 
-pub async fn run_simulate(program_id_arg: Option<String>) -> Result<()> {
-    // Header
-    ui::panel_header(
-        emoji::CHART,
-        "TRANSACTION SIMULATION",
-        Some("Simulate ZK proof verification on Solana"),
+/// Build the transaction that sets the compute unit limit to `cu_limit`,
+/// requests `heap_frame` bytes of heap if given and calls the verifier with
+/// `verify_instruction`
+pub fn build_verify_transaction(
+    fee_payer: &Pubkey,
+    verify_instruction: &Instruction,
+    cu_limit: u32,
+    heap_frame: Option<u32>,
+) -> Transaction {
+    let mut instructions = vec![compute_budget::set_compute_unit_limit(cu_limit)];
+    if let Some(heap_frame) = heap_frame {
+        instructions.push(compute_budget::request_heap_frame(heap_frame));
+    }
+    instructions.push(verify_instruction.clone());
+    Transaction::new_with_payer(&instructions, Some(fee_payer))
+}
+
+/// Check a `--heap-frame` value
+pub fn validate_heap_frame(heap_frame: Option<u32>) -> error::Result<Option<u32>> {
+    match heap_frame {
+        Some(bytes) if !compute_budget::is_valid_heap_frame(bytes) => {
+            Err(ZkLenseError::InvalidInput(format!(
+                "--heap-frame must be a multiple of {} between {} and {} bytes, got {}",
+                compute_budget::HEAP_FRAME_GRANULARITY,
+                compute_budget::DEFAULT_HEAP_FRAME,
+                compute_budget::MAX_HEAP_FRAME,
+                bytes
+            )))
+        }
+        _ => Ok(heap_frame),
+    }
+}
+
+/// Where verify transactions are simulated
+enum Backend {
+    /// An RPC node; `blockhash` is fetched once when connecting
+    Rpc {
+        client: RpcClient,
+        rpc_url: String,
+        blockhash: Hash,
+        checks: SignatureChecks,
+    },
+    /// The embedded runtime, with the verifier program loaded
+    Offline { runtime: Box<svm::Runtime> },
+}
+
+/// How the simulating node treats the transaction's signature and blockhash
+#[derive(Debug, Clone, Copy, Default)]
+struct SignatureChecks {
+    sig_verify: bool,
+    replace_recent_blockhash: bool,
+}
+
+impl Backend {
+    /// Connect to `rpc_url` and fetch a blockhash, funding `fee_payer` on
+    /// localnet
+    async fn connect(
+        network: &SolanaNetwork,
+        rpc_url: &str,
+        fee_payer: &Pubkey,
+        checks: SignatureChecks,
+    ) -> error::Result<Backend> {
+        let start = Instant::now();
+        let spinner = ui::spinner(&format!(
+            "Connecting to {} ({})...",
+            network,
+            style(rpc_url).dim()
+        ));
+
+        let client =
+            RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+
+        // Get blockhash
+        let blockhash = client.get_latest_blockhash().await.map_err(|e| {
+            handle_rpc_error(
+                anyhow::Error::from(e).context("Failed to get latest blockhash"),
+                rpc_url,
+            )
+        })?;
+
+        ui::spinner_success_with_duration(
+            &spinner,
+            &format!("Connected to {}", network),
+            start.elapsed().as_millis(),
+        );
+        if *network == SolanaNetwork::Localnet {
+            localnet::fund_fee_payer(&client, fee_payer).await?;
+        }
+
+        Ok(Backend::Rpc {
+            client,
+            rpc_url: rpc_url.to_string(),
+            blockhash,
+            checks,
+        })
+    }
+
+    /// Sign and simulate `transaction`, showing `message` while it runs.
+    /// With `lookup_tables` it is simulated as a v0 transaction resolving
+    /// accounts through them.
+    async fn simulate(
+        &self,
+        transaction: &mut Transaction,
+        fee_payer: &FeePayer,
+        lookup_tables: Option<&[AddressLookupTableAccount]>,
+        message: &str,
+    ) -> error::Result<RpcSimulateTransactionResult> {
+        let start = Instant::now();
+        let spinner = ui::spinner(message);
+        let result = self
+            .simulate_quietly(transaction, fee_payer, lookup_tables)
+            .await;
+        match &result {
+            Ok(_) => ui::spinner_success_with_duration(
+                &spinner,
+                match self {
+                    Backend::Rpc { .. } => "Simulation complete",
+                    Backend::Offline { .. } => "Simulation complete (offline)",
+                },
+                start.elapsed().as_millis(),
+            ),
+            Err(_) => ui::spinner_error(&spinner, "Simulation failed"),
+        }
+        result
+    }
+
+    /// Fetch a new blockhash to sign with, so repeated simulations do not
+    /// outlive the one fetched when connecting
+    async fn refresh_blockhash(&mut self) -> error::Result<()> {
+        if let Backend::Rpc {
+            client,
+            rpc_url,
+            blockhash,
+            ..
+        } = self
+        {
+            *blockhash = client.get_latest_blockhash().await.map_err(|e| {
+                handle_rpc_error(
+                    anyhow::Error::from(e).context("Failed to get latest blockhash"),
+                    rpc_url,
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// `simulate` without the spinner
+    async fn simulate_quietly(
+        &self,
+        transaction: &mut Transaction,
+        fee_payer: &FeePayer,
+        lookup_tables: Option<&[AddressLookupTableAccount]>,
+    ) -> error::Result<RpcSimulateTransactionResult> {
+        let blockhash = match self {
+            Backend::Rpc { blockhash, .. } => *blockhash,
+            Backend::Offline { runtime } => runtime.latest_blockhash(),
+        };
+        fee_payer.sign(transaction, blockhash);
+        let versioned = match lookup_tables {
+            Some(lookup_tables) => {
+                let mut versioned = versioned::to_v0(transaction, lookup_tables)?;
+                fee_payer.sign_versioned(&mut versioned);
+                versioned
+            }
+            None => VersionedTransaction::from(transaction.clone()),
+        };
+        match self {
+            Backend::Rpc {
+                client,
+                rpc_url,
+                checks,
+                ..
+            } => {
+                let config = RpcSimulateTransactionConfig {
+                    sig_verify: checks.sig_verify,
+                    replace_recent_blockhash: checks.replace_recent_blockhash,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                };
+                client
+                    .simulate_transaction_with_config(&versioned, config)
+                    .await
+                    .map(|response| response.value)
+                    .map_err(|e| {
+                        handle_rpc_error(
+                            anyhow::Error::from(e).context("Failed to simulate transaction"),
+                            rpc_url,
+                        )
+                    })
+            }
+            Backend::Offline { runtime } => Ok(runtime.simulate(versioned)),
+        }
+    }
+
+    /// Sign and execute `transaction`, keeping its effects. On a node it is
+    /// simulated first and only sent if that succeeds; the simulated result
+    /// is returned.
+    async fn execute(
+        &mut self,
+        transaction: &mut Transaction,
+        fee_payer: &FeePayer,
+    ) -> error::Result<RpcSimulateTransactionResult> {
+        match self {
+            Backend::Rpc {
+                client, rpc_url, ..
+            } => {
+                let rpc_error = |e: solana_client::client_error::ClientError, context: &str| {
+                    handle_rpc_error(anyhow::Error::from(e).context(context.to_string()), rpc_url)
+                };
+                let blockhash = client
+                    .get_latest_blockhash()
+                    .await
+                    .map_err(|e| rpc_error(e, "Failed to get latest blockhash"))?;
+                fee_payer.sign(transaction, blockhash);
+                let result = client
+                    .simulate_transaction(&*transaction)
+                    .await
+                    .map_err(|e| rpc_error(e, "Failed to simulate transaction"))?
+                    .value;
+                if result.err.is_none() {
+                    client
+                        .send_and_confirm_transaction(&*transaction)
+                        .await
+                        .map_err(|e| rpc_error(e, "Failed to send transaction"))?;
+                }
+                Ok(result)
+            }
+            Backend::Offline { runtime, .. } => Ok(runtime.execute(transaction, fee_payer)),
+        }
+    }
+
+    /// Lamports an account of `data_len` bytes needs to be rent exempt
+    async fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> error::Result<u64> {
+        match self {
+            Backend::Rpc {
+                client, rpc_url, ..
+            } => client
+                .get_minimum_balance_for_rent_exemption(data_len)
+                .await
+                .map_err(|e| {
+                    handle_rpc_error(
+                        anyhow::Error::from(e).context("Failed to get rent exemption minimum"),
+                        rpc_url,
+                    )
+                }),
+            Backend::Offline { runtime, .. } => {
+                Ok(runtime.minimum_balance_for_rent_exemption(data_len))
+            }
+        }
+    }
+
+    /// Read the lookup tables at `addresses` from the node
+    async fn fetch_lookup_tables(
+        &self,
+        addresses: &[Pubkey],
+    ) -> error::Result<Vec<AddressLookupTableAccount>> {
+        let Backend::Rpc { client, .. } = self else {
+            return Err(ZkLenseError::InvalidInput(
+                "--lookup-table reads tables from the cluster; use --create-lookup-table offline"
+                    .to_string(),
+            ));
+        };
+        let mut tables = Vec::new();
+        for address in addresses {
+            tables.push(versioned::fetch_lookup_table(client, address).await?);
+        }
+        Ok(tables)
+    }
+
+    /// Create a lookup table holding `addresses`, owned by `fee_payer`
+    async fn create_lookup_table(
+        &mut self,
+        fee_payer: &FeePayer,
+        addresses: Vec<Pubkey>,
+    ) -> error::Result<AddressLookupTableAccount> {
+        let spinner = ui::spinner(&format!(
+            "Creating a lookup table for {} account(s)...",
+            addresses.len()
+        ));
+        let result = match self {
+            Backend::Rpc { client, .. } => {
+                versioned::create_lookup_table_on_chain(client, fee_payer, addresses).await
+            }
+            Backend::Offline { runtime } => runtime.add_lookup_table(&fee_payer.pubkey, addresses),
+        };
+        match &result {
+            Ok(table) => {
+                ui::spinner_success(&spinner, &format!("Created lookup table {}", table.key))
+            }
+            Err(_) => ui::spinner_error(&spinner, "Failed to create lookup table"),
+        }
+        result
+    }
+
+    /// Lamports held by `pubkey`; offline accounts are funded on demand
+    async fn balance(&self, pubkey: &Pubkey) -> Option<u64> {
+        match self {
+            Backend::Rpc { client, .. } => client.get_balance(pubkey).await.ok(),
+            Backend::Offline { .. } => None,
+        }
+    }
+
+    /// Fetch recent prioritization fees for transactions writing `accounts`,
+    /// or for the whole cluster when empty. Warns instead of failing when the
+    /// node does not return them; offline simulation has no fee market.
+    async fn recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Option<Vec<PrioritizationFeeSample>> {
+        let Backend::Rpc { client, .. } = self else {
+            return None;
+        };
+
+        let spinner = ui::spinner(&if accounts.is_empty() {
+            "Fetching prioritization fees...".to_string()
+        } else {
+            format!(
+                "Fetching prioritization fees for {} writable account(s)...",
+                accounts.len()
+            )
+        });
+        match client.get_recent_prioritization_fees(accounts).await {
+            Ok(fees_vec) => {
+                let fees: Vec<PrioritizationFeeSample> = fees_vec
+                    .iter()
+                    .rev()
+                    .take(50)
+                    .map(|fee| PrioritizationFeeSample {
+                        slot: fee.slot,
+                        prioritization_fee: fee.prioritization_fee,
+                    })
+                    .collect();
+                ui::spinner_success(&spinner, "Fetched prioritization fees");
+                Some(fees)
+            }
+            Err(_) => {
+                ui::spinner_warn(&spinner, "Could not fetch prioritization fees");
+                None
+            }
+        }
+    }
+}
+
+/// Median of the sampled prioritization fees, in micro-lamports per CU
+fn median_prioritization_fee(samples: Option<&[PrioritizationFeeSample]>) -> u64 {
+    let mut fees: Vec<u64> = samples
+        .unwrap_or_default()
+        .iter()
+        .map(|sample| sample.prioritization_fee)
+        .collect();
+    fees.sort_unstable();
+    priority_fee::percentile(&fees, 50)
+}
+
+/// What the verify transaction is rebuilt from to re-simulate it with other
+/// limits
+#[derive(Clone, Copy)]
+struct VerifyTemplate<'a> {
+    fee_payer: &'a FeePayer,
+    instruction: &'a Instruction,
+    lookup_tables: Option<&'a [AddressLookupTableAccount]>,
+    cu_limit: u32,
+    heap_frame: Option<u32>,
+}
+
+impl VerifyTemplate<'_> {
+    fn build(&self) -> Transaction {
+        build_verify_transaction(
+            &self.fee_payer.pubkey,
+            self.instruction,
+            self.cu_limit,
+            self.heap_frame,
+        )
+    }
+}
+
+/// Re-simulate with the compute unit limit recommended for `consumed` units
+/// and compare the prioritization fee with the one at the maximum limit
+async fn tune_cu_limit(
+    backend: &Backend,
+    template: VerifyTemplate<'_>,
+    consumed: u64,
+    margin_percent: f64,
+    cu_price_microlamports: u64,
+) -> error::Result<CuLimitRecommendation> {
+    let recommended = compute_budget::recommended_cu_limit(consumed, margin_percent);
+    let template = VerifyTemplate {
+        cu_limit: recommended,
+        ..template
+    };
+    let result = backend
+        .simulate(
+            &mut template.build(),
+            template.fee_payer,
+            template.lookup_tables,
+            &format!(
+                "Confirming with a {} CU limit...",
+                format_number(recommended as u64)
+            ),
+        )
+        .await?;
+
+    let fee_at_max = compute_budget::prioritization_fee(
+        compute_budget::MAX_COMPUTE_UNITS,
+        cu_price_microlamports,
     );
+    let fee_at_recommended =
+        compute_budget::prioritization_fee(recommended, cu_price_microlamports);
+    Ok(CuLimitRecommendation {
+        recommended_cu_limit: recommended,
+        safety_margin_percent: margin_percent,
+        confirmed: result.err.is_none(),
+        units_consumed: result.units_consumed.unwrap_or(0),
+        error: result.err.as_ref().map(|e| format!("{:?}", e)),
+        cu_price_microlamports,
+        prioritization_fee_at_max: fee_at_max,
+        prioritization_fee_at_recommended: fee_at_recommended,
+        fee_savings: fee_at_max.saturating_sub(fee_at_recommended),
+    })
+}
+
+/// Binary-search the smallest heap frame verification succeeds with between
+/// `lowest` and `highest` bytes, given that `highest` is known to succeed if
+/// `known_good`
+async fn find_min_heap_frame(
+    backend: &Backend,
+    template: VerifyTemplate<'_>,
+    lowest: u32,
+    highest: u32,
+    known_good: bool,
+) -> error::Result<HeapSearch> {
+    let step = compute_budget::HEAP_FRAME_GRANULARITY;
+    let start = Instant::now();
+    let spinner = ui::spinner("Searching for the smallest heap frame...");
+    let mut minimum = known_good.then_some(highest);
+    let mut heap_frames_tried = Vec::new();
+    let (mut low, mut high) = (lowest / step, highest / step);
+    if known_good {
+        high -= 1;
+    }
+    while low <= high {
+        let middle = low + (high - low) / 2;
+        let heap_frame = middle * step;
+        spinner.set_message(format!("Simulating with a {} KiB heap...", middle));
+        heap_frames_tried.push(heap_frame);
+        let template = VerifyTemplate {
+            heap_frame: Some(heap_frame),
+            ..template
+        };
+        let result = backend
+            .simulate_quietly(
+                &mut template.build(),
+                template.fee_payer,
+                template.lookup_tables,
+            )
+            .await;
+        match result {
+            Ok(result) if result.err.is_none() => {
+                minimum = Some(heap_frame);
+                high = middle - 1;
+            }
+            Ok(result) if diagnose::heap_exhausted(result.logs.as_deref().unwrap_or_default()) => {
+                low = middle + 1
+            }
+            // Any other failure says nothing about the heap frame
+            Ok(result) => {
+                ui::spinner_error(&spinner, "Heap frame search failed");
+                return Err(ZkLenseError::SimulationFailed(format!(
+                    "Simulation with a {} KiB heap failed for a reason other than heap exhaustion: {}",
+                    middle,
+                    result.err.map(|e| e.to_string()).unwrap_or_default()
+                )));
+            }
+            Err(e) => {
+                ui::spinner_error(&spinner, "Heap frame search failed");
+                return Err(e);
+            }
+        }
+    }
+
+    let message = match minimum {
+        Some(minimum) => format!("Smallest heap frame: {} KiB", minimum / step),
+        None => "Verification fails with every heap frame".to_string(),
+    };
+    ui::spinner_success_with_duration(&spinner, &message, start.elapsed().as_millis());
+    Ok(HeapSearch {
+        minimum_heap_frame: minimum,
+        heap_frames_tried,
+    })
+}
+
+/// A proof and public witness simulated by `--runs`, with the verify
+/// instruction built from them
+struct RunInput {
+    proof_path: PathBuf,
+    witness_path: PathBuf,
+    instruction: Instruction,
+}
+
+/// Simulate `transaction` with a fresh blockhash, retrying RPC failures
+/// with backoff. Returns the result and the number of retries.
+async fn simulate_with_retries(
+    backend: &mut Backend,
+    transaction: &mut Transaction,
+    fee_payer: &FeePayer,
+    lookup_tables: Option<&[AddressLookupTableAccount]>,
+) -> (error::Result<RpcSimulateTransactionResult>, u32) {
+    let mut retries = 0;
+    loop {
+        let result = match backend.refresh_blockhash().await {
+            Ok(()) => {
+                backend
+                    .simulate_quietly(transaction, fee_payer, lookup_tables)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Err(ZkLenseError::Rpc(_)) if retries < runs::MAX_RETRIES => {
+                retries += 1;
+                tokio::time::sleep(runs::backoff(retries)).await;
+            }
+            result => return (result, retries),
+        }
+    }
+}
+
+/// Simulate runs 2 to `count`, cycling through `inputs`; `first` is the
+/// simulation already done with the first input
+async fn repeat_simulation(
+    backend: &mut Backend,
+    template: VerifyTemplate<'_>,
+    inputs: &[RunInput],
+    count: usize,
+    first: RunSample,
+) -> Vec<RunSample> {
+    let start = Instant::now();
+    let spinner = ui::spinner("Repeating the simulation...");
+    let mut samples = vec![first];
+    for run in 2..=count {
+        spinner.set_message(format!("Run {} of {}...", run, count));
+        let input = &inputs[(run - 1) % inputs.len()];
+        let template = VerifyTemplate {
+            instruction: &input.instruction,
+            ..template
+        };
+        let run_start = Instant::now();
+        let (result, retries) = simulate_with_retries(
+            backend,
+            &mut template.build(),
+            template.fee_payer,
+            template.lookup_tables,
+        )
+        .await;
+        let (units_consumed, error) = match result {
+            Ok(result) => (
+                result.units_consumed,
+                result.err.as_ref().map(|e| e.to_string()),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        samples.push(RunSample {
+            run,
+            proof_path: input.proof_path.display().to_string(),
+            witness_path: input.witness_path.display().to_string(),
+            units_consumed,
+            error,
+            retries,
+            duration_ms: run_start.elapsed().as_millis() as u64,
+        });
+    }
+    let failed = samples.iter().filter(|s| s.error.is_some()).count();
+    let message = format!("Completed {} runs ({} failed)", count, failed);
+    if failed == 0 {
+        ui::spinner_success_with_duration(&spinner, &message, start.elapsed().as_millis());
+    } else {
+        ui::spinner_warn(&spinner, &message);
+    }
+    samples
+}
+
+/// One batch entry per simulated input, with fees priced at
+/// `cu_price_microlamports`
+fn batch_entries(
+    template: VerifyTemplate<'_>,
+    inputs: &[RunInput],
+    samples: Vec<RunSample>,
+    cu_price_microlamports: u64,
+) -> Vec<BatchEntry> {
+    samples
+        .into_iter()
+        .zip(inputs)
+        .map(|(sample, input)| {
+            let transaction = VerifyTemplate {
+                instruction: &input.instruction,
+                ..template
+            }
+            .build();
+            let transaction_size = match template.lookup_tables {
+                Some(lookup_tables) => versioned::to_v0(&transaction, lookup_tables)
+                    .ok()
+                    .and_then(|versioned| bincode::serialized_size(&versioned).ok()),
+                None => bincode::serialized_size(&transaction).ok(),
+            };
+            let base_fee = transaction.signatures.len().max(1) as u64 * LAMPORTS_PER_SIGNATURE;
+            let succeeded = sample.error.is_none();
+            BatchEntry {
+                proof_path: sample.proof_path,
+                witness_path: sample.witness_path,
+                status: if succeeded {
+                    Status::Success
+                } else {
+                    Status::Failed
+                },
+                units_consumed: sample.units_consumed,
+                deviation_percent: None,
+                outlier: false,
+                transaction_size: transaction_size.unwrap_or(0) as usize,
+                fee: estimated_fee(&transaction),
+                fee_at_recommended_limit: sample.units_consumed.filter(|_| succeeded).map(
+                    |units| {
+                        base_fee
+                            + compute_budget::prioritization_fee(
+                                compute_budget::recommended_cu_limit(
+                                    units,
+                                    compute_budget::DEFAULT_CU_MARGIN_PERCENT,
+                                ),
+                                cu_price_microlamports,
+                            )
+                    },
+                ),
+                error: sample.error,
+                retries: sample.retries,
+            }
+        })
+        .collect()
+}
 
-    // Get program ID from argument or prompt user
-    let program_id_str = match program_id_arg {
-        Some(id) => id,
-        None => Input::<String>::new()
+/// Proof and witness files given with `--proof` and `--witness`
+fn proof_pairs(proofs: &[String], witnesses: &[String]) -> error::Result<Vec<(PathBuf, PathBuf)>> {
+    if proofs.len() != witnesses.len() {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Got {} --proof file(s) but {} --witness file(s); pass one witness per proof",
+            proofs.len(),
+            witnesses.len()
+        )));
+    }
+    Ok(proofs
+        .iter()
+        .zip(witnesses)
+        .map(|(proof, witness)| (PathBuf::from(proof), PathBuf::from(witness)))
+        .collect())
+}
+
+/// Create the buffer and write the proof to it, stopping at the first
+/// failed transaction
+async fn deliver(
+    backend: &mut Backend,
+    plan: &mut DeliveryPlan,
+    fee_payer: &FeePayer,
+) -> error::Result<Vec<RpcSimulateTransactionResult>> {
+    let count = plan.transactions.len();
+    let spinner = ui::spinner(&format!(
+        "Writing proof to buffer {} ({} transactions)...",
+        plan.buffer, count
+    ));
+    let start = Instant::now();
+    let mut results = Vec::new();
+    for (index, transaction) in plan.transactions.iter_mut().enumerate() {
+        spinner.set_message(format!("Buffer transaction {} of {}...", index + 1, count));
+        let result = backend.execute(transaction, fee_payer).await?;
+        let failed = result.err.is_some();
+        results.push(result);
+        if failed {
+            ui::spinner_error(
+                &spinner,
+                &format!("Buffer transaction {} of {} failed", index + 1, count),
+            );
+            return Ok(results);
+        }
+    }
+    ui::spinner_success_with_duration(
+        &spinner,
+        &format!("Wrote proof to buffer in {} transactions", count),
+        start.elapsed().as_millis(),
+    );
+    Ok(results)
+}
+
+/// Print the smallest heap frame and the frames tried to find it
+fn print_heap_search(search: &HeapSearch) {
+    ui::section(emoji::PACKAGE, "Heap Frame");
+    let kib = |bytes: &u32| format!("{} KiB", bytes / compute_budget::HEAP_FRAME_GRANULARITY);
+    let minimum = match &search.minimum_heap_frame {
+        Some(minimum) => format!("{} ({} bytes)", kib(minimum), minimum),
+        None => format!("none up to {}", kib(&compute_budget::MAX_HEAP_FRAME)),
+    };
+    let tried: Vec<String> = search.heap_frames_tried.iter().map(kib).collect();
+    ui::print_tree_with_status(&[
+        ("Minimum", &minimum, search.minimum_heap_frame.is_some()),
+        ("Simulated", &tried.join(", "), true),
+    ]);
+}
+
+/// Print the compute unit limit recommendation
+fn print_cu_limit_recommendation(recommendation: &CuLimitRecommendation) {
+    ui::section(emoji::BULB, "Compute Unit Limit");
+    let limit = format!(
+        "{} CU (+{}% margin)",
+        format_number(recommendation.recommended_cu_limit as u64),
+        recommendation.safety_margin_percent
+    );
+    let confirmation = if recommendation.confirmed {
+        format!(
+            "succeeded, {} CU consumed",
+            format_number(recommendation.units_consumed)
+        )
+    } else {
+        format!(
+            "failed: {}",
+            recommendation.error.as_deref().unwrap_or("unknown error")
+        )
+    };
+    let price = format!(
+        "{} micro-lamports/CU",
+        format_number(recommendation.cu_price_microlamports)
+    );
+    let fees = format!(
+        "{} → {} lamports",
+        format_number(recommendation.prioritization_fee_at_max),
+        format_number(recommendation.prioritization_fee_at_recommended)
+    );
+    let savings = format!(
+        "{} lamports per transaction",
+        format_number(recommendation.fee_savings)
+    );
+    ui::print_tree_with_status(&[
+        ("Recommended", &limit, true),
+        ("Re-simulation", &confirmation, recommendation.confirmed),
+        ("CU Price", &price, true),
+        ("Priority Fee", &fees, true),
+        ("Savings", &savings, true),
+    ]);
+    if recommendation.cu_price_microlamports == 0 {
+        ui_println!(
+            "  {}",
+            style("No recent prioritization fees to price the limit with; savings apply once a CU price is set").dim()
+        );
+    }
+    if !recommendation.confirmed {
+        ui_println!(
+            "  {} {}",
+            emoji::WARNING,
+            style("Verification fails with this limit; increase --cu-margin").yellow()
+        );
+    }
+}
+
+/// Network and RPC URL from the config, or localnet when `localnet` is set
+pub fn resolve_network(config: &ZkLenseConfig, localnet: bool) -> (SolanaNetwork, String) {
+    let configured_network = config.get_solana_network();
+    if localnet && configured_network != SolanaNetwork::Localnet {
+        (
+            SolanaNetwork::Localnet,
+            SolanaNetwork::Localnet.rpc_url().to_string(),
+        )
+    } else {
+        (configured_network, config.get_solana_rpc_url())
+    }
+}
+
+/// Program ID from the argument, derived from `local_circuit`, or prompted
+pub fn resolve_program_id(
+    program_id: Option<String>,
+    local_circuit: Option<&str>,
+) -> error::Result<Pubkey> {
+    let program_id_str = match (program_id, local_circuit) {
+        (Some(id), _) => id,
+        (None, Some(circuit)) => localnet::program_id(circuit).to_string(),
+        (None, None) if !ui::is_interactive() => {
+            return Err(ui::missing_input("Program ID", "--program-id <PROGRAM_ID>").into());
+        }
+        (None, None) => Input::<String>::new()
             .with_prompt(format!("{} Enter Solana program ID", emoji::PIN))
             .interact_text()
             .context("Failed to read program ID")?,
@@ -569,163 +1483,716 @@ pub async fn run_simulate(program_id_arg: Option<String>) -> Result<()> {
 
     if program_id_str.is_empty() {
         ui::panel_error("INVALID INPUT", "Program ID cannot be empty", None, None);
-        return Err(anyhow::anyhow!("Program ID cannot be empty"));
+        return Err(ZkLenseError::InvalidInput(
+            "Program ID cannot be empty".to_string(),
+        ));
     }
+    Pubkey::from_str(&program_id_str).map_err(|e| {
+        ZkLenseError::InvalidInput(format!("Invalid program ID '{}': {}", program_id_str, e))
+    })
+}
 
-    ui::blank();
-
-    // Read proof and witness files (automatically found by extension)
-    let (proof_result, proof_path, witness_path) = read_proof_files()?;
-    let proof_size = proof_result.proof.len();
-    let witness_size = proof_result.public_witness.len();
+/// Options for the simulate command
+#[derive(Debug, Default)]
+pub struct SimulateOptions {
+    pub program_id: Option<String>,
+    /// Limits from the command line, merged over the config file's budgets
+    pub budgets: Budgets,
+    /// Simulate against a local validator regardless of the configured network
+    pub localnet: bool,
+    /// Leave a validator started by zklense running afterwards
+    pub keep_validator: bool,
+    /// Simulate in-process instead of over RPC
+    pub offline: bool,
+    /// Find the smallest compute unit limit that still succeeds
+    pub tune_cu: bool,
+    /// Safety margin over the consumed compute units when tuning, in percent
+    pub cu_margin: Option<f64>,
+    /// Fee payer keypair file, overriding the configured one
+    pub keypair: Option<String>,
+    /// Have the node verify the transaction signature
+    pub sig_verify: bool,
+    /// Have the node replace the transaction's blockhash
+    pub replace_blockhash: bool,
+    /// Write the proof to a buffer account before verifying
+    pub buffered: bool,
+    /// Simulate a v0 transaction instead of a legacy one
+    pub v0: bool,
+    /// Address lookup tables the v0 transaction resolves accounts through
+    pub lookup_tables: Vec<String>,
+    /// Create a lookup table holding the verify instruction's accounts
+    pub create_lookup_table: bool,
+    /// Heap frame to request in bytes
+    pub heap_frame: Option<u32>,
+    /// Search for the smallest heap frame verification succeeds with
+    pub measure_heap: bool,
+    /// Simulate this many times and report statistics
+    pub runs: Option<usize>,
+    /// Directory or pattern of proof and witness pairs to simulate each of
+    pub batch: Option<String>,
+    /// Proof files to simulate instead of the one found in the project,
+    /// paired with `witnesses`
+    pub proofs: Vec<String>,
+    pub witnesses: Vec<String>,
+}
 
-    // Create instruction data by concatenating proof + witness
-    let instruction_data = create_instruction_data(&proof_result);
+pub async fn run_simulate(options: SimulateOptions) -> error::Result<()> {
+    // Header
+    ui::panel_header(
+        emoji::CHART,
+        "TRANSACTION SIMULATION",
+        Some("Simulate ZK proof verification on Solana"),
+    );
 
-    // Get RPC URL from config
+    // Get network and RPC URL from config
     let current_dir = std::env::current_dir()?;
-    let rpc_url = get_solana_rpc_url(&current_dir)
-        .map_err(|e| anyhow::anyhow!("Failed to read config: {}. Run 'zklense init' first.", e))?;
-    let network = get_solana_network(&current_dir)
-        .map_err(|e| anyhow::anyhow!("Failed to read config: {}. Run 'zklense init' first.", e))?;
+    let config = read_config(&current_dir).map_err(|e| {
+        ZkLenseError::Config(format!(
+            "Failed to read config: {}. Run 'zklense init' first.",
+            e
+        ))
+    })?;
+    let (network, rpc_url) = resolve_network(&config, options.localnet);
+    // Command-line budgets override the [budgets] table in config.toml
+    let budgets = config.budgets.merged_with(&options.budgets);
+
+    // On localnet and offline the verifier is loaded at an ID derived from
+    // the circuit
+    let local_circuit = if network == SolanaNetwork::Localnet || options.offline {
+        Some(super::run::read_circuit_name(&current_dir)?)
+    } else {
+        None
+    };
 
-    // Connect to Solana
-    let start = Instant::now();
-    let spinner = ui::spinner(&format!(
-        "Connecting to {} ({})...",
-        network,
-        style(&rpc_url).dim()
-    ));
+    let program_id = resolve_program_id(options.program_id, local_circuit.as_deref())?;
 
-    let connection = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+    ui::blank();
+
+    // Read the given proof and witness, every pair of a batch, or find them
+    // by extension
+    let pairs = match &options.batch {
+        Some(source) => batch::discover(&current_dir, source)?,
+        None => proof_pairs(&options.proofs, &options.witnesses)?,
+    };
+    let run_count = options.runs.unwrap_or(pairs.len().max(1));
+    // A batch reports on every input, even a single one
+    let repeated = run_count > 1 || options.batch.is_some();
+    if run_count == 0 {
+        return Err(ZkLenseError::InvalidInput(
+            "--runs must be at least 1".to_string(),
+        ));
+    }
+    if options.buffered && run_count > 1 {
+        return Err(ZkLenseError::InvalidInput(
+            "--buffered simulates a single proof; drop --runs and pass one --proof".to_string(),
+        ));
+    }
+    let (proof_result, proof_path, witness_path) = match pairs.first() {
+        Some((proof, witness)) => read_proof_pair(proof.clone(), witness.clone())?,
+        None => read_proof_files()?,
+    };
+    let proof_size = proof_result.proof.len();
+    let witness_size = proof_result.public_witness.len();
 
-    // Parse program ID
-    let program_id = Pubkey::from_str(&program_id_str)?;
+    // Local simulations can pay with a throwaway keypair, remote ones need a
+    // funded one to be signed
+    let mut fee_payer = FeePayer::resolve(
+        options.keypair.as_deref(),
+        config.get(KEYPAIR_PATH_KEY).map(|s| s.as_str()),
+        &current_dir,
+        local_circuit.is_some(),
+    )?;
+    if options.sig_verify && !fee_payer.signs() {
+        return Err(ZkLenseError::InvalidInput(
+            "--sig-verify needs a fee payer keypair; pass --keypair or run 'zklense config set-keypair'"
+                .to_string(),
+        ));
+    }
+    if fee_payer.signs() {
+        ui::info(&format!(
+            "Fee payer {} ({})",
+            fee_payer.pubkey,
+            style(fee_payer.describe()).dim()
+        ));
+    } else {
+        ui::warn(&format!(
+            "No keypair at {}; simulating unsigned with a placeholder fee payer. Pass --keypair or run 'zklense config set-keypair'.",
+            fee_payer::default_keypair_path()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "~/.config/solana/id.json".to_string())
+        ));
+    }
+    let checks = SignatureChecks {
+        sig_verify: options.sig_verify,
+        replace_recent_blockhash: options.replace_blockhash,
+    };
 
-    // Create a keypair for the fee payer (can be loaded from file or generated)
-    // For simulation, we can use a dummy keypair
-    let fee_payer = Pubkey::from_str("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM")?;
+    let heap_frame = validate_heap_frame(options.heap_frame)?;
 
-    // Create the verify instruction with proof + witness data
-    let verify_instruction = Instruction {
-        program_id,
-        accounts: vec![], // No accounts needed for this instruction
-        data: instruction_data,
+    // Lookup tables only exist on the cluster they were created on; new ones
+    // are only created where nothing is paid for
+    if options.create_lookup_table && local_circuit.is_none() {
+        return Err(ZkLenseError::InvalidInput(
+            "--create-lookup-table sends a transaction; use it with --localnet or --offline, or pass an existing table with --lookup-table"
+                .to_string(),
+        ));
+    }
+    let lookup_table_addresses = options
+        .lookup_tables
+        .iter()
+        .map(|address| {
+            Pubkey::from_str(address).map_err(|_| {
+                ZkLenseError::InvalidInput(format!("Invalid lookup table address '{}'", address))
+            })
+        })
+        .collect::<error::Result<Vec<Pubkey>>>()?;
+
+    // The bare verifier call, or the instruction in .zklense/instruction.toml.
+    // Buffered delivery writes to a fresh buffer account first, which only
+    // persists on a local validator or in-process.
+    let buffer = if options.buffered {
+        if local_circuit.is_none() {
+            return Err(ZkLenseError::InvalidInput(
+                "--buffered writes the proof to the chain before verifying; use it with --localnet or --offline"
+                    .to_string(),
+            ));
+        }
+        let spec = InstructionSpec::load(&current_dir)?
+            .filter(|spec| spec.buffer.is_some())
+            .ok_or_else(|| {
+                ZkLenseError::Config(format!(
+                    "--buffered needs a [buffer] section in {}",
+                    instruction_spec::spec_path(&current_dir).display()
+                ))
+            })?;
+        Some((spec, Keypair::new()))
+    } else {
+        None
+    };
+    let verify = match &buffer {
+        Some((spec, keypair)) => spec.build(
+            &current_dir,
+            &program_id,
+            &fee_payer.pubkey,
+            Some(&keypair.pubkey()),
+            &proof_result,
+        )?,
+        None => instruction_spec::verify_instruction(
+            &current_dir,
+            &program_id,
+            &fee_payer.pubkey,
+            &proof_result,
+        )?,
     };
+    fee_payer.add_cosigners(verify.signers);
+    let mut run_inputs = vec![RunInput {
+        proof_path: proof_path.clone(),
+        witness_path: witness_path.clone(),
+        instruction: verify.instruction.clone(),
+    }];
+    if run_count > 1 {
+        for (proof, witness) in pairs.iter().skip(1) {
+            let (result, proof_path, witness_path) =
+                read_proof_pair(proof.clone(), witness.clone())?;
+            let input = instruction_spec::verify_instruction(
+                &current_dir,
+                &program_id,
+                &fee_payer.pubkey,
+                &result,
+            )?;
+            fee_payer.add_cosigners(input.signers);
+            run_inputs.push(RunInput {
+                proof_path,
+                witness_path,
+                instruction: input.instruction,
+            });
+        }
+    }
+    let mut transaction = build_verify_transaction(
+        &fee_payer.pubkey,
+        &verify.instruction,
+        MAX_COMPUTE_UNITS,
+        heap_frame,
+    );
 
-    // Create compute budget instruction automatically
-    // Use MAX_COMPUTE_UNITS as default to ensure sufficient budget for any proof size
-    let compute_budget_program_id =
-        Pubkey::from_str("ComputeBudget111111111111111111111111111111")?;
-    let compute_units = MAX_COMPUTE_UNITS;
+    let so_path = local_circuit
+        .as_ref()
+        .map(|circuit| current_dir.join(super::run::target_file(circuit, "so")));
+
+    // Start or attach to the local validator; one started here is stopped
+    // when `_validator` goes out of scope
+    let mut _validator = None;
+    let mut backend = if options.offline {
+        let so_path = so_path.unwrap_or_default();
+        let program = fs::read(&so_path).map_err(|_| {
+            ZkLenseError::MissingArtifact(format!(
+                "Solana program not found at {}\nRun 'zklense run' first to build it.",
+                so_path.display()
+            ))
+        })?;
+        let mut runtime =
+            svm::Runtime::new(&program_id, &program, &fee_payer.pubkey, checks.sig_verify)?;
+        if let Some((spec, _)) = &buffer
+            && let Some(buffer_spec) = &spec.buffer
+            && let Some(so) = &buffer_spec.so
+        {
+            let so_path = current_dir.join(so);
+            let program = fs::read(&so_path).map_err(|_| {
+                ZkLenseError::MissingArtifact(format!(
+                    "Buffer program not found at {}",
+                    so_path.display()
+                ))
+            })?;
+            runtime.add_program(&buffer::program_id(buffer_spec, &program_id)?, &program)?;
+        }
+        Backend::Offline {
+            runtime: Box::new(runtime),
+        }
+    } else {
+        if let Some(so_path) = &so_path {
+            let validator =
+                localnet::start_or_attach(&current_dir, &rpc_url, &program_id, so_path).await?;
+            if options.keep_validator && validator.started() {
+                if let Some(pid) = validator.keep_running() {
+                    ui::info(&format!(
+                        "Validator left running (pid {}, log {})",
+                        pid,
+                        localnet::log_path(&current_dir).display()
+                    ));
+                }
+            } else {
+                _validator = Some(validator);
+            }
+        }
+        Backend::connect(&network, &rpc_url, &fee_payer.pubkey, checks).await?
+    };
 
-    let mut compute_unit_limit_data = vec![2u8, 0, 0, 0];
-    compute_unit_limit_data.extend_from_slice(&compute_units.to_le_bytes());
+    // Plan the buffer writes once the rent for the buffer is known
+    let mut delivery = None;
+    if let Some((spec, keypair)) = buffer {
+        let buffer_spec = spec.buffer.unwrap_or_default();
+        let payload = create_instruction_data(&proof_result);
+        let rent = backend
+            .minimum_balance_for_rent_exemption(buffer::buffer_size(&buffer_spec, payload.len()))
+            .await?;
+        delivery = Some(DeliveryPlan::new(
+            &buffer_spec,
+            &program_id,
+            &fee_payer.pubkey,
+            &keypair.pubkey(),
+            &payload,
+            rent,
+        )?);
+        fee_payer.add_cosigners(vec![keypair]);
+    }
 
-    let compute_unit_limit_ix = Instruction {
-        program_id: compute_budget_program_id,
-        accounts: vec![],
-        data: compute_unit_limit_data,
+    // A real payer must be able to cover the fee at the requested limit,
+    // plus any buffer writes and rent
+    let balance = match fee_payer.source {
+        fee_payer::Source::File(_) => backend.balance(&fee_payer.pubkey).await,
+        _ => None,
+    };
+    let required_fee = estimated_fee(&transaction)
+        + delivery.as_ref().map_or(0, |plan| {
+            plan.rent_lamports + plan.transactions.iter().map(estimated_fee).sum::<u64>()
+        });
+    if let Some(balance) = balance
+        && balance < required_fee
+    {
+        ui::panel_error(
+            "INSUFFICIENT FUNDS",
+            &format!(
+                "Fee payer {} holds {} lamports but the transaction costs up to {} lamports.",
+                fee_payer.pubkey,
+                format_number(balance),
+                format_number(required_fee)
+            ),
+            None,
+            Some(&[
+                &format!(
+                    "Fund it on devnet: solana airdrop 1 {} --url devnet",
+                    fee_payer.pubkey
+                ),
+                "Or use another keypair: zklense simulate --keypair <path>",
+            ]),
+        );
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Fee payer {} has {} lamports, {} needed",
+            fee_payer.pubkey, balance, required_fee
+        )));
+    }
+    let (network_label, rpc_label) = match &backend {
+        Backend::Rpc { .. } => (network.to_string(), rpc_url.clone()),
+        Backend::Offline { .. } => (svm::NETWORK.to_string(), svm::RPC_URL.to_string()),
     };
 
-    // Build transaction with compute budget and verify instructions
-    let mut transaction = Transaction::new_with_payer(
-        &[compute_unit_limit_ix, verify_instruction],
-        Some(&fee_payer),
-    );
+    // Simulate as a v0 transaction, through the given lookup tables or one
+    // created for the verify instruction's accounts
+    let lookup_tables = if !options.v0 {
+        None
+    } else if options.create_lookup_table {
+        let candidates = versioned::lookup_candidates(&transaction);
+        if candidates.is_empty() {
+            ui::warn(
+                "The verify instruction has no accounts to look up; simulating v0 without a lookup table",
+            );
+            Some(Vec::new())
+        } else {
+            Some(vec![
+                backend.create_lookup_table(&fee_payer, candidates).await?,
+            ])
+        }
+    } else {
+        Some(backend.fetch_lookup_tables(&lookup_table_addresses).await?)
+    };
 
-    // Get blockhash
-    let blockhash = connection.get_latest_blockhash().await
-        .map_err(|e| handle_rpc_error(anyhow::Error::from(e).context("Failed to get latest blockhash"), &rpc_url))?;
-    transaction.message.recent_blockhash = blockhash;
+    let delivery_results = match &mut delivery {
+        Some(plan) => deliver(&mut backend, plan, &fee_payer).await?,
+        None => Vec::new(),
+    };
 
-    ui::spinner_success_with_duration(
-        &spinner,
-        &format!("Connected to {}", network),
-        start.elapsed().as_millis(),
-    );
+    let simulation_start = Instant::now();
+    let message = if options.offline {
+        "Simulating transaction in-process..."
+    } else {
+        "Simulating transaction..."
+    };
+    let (sim_result, first_retries) = if repeated {
+        // Every run retries transient RPC failures, this one included
+        let spinner = ui::spinner(message);
+        match simulate_with_retries(
+            &mut backend,
+            &mut transaction,
+            &fee_payer,
+            lookup_tables.as_deref(),
+        )
+        .await
+        {
+            (Ok(result), retries) => {
+                ui::spinner_success_with_duration(
+                    &spinner,
+                    "Simulation complete",
+                    simulation_start.elapsed().as_millis(),
+                );
+                (result, retries)
+            }
+            (Err(e), _) => {
+                ui::spinner_error(&spinner, "Simulation failed");
+                return Err(e);
+            }
+        }
+    } else {
+        let result = backend
+            .simulate(
+                &mut transaction,
+                &fee_payer,
+                lookup_tables.as_deref(),
+                message,
+            )
+            .await?;
+        (result, 0)
+    };
+    let fee_accounts = priority_fee::writable_accounts(&transaction, &program_id);
+    let recent_prioritization_fees = backend.recent_prioritization_fees(&fee_accounts).await;
+
+    let template = VerifyTemplate {
+        fee_payer: &fee_payer,
+        instruction: &verify.instruction,
+        lookup_tables: lookup_tables.as_deref(),
+        cu_limit: MAX_COMPUTE_UNITS,
+        heap_frame,
+    };
 
-    // Simulate the transaction
-    let start = Instant::now();
-    let spinner = ui::spinner("Simulating transaction...");
-
-    let sim_response = connection.simulate_transaction(&transaction).await
-        .map_err(|e| handle_rpc_error(anyhow::Error::from(e).context("Failed to simulate transaction"), &rpc_url))?;
-
-    ui::spinner_success_with_duration(&spinner, "Simulation complete", start.elapsed().as_millis());
-
-    // Fetch recent prioritization fees (non-blocking, with warning on failure)
-    let spinner = ui::spinner("Fetching prioritization fees...");
-    let recent_prioritization_fees = match connection.get_recent_prioritization_fees(&[]).await {
-        Ok(fees_vec) => {
-            let fees: Vec<serde_json::Value> = fees_vec
-                .iter()
-                .rev()
-                .take(50)
-                .map(|fee| {
-                    json!({
-                        "slot": fee.slot,
-                        "prioritization_fee": fee.prioritization_fee
-                    })
-                })
-                .collect();
-            ui::spinner_success(&spinner, "Fetched prioritization fees");
-            Some(json!(fees))
+    // Tighten the compute unit limit once verification is known to succeed
+    let cu_limit_recommendation = if !options.tune_cu {
+        None
+    } else if sim_result.err.is_some() {
+        ui::warn("Skipping compute unit limit tuning because the simulation failed");
+        None
+    } else {
+        let margin = options
+            .cu_margin
+            .unwrap_or(compute_budget::DEFAULT_CU_MARGIN_PERCENT);
+        if !margin.is_finite() || margin < 0.0 {
+            return Err(ZkLenseError::InvalidInput(format!(
+                "--cu-margin must be a non-negative percentage, got {}",
+                margin
+            )));
         }
-        Err(_) => {
-            ui::spinner_warn(&spinner, "Could not fetch prioritization fees");
-            None
+        let cu_price = match parse_compute_budget_instructions(&transaction).1 {
+            0 => median_prioritization_fee(recent_prioritization_fees.as_deref()),
+            price => price,
+        };
+        Some(
+            tune_cu_limit(
+                &backend,
+                template,
+                sim_result.units_consumed.unwrap_or(0),
+                margin,
+                cu_price,
+            )
+            .await?,
+        )
+    };
+
+    // Search for the heap frame verification needs, below the requested one
+    // when it succeeded and above it when the program ran out of heap
+    let requested_heap = heap_frame.unwrap_or(compute_budget::DEFAULT_HEAP_FRAME);
+    let heap_exhausted = sim_result.err.is_some()
+        && diagnose::heap_exhausted(sim_result.logs.as_deref().unwrap_or_default());
+    let heap_search = if heap_exhausted && requested_heap < compute_budget::MAX_HEAP_FRAME {
+        ui::warn("The program ran out of heap; searching for the heap frame it needs");
+        Some(
+            find_min_heap_frame(
+                &backend,
+                template,
+                requested_heap + compute_budget::HEAP_FRAME_GRANULARITY,
+                compute_budget::MAX_HEAP_FRAME,
+                false,
+            )
+            .await?,
+        )
+    } else if !options.measure_heap {
+        None
+    } else if sim_result.err.is_some() {
+        ui::warn("Skipping the heap frame search because the simulation failed");
+        None
+    } else {
+        Some(
+            find_min_heap_frame(
+                &backend,
+                template,
+                compute_budget::DEFAULT_HEAP_FRAME,
+                requested_heap,
+                true,
+            )
+            .await?,
+        )
+    };
+
+    // Repeat the simulation for statistics, cycling through the inputs
+    let (run_statistics, batch_report) = if repeated {
+        let first = RunSample {
+            run: 1,
+            proof_path: proof_path.display().to_string(),
+            witness_path: witness_path.display().to_string(),
+            units_consumed: sim_result.units_consumed,
+            error: sim_result.err.as_ref().map(|e| e.to_string()),
+            retries: first_retries,
+            duration_ms: simulation_start.elapsed().as_millis() as u64,
+        };
+        let samples =
+            repeat_simulation(&mut backend, template, &run_inputs, run_count, first).await;
+        match &options.batch {
+            Some(source) => {
+                let cu_price = match parse_compute_budget_instructions(&transaction).1 {
+                    0 => median_prioritization_fee(recent_prioritization_fees.as_deref()),
+                    price => price,
+                };
+                let entries = batch_entries(template, &run_inputs, samples, cu_price);
+                (None, Some(batch::summarize(source, entries)))
+            }
+            None => (Some(runs::summarize(samples)), None),
         }
+    } else {
+        (None, None)
     };
 
     ui::blank();
 
     // Print formatted results to console
     print_simulation_results(
-        &sim_response.value,
+        &sim_result,
         &transaction,
         proof_size,
         witness_size,
         &proof_path,
         &witness_path,
     );
+    if let Some(layout) = &verify.layout {
+        instruction_spec::print_layout(layout);
+    }
+    // Explain a failure from the error, the logs and the local artifacts
+    let diagnosis = sim_result.err.as_ref().map(|err| {
+        diagnose::diagnose(
+            err,
+            sim_result.logs.as_deref().unwrap_or_default(),
+            &diagnose::Context {
+                transaction: &transaction,
+                program_id: &program_id,
+                network: &network_label,
+                artifacts: diagnose::check_artifacts(&current_dir, &proof_result),
+            },
+        )
+    });
+    if let Some(diagnosis) = &diagnosis {
+        diagnose::print_diagnosis(diagnosis);
+    }
+    let buffered_delivery = delivery
+        .as_ref()
+        .map(|plan| plan.summarize(&delivery_results, &transaction, &sim_result));
+    if let Some(buffered_delivery) = &buffered_delivery {
+        buffer::print_delivery(buffered_delivery);
+    }
+    if let Some(recommendation) = &cu_limit_recommendation {
+        print_cu_limit_recommendation(recommendation);
+    }
+    let transaction_encodings = match &lookup_tables {
+        Some(lookup_tables) => Some(versioned::compare(&transaction, lookup_tables)?),
+        None => None,
+    };
+    if let Some(encodings) = &transaction_encodings {
+        versioned::print_comparison(encodings);
+    }
+    if let Some(statistics) = &run_statistics {
+        runs::print_statistics(statistics);
+    }
+    if let Some(batch) = &batch_report {
+        batch::print_batch(batch, &current_dir);
+    }
 
-    // Create JSON output
-    let simulation_json = create_simulation_json(
-        &sim_response.value,
+    // Create the report
+    let mut report = create_simulation_report(
+        &sim_result,
         &transaction,
         proof_size,
         witness_size,
         recent_prioritization_fees,
         &program_id,
-        &network,
-        &rpc_url,
+        &network_label,
+        &rpc_label,
     );
+    // Fee tiers assume the tuned limit, or the one tuning would recommend
+    let units_consumed = report.compute_units.total_compute_units_consumed;
+    let recommended_cu_limit = match &cu_limit_recommendation {
+        Some(recommendation) => recommendation.recommended_cu_limit,
+        None => compute_budget::recommended_cu_limit(
+            units_consumed,
+            compute_budget::DEFAULT_CU_MARGIN_PERCENT,
+        ),
+    };
+    report.priority_fee_estimates =
+        report
+            .recent_prioritization_fees
+            .as_deref()
+            .and_then(|samples| {
+                priority_fee::estimate(
+                    samples,
+                    &fee_accounts,
+                    units_consumed,
+                    recommended_cu_limit,
+                    report.cost.base_fee,
+                )
+            });
+    if let Some(estimates) = &report.priority_fee_estimates {
+        priority_fee::print_estimates(estimates);
+        report.cost.suggestion = priority_fee::suggestion(estimates);
+    }
+    report.cu_limit_recommendation = cu_limit_recommendation;
+    report.fee_payer = Some(FeePayerInfo {
+        address: fee_payer.pubkey.to_string(),
+        source: fee_payer.describe(),
+        signed: fee_payer.signs(),
+        sig_verify: checks.sig_verify,
+        replace_recent_blockhash: checks.replace_recent_blockhash,
+        balance,
+    });
+    report.instruction = verify.layout;
+    report.buffered_delivery = buffered_delivery;
+    if let Some(search) = heap_search {
+        print_heap_search(&search);
+        report.heap_usage = heap_report(requested_heap, heap_exhausted, Some(search));
+    }
+    if let Some(diagnosis) = diagnosis {
+        report.transaction_status.suggestion = diagnosis.fix.clone();
+        report.transaction_status.diagnosis = Some(diagnosis);
+    }
+    if let Some(encodings) = &transaction_encodings {
+        report.transaction_size =
+            size_report(encodings.v0.transaction_size, encodings.v0.message_size);
+    }
+    report.transaction_encodings = transaction_encodings;
+    report.runs = run_statistics;
+    report.batch = batch_report;
+    report.metadata = Some(report_metadata(&current_dir, &proof_result, &proof_path));
 
-    let json_output = serde_json::to_string_pretty(&simulation_json)?;
-
-    // Save to .zklense/report.json
+    // Save to .zklense/reports/ and update .zklense/report.json
     let spinner = ui::spinner("Saving report...");
-    let zklense_dir = std::env::current_dir()?.join(".zklense");
-    fs::create_dir_all(&zklense_dir).with_context(|| {
+    let history_path = history::save_report(&current_dir, &mut report).with_context(|| {
         format!(
-            "Failed to create .zklense directory: {}",
-            zklense_dir.display()
+            "Failed to save report to: {}",
+            history::reports_dir(&current_dir).display()
         )
     })?;
-
-    let report_path = zklense_dir.join("report.json");
-    fs::write(&report_path, &json_output)
-        .with_context(|| format!("Failed to write report to: {}", report_path.display()))?;
+    let report_path = history::latest_report_path(&current_dir);
+    let report_id = report
+        .metadata
+        .as_ref()
+        .map(|m| m.id.clone())
+        .unwrap_or_default();
 
     ui::spinner_success(
         &spinner,
-        &format!("Report saved to {}", style(report_path.display()).dim()),
+        &format!(
+            "Report {} saved to {}",
+            report_id,
+            style(history_path.display()).dim()
+        ),
     );
 
+    let budget_checks = budget::check_budgets(&budgets, &report);
+    if !budget_checks.is_empty() {
+        ui::blank();
+        budget::print_budget_checks(&budget_checks);
+    }
+    let exceeded: Vec<&str> = budget_checks
+        .iter()
+        .filter(|c| !c.ok())
+        .map(|c| c.name)
+        .collect();
+
     // Success panel
-    let is_success = sim_response.value.err.is_none();
-    if is_success {
+    let is_success = sim_result.err.is_none();
+    let batch_failed = report.batch.as_ref().map_or(0, |batch| batch.failed);
+    ui::emit_json(&json!({
+        "command": "simulate",
+        "status": if is_success && batch_failed == 0 { "success" } else { "failed" },
+        "report_id": report_id,
+        "report_path": report_path.display().to_string(),
+        "history_path": history_path.display().to_string(),
+        "budgets": budget::checks_json(&budget_checks),
+        "report": report,
+    }));
+    if is_success && !exceeded.is_empty() {
+        ui::panel_warning(
+            "SIMULATION COMPLETE (OVER BUDGET)",
+            &format!(
+                "Transaction simulation was successful but exceeded: {}\n\nView full report: {}",
+                exceeded.join(", "),
+                report_path.display()
+            ),
+        );
+        return Err(ZkLenseError::BudgetExceeded(format!(
+            "Simulation exceeded {} budget(s): {}",
+            exceeded.len(),
+            exceeded.join(", ")
+        )));
+    } else if is_success && batch_failed > 0 {
+        let inputs = report.batch.as_ref().map_or(0, |batch| batch.inputs);
+        ui::panel_warning(
+            "BATCH COMPLETE (WITH FAILURES)",
+            &format!(
+                "{} of {} inputs failed verification.\n\nView full report: {}",
+                batch_failed,
+                inputs,
+                report_path.display()
+            ),
+        );
+        return Err(ZkLenseError::SimulationFailed(format!(
+            "{} of {} batch inputs failed",
+            batch_failed, inputs
+        )));
+    } else if is_success {
         ui::panel_success(
             "SIMULATION COMPLETE",
             &format!(
@@ -741,7 +2208,15 @@ pub async fn run_simulate(program_id_arg: Option<String>) -> Result<()> {
                 report_path.display()
             ),
         );
+        let explanation = match &report.transaction_status.diagnosis {
+            Some(diagnosis) => diagnosis.explanation.clone(),
+            None => format!("{:?}", sim_result.err.as_ref().unwrap()),
+        };
+        return Err(ZkLenseError::SimulationFailed(format!(
+            "Transaction simulation failed: {}",
+            explanation
+        )));
     }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,602 @@
+//! Send the verify transaction and report the confirmed result
+//!
+//! `zklense submit` builds the same transaction as `zklense simulate`, signs
+//! it with the fee payer keypair and sends it. A send that fails in transit is
+//! retried with the same signed transaction, and only a transaction whose
+//! blockhash has expired unconfirmed is re-signed with a fresh one, so a retry
+//! can never land twice. The confirmed transaction's compute units, fee and
+//! logs are written to the usual report with `mode: submitted`.
+
+use console::style;
+use serde_json::json;
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::{
+    hash::Hash, native_token::LAMPORTS_PER_SOL, signature::Signature, transaction::Transaction,
+};
+use std::time::{Duration, Instant};
+
+use super::compute_budget::MAX_COMPUTE_UNITS;
+use super::diagnose;
+use super::fee_payer::FeePayer;
+use super::history;
+use super::init::{KEYPAIR_PATH_KEY, SolanaNetwork, read_config};
+use super::instruction_spec;
+use super::localnet;
+use super::report::{FeePayerInfo, ReportMode, Submission};
+use super::simulate::{
+    build_verify_transaction, create_simulation_report, estimated_fee, format_number,
+    handle_rpc_error, print_simulation_results, read_proof_files, report_metadata, resolve_network,
+    resolve_program_id, validate_heap_frame,
+};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Resends after the first attempt unless overridden
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+/// How long the confirmed transaction may take to become queryable
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Roughly how long a blockhash stays valid (150 blocks)
+const BLOCKHASH_LIFETIME: Duration = Duration::from_secs(90);
+
+/// Options for the submit command
+#[derive(Debug, Default)]
+pub struct SubmitOptions {
+    pub program_id: Option<String>,
+    /// Fee payer keypair file, overriding the configured one
+    pub keypair: Option<String>,
+    /// `processed`, `confirmed` (default) or `finalized`
+    pub commitment: Option<String>,
+    /// Times to resend after the blockhash of an unconfirmed attempt expires
+    pub max_retries: Option<u32>,
+    /// Compute unit limit to request instead of the maximum
+    pub cu_limit: Option<u32>,
+    /// Heap frame to request in bytes
+    pub heap_frame: Option<u32>,
+    /// Submit to a local validator regardless of the configured network
+    pub localnet: bool,
+    /// Leave a validator started by zklense running afterwards
+    pub keep_validator: bool,
+}
+
+fn parse_commitment(commitment: Option<&str>) -> error::Result<CommitmentConfig> {
+    let level = match commitment {
+        None => CommitmentLevel::Confirmed,
+        Some(level) => level.parse().map_err(|_| {
+            ZkLenseError::InvalidInput(format!(
+                "Invalid commitment '{}'. Valid options: processed, confirmed, finalized",
+                level
+            ))
+        })?,
+    };
+    Ok(CommitmentConfig { commitment: level })
+}
+
+/// Whether a send failure is worth retrying: transport and node errors are,
+/// a preflight simulation rejecting the transaction is not
+fn is_transient(error: &ClientError) -> bool {
+    error.get_transaction_error().is_none()
+}
+
+/// Whether the next attempt must resend the already-signed transaction.
+/// Until its blockhash expires (`block_height` past `last_valid_block_height`)
+/// it may still land, so signing it again would risk paying twice; an unknown
+/// block height counts as not expired.
+fn must_resend_signed(last_valid_block_height: Option<u64>, block_height: Option<u64>) -> bool {
+    match (last_valid_block_height, block_height) {
+        (None, _) => false,
+        (Some(last_valid), Some(height)) => height <= last_valid,
+        (Some(_), None) => true,
+    }
+}
+
+/// Whether to stop waiting for a sent transaction. Expiry is normally seen
+/// through the block height, but an unreachable node never reports it, so the
+/// wait is also capped at the blockhash lifetime plus the fetch timeout.
+fn confirmation_timed_out(elapsed: Duration) -> bool {
+    elapsed > BLOCKHASH_LIFETIME + FETCH_TIMEOUT
+}
+
+/// Sign and send `transaction` until it is confirmed at `commitment`.
+/// Returns the signature, the number of attempts and the on-chain result.
+async fn send_until_confirmed(
+    client: &RpcClient,
+    rpc_url: &str,
+    transaction: &mut Transaction,
+    fee_payer: &FeePayer,
+    commitment: CommitmentConfig,
+    max_retries: u32,
+) -> error::Result<(Signature, u32, Option<String>)> {
+    let send_config = RpcSendTransactionConfig {
+        preflight_commitment: Some(commitment.commitment),
+        ..Default::default()
+    };
+    // A resend may reach a node that already has the transaction; without
+    // preflight the duplicate is dropped instead of rejected
+    let resend_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        ..send_config
+    };
+    let attempts = max_retries + 1;
+    // Last valid block height of the signed transaction while it may still land
+    let mut signed_until: Option<u64> = None;
+
+    for attempt in 1..=attempts {
+        if attempt > 1 {
+            let backoff = Duration::from_secs(1 << (attempt - 2).min(4));
+            ui::warn(&format!(
+                "Retrying in {}s (attempt {} of {})",
+                backoff.as_secs(),
+                attempt,
+                attempts
+            ));
+            tokio::time::sleep(backoff).await;
+        }
+
+        let block_height = match signed_until {
+            Some(_) => client.get_block_height().await.ok(),
+            None => None,
+        };
+        let resend = must_resend_signed(signed_until, block_height);
+        if !resend {
+            let (blockhash, last_valid_block_height) = client
+                .get_latest_blockhash_with_commitment(commitment)
+                .await
+                .map_err(|e| {
+                    handle_rpc_error(
+                        anyhow::Error::from(e).context("Failed to get latest blockhash"),
+                        rpc_url,
+                    )
+                })?;
+            fee_payer.sign(transaction, blockhash);
+            signed_until = Some(last_valid_block_height);
+        }
+        let last_valid_block_height = signed_until.unwrap_or_default();
+
+        let spinner = ui::spinner("Sending transaction...");
+        let config = if resend { resend_config } else { send_config };
+        let signature = match client
+            .send_transaction_with_config(&*transaction, config)
+            .await
+        {
+            Ok(signature) => signature,
+            Err(e) if is_transient(&e) && attempt < attempts => {
+                // The node may have forwarded it anyway; keep the signature
+                ui::spinner_warn(&spinner, &format!("Send failed: {}", e));
+                continue;
+            }
+            Err(e) => {
+                ui::spinner_error(&spinner, "Transaction rejected");
+                return Err(handle_rpc_error(
+                    anyhow::Error::from(e).context("Failed to send transaction"),
+                    rpc_url,
+                ));
+            }
+        };
+
+        // Wait for confirmation until the blockhash expires; after that the
+        // transaction can no longer land and it is safe to resend
+        spinner.set_message(format!(
+            "Waiting for {:?} confirmation...",
+            commitment.commitment
+        ));
+        let start = Instant::now();
+        loop {
+            if let Ok(Some(result)) = client
+                .get_signature_status_with_commitment(&signature, commitment)
+                .await
+            {
+                ui::spinner_success_with_duration(
+                    &spinner,
+                    &format!("Transaction {:?}", commitment.commitment),
+                    start.elapsed().as_millis(),
+                );
+                return Ok((signature, attempt, result.err().map(|e| e.to_string())));
+            }
+            if let Ok(height) = client.get_block_height().await
+                && height > last_valid_block_height
+            {
+                ui::spinner_warn(
+                    &spinner,
+                    &format!("Transaction {} expired unconfirmed", signature),
+                );
+                signed_until = None;
+                break;
+            }
+            if confirmation_timed_out(start.elapsed()) {
+                ui::spinner_error(&spinner, "Could not confirm transaction");
+                return Err(ZkLenseError::Rpc(format!(
+                    "Transaction {} was sent but its status could not be read within {}s; check it with 'solana confirm {}' before resubmitting",
+                    signature,
+                    start.elapsed().as_secs(),
+                    signature
+                )));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    Err(ZkLenseError::Rpc(format!(
+        "Transaction was not confirmed after {} attempt(s)",
+        attempts
+    )))
+}
+
+/// Fetch the landed transaction's execution results in the shape of a
+/// simulation result, plus its slot and the fee charged
+async fn fetch_confirmed(
+    client: &RpcClient,
+    rpc_url: &str,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> error::Result<(RpcSimulateTransactionResult, u64, u64)> {
+    // Landed transactions cannot be queried at `processed`
+    let commitment = if commitment.commitment == CommitmentLevel::Processed {
+        CommitmentConfig::confirmed()
+    } else {
+        commitment
+    };
+    let config = RpcTransactionConfig {
+        encoding: None,
+        commitment: Some(commitment),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let spinner = ui::spinner("Fetching confirmed transaction...");
+    let start = Instant::now();
+    let confirmed = loop {
+        match client.get_transaction_with_config(signature, config).await {
+            Ok(confirmed) => break confirmed,
+            Err(_) if start.elapsed() < FETCH_TIMEOUT => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            Err(e) => {
+                ui::spinner_error(&spinner, "Could not fetch transaction");
+                return Err(handle_rpc_error(
+                    anyhow::Error::from(e).context("Failed to fetch confirmed transaction"),
+                    rpc_url,
+                ));
+            }
+        }
+    };
+    let Some(meta) = confirmed.transaction.meta else {
+        ui::spinner_error(&spinner, "Transaction has no status metadata");
+        return Err(ZkLenseError::Rpc(format!(
+            "RPC node returned no metadata for {}",
+            signature
+        )));
+    };
+    ui::spinner_success(&spinner, "Fetched confirmed transaction");
+
+    let result = RpcSimulateTransactionResult {
+        err: meta.err,
+        logs: meta.log_messages.into(),
+        accounts: None,
+        units_consumed: meta.compute_units_consumed.into(),
+        loaded_accounts_data_size: None,
+        return_data: None,
+        inner_instructions: None,
+        replacement_blockhash: None,
+        fee: Some(meta.fee),
+        pre_balances: None,
+        post_balances: None,
+        pre_token_balances: None,
+        post_token_balances: None,
+        loaded_addresses: None,
+    };
+    Ok((result, confirmed.slot, meta.fee))
+}
+
+/// Run the submit command
+pub async fn run_submit(options: SubmitOptions) -> error::Result<()> {
+    ui::panel_header(
+        emoji::ROCKET,
+        "TRANSACTION SUBMISSION",
+        Some("Send ZK proof verification to Solana"),
+    );
+
+    let current_dir = std::env::current_dir()?;
+    let config = read_config(&current_dir).map_err(|e| {
+        ZkLenseError::Config(format!(
+            "Failed to read config: {}. Run 'zklense init' first.",
+            e
+        ))
+    })?;
+    let (network, rpc_url) = resolve_network(&config, options.localnet);
+    let commitment = parse_commitment(options.commitment.as_deref())?;
+    let max_retries = options.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+    let cu_limit = options.cu_limit.unwrap_or(MAX_COMPUTE_UNITS);
+    if cu_limit == 0 || cu_limit > MAX_COMPUTE_UNITS {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "--cu-limit must be between 1 and {}",
+            format_number(MAX_COMPUTE_UNITS as u64)
+        )));
+    }
+    let heap_frame = validate_heap_frame(options.heap_frame)?;
+
+    let local_circuit = if network == SolanaNetwork::Localnet {
+        Some(super::run::read_circuit_name(&current_dir)?)
+    } else {
+        None
+    };
+    let program_id = resolve_program_id(options.program_id, local_circuit.as_deref())?;
+
+    let mut fee_payer = FeePayer::resolve(
+        options.keypair.as_deref(),
+        config.get(KEYPAIR_PATH_KEY).map(|s| s.as_str()),
+        &current_dir,
+        local_circuit.is_some(),
+    )?;
+    if !fee_payer.signs() {
+        return Err(ZkLenseError::Config(
+            "Submitting needs a fee payer keypair; pass --keypair or run 'zklense config set-keypair'"
+                .to_string(),
+        ));
+    }
+    ui::info(&format!(
+        "Fee payer {} ({})",
+        fee_payer.pubkey,
+        style(fee_payer.describe()).dim()
+    ));
+    ui::blank();
+
+    let (proof_result, proof_path, witness_path) = read_proof_files()?;
+    let proof_size = proof_result.proof.len();
+    let witness_size = proof_result.public_witness.len();
+    let verify = instruction_spec::verify_instruction(
+        &current_dir,
+        &program_id,
+        &fee_payer.pubkey,
+        &proof_result,
+    )?;
+    fee_payer.add_cosigners(verify.signers);
+    let mut transaction =
+        build_verify_transaction(&fee_payer.pubkey, &verify.instruction, cu_limit, heap_frame);
+    // Signer accounts without a keypair would leave the transaction unsigned
+    fee_payer.sign(&mut transaction, Hash::default());
+    if !transaction.is_signed() {
+        return Err(ZkLenseError::Config(format!(
+            "Every signer account needs a keypair to submit; add `keypair` to the signer accounts in {}",
+            instruction_spec::spec_path(&current_dir).display()
+        )));
+    }
+
+    // Sending spends real lamports outside local networks
+    if network != SolanaNetwork::Localnet {
+        let proceed = ui::confirm_custom(
+            &format!(
+                "Send the verify transaction to {}? It costs up to {} lamports.",
+                network,
+                format_number(estimated_fee(&transaction))
+            ),
+            &format!("{} Yes, send it", emoji::CHECKMARK),
+            &format!("{} No, cancel", emoji::CROSSMARK),
+        )?;
+        if !proceed {
+            return Err(ZkLenseError::UserAbort("Submission cancelled".to_string()));
+        }
+    }
+
+    // Start or attach to the local validator; one started here is stopped
+    // when `_validator` goes out of scope
+    let mut _validator = None;
+    if let Some(circuit) = &local_circuit {
+        let so_path = current_dir.join(super::run::target_file(circuit, "so"));
+        let validator =
+            localnet::start_or_attach(&current_dir, &rpc_url, &program_id, &so_path).await?;
+        if options.keep_validator && validator.started() {
+            if let Some(pid) = validator.keep_running() {
+                ui::info(&format!(
+                    "Validator left running (pid {}, log {})",
+                    pid,
+                    localnet::log_path(&current_dir).display()
+                ));
+            }
+        } else {
+            _validator = Some(validator);
+        }
+    }
+
+    let client = RpcClient::new_with_commitment(rpc_url.clone(), commitment);
+    if network == SolanaNetwork::Localnet {
+        localnet::fund_fee_payer(&client, &fee_payer.pubkey).await?;
+    }
+    let balance = client.get_balance(&fee_payer.pubkey).await.ok();
+
+    let (signature, attempts, chain_error) = send_until_confirmed(
+        &client,
+        &rpc_url,
+        &mut transaction,
+        &fee_payer,
+        commitment,
+        max_retries,
+    )
+    .await?;
+    let (result, slot, fee) = fetch_confirmed(&client, &rpc_url, &signature, commitment).await?;
+
+    ui::blank();
+    print_simulation_results(
+        &result,
+        &transaction,
+        proof_size,
+        witness_size,
+        &proof_path,
+        &witness_path,
+    );
+    if let Some(layout) = &verify.layout {
+        instruction_spec::print_layout(layout);
+    }
+
+    let diagnosis = result.err.as_ref().map(|err| {
+        diagnose::diagnose(
+            err,
+            result.logs.as_deref().unwrap_or_default(),
+            &diagnose::Context {
+                transaction: &transaction,
+                program_id: &program_id,
+                network: network.as_str(),
+                artifacts: diagnose::check_artifacts(&current_dir, &proof_result),
+            },
+        )
+    });
+    if let Some(diagnosis) = &diagnosis {
+        diagnose::print_diagnosis(diagnosis);
+    }
+
+    ui::section(emoji::LINK, "Submission");
+    ui::print_tree(&[
+        ("Signature", &signature.to_string()),
+        ("Slot", &format_number(slot)),
+        ("Commitment", &format!("{:?}", commitment.commitment)),
+        ("Attempts", &attempts.to_string()),
+        (
+            "Fee Charged",
+            &format!(
+                "{} lamports ({:.9} SOL)",
+                format_number(fee),
+                fee as f64 / LAMPORTS_PER_SOL as f64
+            ),
+        ),
+    ]);
+    ui::blank();
+
+    let mut report = create_simulation_report(
+        &result,
+        &transaction,
+        proof_size,
+        witness_size,
+        None,
+        &program_id,
+        network.as_str(),
+        &rpc_url,
+    );
+    report.mode = ReportMode::Submitted;
+    report.submission = Some(Submission {
+        signature: signature.to_string(),
+        slot,
+        commitment: format!("{:?}", commitment.commitment).to_lowercase(),
+        attempts,
+        fee,
+    });
+    report.fee_payer = Some(FeePayerInfo {
+        address: fee_payer.pubkey.to_string(),
+        source: fee_payer.describe(),
+        signed: true,
+        sig_verify: true,
+        replace_recent_blockhash: false,
+        balance,
+    });
+    // The cluster's fee replaces the estimate
+    report.cost.total_fee = fee;
+    report.cost.cost_in_sol = fee as f64 / LAMPORTS_PER_SOL as f64;
+    report.instruction = verify.layout;
+    if let Some(diagnosis) = diagnosis {
+        report.transaction_status.suggestion = diagnosis.fix.clone();
+        report.transaction_status.diagnosis = Some(diagnosis);
+    }
+    report.metadata = Some(report_metadata(&current_dir, &proof_result, &proof_path));
+
+    let history_path = history::save_report(&current_dir, &mut report).map_err(|e| {
+        ZkLenseError::Other(format!(
+            "Failed to save report to {}: {}",
+            history::reports_dir(&current_dir).display(),
+            e
+        ))
+    })?;
+    let report_path = history::latest_report_path(&current_dir);
+    let report_id = report
+        .metadata
+        .as_ref()
+        .map(|m| m.id.clone())
+        .unwrap_or_default();
+    ui::success(&format!(
+        "Report {} saved to {}",
+        report_id,
+        style(history_path.display()).dim()
+    ));
+
+    ui::emit_json(&json!({
+        "command": "submit",
+        "status": if chain_error.is_none() { "success" } else { "failed" },
+        "signature": signature.to_string(),
+        "report_id": report_id,
+        "report_path": report_path.display().to_string(),
+        "history_path": history_path.display().to_string(),
+        "report": report,
+    }));
+
+    match chain_error {
+        None => {
+            ui::panel_success(
+                "TRANSACTION CONFIRMED",
+                &format!(
+                    "Proof verified on-chain in slot {}.\n\nView full report: {}",
+                    slot,
+                    report_path.display()
+                ),
+            );
+            Ok(())
+        }
+        Some(err) => {
+            ui::panel_warning(
+                "TRANSACTION FAILED",
+                &format!(
+                    "The transaction landed but failed: {}\n\nView full report: {}",
+                    err,
+                    report_path.display()
+                ),
+            );
+            ui_println!("  {} {}", emoji::INFO, style(signature).dim());
+            Err(ZkLenseError::SimulationFailed(format!(
+                "Transaction {} failed on-chain: {}",
+                signature, err
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_levels() {
+        assert_eq!(
+            parse_commitment(None).unwrap().commitment,
+            CommitmentLevel::Confirmed
+        );
+        assert_eq!(
+            parse_commitment(Some("finalized")).unwrap().commitment,
+            CommitmentLevel::Finalized
+        );
+        assert_eq!(parse_commitment(Some("final")).unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_resend_signed_until_blockhash_expires() {
+        // Nothing signed yet
+        assert!(!must_resend_signed(None, None));
+        assert!(!must_resend_signed(None, Some(200)));
+        // The first transaction may still land after a failed send
+        assert!(must_resend_signed(Some(150), Some(100)));
+        assert!(must_resend_signed(Some(150), Some(150)));
+        assert!(must_resend_signed(Some(150), None));
+        // Only an expired blockhash makes re-signing safe
+        assert!(!must_resend_signed(Some(150), Some(151)));
+    }
+
+    #[test]
+    fn test_confirmation_wait_is_bounded() {
+        assert!(!confirmation_timed_out(Duration::ZERO));
+        assert!(!confirmation_timed_out(BLOCKHASH_LIFETIME));
+        assert!(!confirmation_timed_out(BLOCKHASH_LIFETIME + FETCH_TIMEOUT));
+        assert!(confirmation_timed_out(
+            BLOCKHASH_LIFETIME + FETCH_TIMEOUT + POLL_INTERVAL
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+//! In-process simulation with an embedded Solana runtime
+//!
+//! `zklense simulate --offline` loads `target/<circuit>.so` into LiteSVM and
+//! executes the verify transaction without any RPC endpoint. Results are
+//! returned in the same shape as an RPC `simulateTransaction` response so
+//! the report is built exactly as for a network simulation.
+
+use litesvm::LiteSVM;
+use litesvm::types::TransactionMetadata;
+use solana_client::rpc_response::{RpcSimulateTransactionResult, UiTransactionError};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    message::AddressLookupTableAccount,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use super::fee_payer::FeePayer;
+use super::versioned;
+use crate::error::{self, ZkLenseError};
+
+/// Environment label recorded in offline reports
+pub const NETWORK: &str = "offline";
+/// Stand-in for the RPC URL in offline reports
+pub const RPC_URL: &str = "in-process";
+
+/// Embedded runtime with the verifier program loaded and the fee payer
+/// funded. Signatures are only checked with `sig_verify`; blockhashes never.
+pub struct Runtime {
+    svm: LiteSVM,
+}
+
+impl Runtime {
+    /// Load `program` (an SBF ELF) at `program_id` and fund `fee_payer`
+    pub fn new(
+        program_id: &Pubkey,
+        program: &[u8],
+        fee_payer: &Pubkey,
+        sig_verify: bool,
+    ) -> error::Result<Runtime> {
+        let mut runtime = Runtime {
+            svm: LiteSVM::new()
+                .with_sigverify(sig_verify)
+                .with_blockhash_check(false),
+        };
+        runtime.add_program(program_id, program)?;
+        runtime
+            .svm
+            .airdrop(fee_payer, 10 * LAMPORTS_PER_SOL)
+            .map_err(|e| ZkLenseError::Other(format!("Failed to fund fee payer: {:?}", e.err)))?;
+        Ok(runtime)
+    }
+
+    /// Load another program, e.g. a separate buffer program
+    pub fn add_program(&mut self, program_id: &Pubkey, program: &[u8]) -> error::Result<()> {
+        self.svm.add_program(*program_id, program).map_err(|e| {
+            ZkLenseError::InvalidInput(format!("Failed to load program {}: {:?}", program_id, e))
+        })
+    }
+
+    /// Store an active lookup table holding `addresses`, as if `authority`
+    /// had created it in the genesis slot
+    pub fn add_lookup_table(
+        &mut self,
+        authority: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> error::Result<AddressLookupTableAccount> {
+        let key = versioned::lookup_table_address(authority, 0);
+        let data = versioned::lookup_table_data(authority, &addresses)?;
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: solana_address_lookup_table_interface::program::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm
+            .set_account(key, account)
+            .map_err(|e| ZkLenseError::Other(format!("Failed to store lookup table: {:?}", e)))?;
+        Ok(AddressLookupTableAccount { key, addresses })
+    }
+
+    /// Blockhash to sign transactions with
+    pub fn latest_blockhash(&self) -> Hash {
+        self.svm.latest_blockhash()
+    }
+
+    /// Simulate a signed `transaction` without changing any state
+    pub fn simulate(&self, transaction: VersionedTransaction) -> RpcSimulateTransactionResult {
+        match self.svm.simulate_transaction(transaction) {
+            Ok(info) => to_rpc_result(None, info.meta),
+            Err(failed) => to_rpc_result(Some(failed.err.into()), failed.meta),
+        }
+    }
+
+    /// Sign and execute `transaction`, keeping the accounts it writes
+    pub fn execute(
+        &mut self,
+        transaction: &mut Transaction,
+        fee_payer: &FeePayer,
+    ) -> RpcSimulateTransactionResult {
+        fee_payer.sign(transaction, self.svm.latest_blockhash());
+        match self.svm.send_transaction(transaction.clone()) {
+            Ok(meta) => to_rpc_result(None, meta),
+            Err(failed) => to_rpc_result(Some(failed.err.into()), failed.meta),
+        }
+    }
+
+    /// Lamports an account of `data_len` bytes needs to be rent exempt
+    pub fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> u64 {
+        self.svm.minimum_balance_for_rent_exemption(data_len)
+    }
+}
+
+fn to_rpc_result(
+    err: Option<UiTransactionError>,
+    meta: TransactionMetadata,
+) -> RpcSimulateTransactionResult {
+    RpcSimulateTransactionResult {
+        err,
+        logs: Some(meta.logs),
+        accounts: None,
+        units_consumed: Some(meta.compute_units_consumed),
+        loaded_accounts_data_size: None,
+        return_data: None,
+        inner_instructions: None,
+        replacement_blockhash: None,
+        fee: Some(meta.fee),
+        pre_balances: None,
+        post_balances: None,
+        pre_token_balances: None,
+        post_token_balances: None,
+        loaded_addresses: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_program_is_rejected() {
+        let program_id = Pubkey::new_unique();
+        let payer = FeePayer::resolve(None, None, &std::env::temp_dir(), true).unwrap();
+        let err = Runtime::new(&program_id, b"not an ELF", &payer.pubkey, false)
+            .err()
+            .unwrap();
+        assert_eq!(err.exit_code(), 2);
+    }
+}
@@ -0,0 +1,510 @@
+//! Native Groth16 verification of sunspot artifacts
+//!
+//! Re-implements gnark's BN254 Groth16 verifier, including BSB22 commitments,
+//! so proofs can be checked without Go or sunspot installed.
+
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{One, PrimeField};
+use console::style;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::gnark::{self, Proof, VerifyingKey};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Domain separation tag gnark uses to hash commitments into public inputs
+const COMMITMENT_DST: &[u8] = b"bsb22-commitment";
+/// Domain separation tag for the commitment folding challenge
+const CHALLENGE_DST: &[u8] = b"G16-BSB22";
+
+/// Verify a Groth16 proof against a verifying key and public inputs.
+///
+/// Returns the values derived from the proof's commitments, which are
+/// appended to the public inputs before the pairing check.
+pub fn verify_proof(
+    vk: &VerifyingKey,
+    proof: &Proof,
+    public_inputs: &[Fr],
+) -> error::Result<Vec<Fr>> {
+    let nb_commitments = vk.public_and_commitment_committed.len();
+    if public_inputs.len() != vk.nb_public_inputs() {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Verifying key expects {} public inputs but the public witness has {}",
+            vk.nb_public_inputs(),
+            public_inputs.len()
+        )));
+    }
+    if proof.commitments.len() != nb_commitments {
+        return Err(ZkLenseError::InvalidInput(format!(
+            "Verifying key expects {} commitments but the proof has {}",
+            nb_commitments,
+            proof.commitments.len()
+        )));
+    }
+
+    // Hash each commitment, together with the public inputs it covers, into
+    // an extra public input
+    let mut commitment_values = Vec::with_capacity(nb_commitments);
+    let mut commitments_serialized = Vec::with_capacity(nb_commitments * gnark::FIELD_SIZE);
+    for (commitment, committed) in proof
+        .commitments
+        .iter()
+        .zip(&vk.public_and_commitment_committed)
+    {
+        let mut prehash = gnark::g1_raw_bytes(commitment);
+        for &index in committed {
+            let value = index
+                .checked_sub(1)
+                .and_then(|i| public_inputs.get(i as usize))
+                .ok_or_else(|| {
+                    ZkLenseError::InvalidInput(format!(
+                        "Verifying key commits to public input {} which does not exist",
+                        index
+                    ))
+                })?;
+            prehash.extend_from_slice(&gnark::fr_bytes(value));
+        }
+        let value = hash_to_field(&prehash, COMMITMENT_DST);
+        commitments_serialized.extend_from_slice(&gnark::fr_bytes(&value));
+        commitment_values.push(value);
+    }
+
+    if !vk.commitment_keys.is_empty() {
+        if vk.commitment_keys.len() != proof.commitments.len() {
+            return Err(ZkLenseError::InvalidInput(format!(
+                "Verifying key has {} commitment keys but the proof has {} commitments",
+                vk.commitment_keys.len(),
+                proof.commitments.len()
+            )));
+        }
+
+        // Fold the commitments with powers of the challenge and check the
+        // single proof of knowledge against them
+        let challenge = hash_to_field(&commitments_serialized, CHALLENGE_DST);
+        let mut g1: Vec<G1Affine> = Vec::with_capacity(vk.commitment_keys.len() + 1);
+        let mut g2: Vec<G2Affine> = Vec::with_capacity(vk.commitment_keys.len() + 1);
+        let mut r = challenge;
+        for (i, (commitment, key)) in proof
+            .commitments
+            .iter()
+            .zip(&vk.commitment_keys)
+            .enumerate()
+        {
+            if i == 0 {
+                g1.push(*commitment);
+            } else {
+                g1.push((*commitment * r).into_affine());
+                r *= challenge;
+            }
+            g2.push(key.g);
+        }
+        g1.push(proof.commitment_pok);
+        g2.push(vk.commitment_keys[0].g_sigma_neg);
+
+        if !Bn254::multi_pairing(g1, g2).0.is_one() {
+            return Err(ZkLenseError::VerificationFailed(
+                "Commitment proof of knowledge is invalid".to_string(),
+            ));
+        }
+    }
+
+    // Σ x_i·K_i over the public inputs and commitment values, plus the
+    // constant term and the commitments themselves
+    let scalars: Vec<Fr> = public_inputs
+        .iter()
+        .chain(&commitment_values)
+        .copied()
+        .collect();
+    let mut k_sum = G1Projective::msm(&vk.k[1..], &scalars)
+        .map_err(|_| ZkLenseError::InvalidInput("Malformed verifying key".to_string()))?;
+    k_sum += vk.k[0];
+    for commitment in &proof.commitments {
+        k_sum += commitment;
+    }
+
+    // e(A, B) == e(α, β)·e(Σ x_i·K_i, γ)·e(C, δ)
+    let result = Bn254::multi_pairing(
+        [proof.ar, -vk.alpha_g1, -k_sum.into_affine(), -proof.krs],
+        [proof.bs, vk.beta_g2, vk.gamma_g2, vk.delta_g2],
+    );
+    if !result.0.is_one() {
+        return Err(ZkLenseError::VerificationFailed(
+            "Pairing check failed: the proof does not match the verifying key and public inputs"
+                .to_string(),
+        ));
+    }
+
+    Ok(commitment_values)
+}
+
+/// RFC 9380 hash_to_field for the BN254 scalar field (one element, SHA-256)
+fn hash_to_field(msg: &[u8], dst: &[u8]) -> Fr {
+    // 16 bytes of headroom over the 32-byte field size keeps the bias negligible
+    let uniform = expand_message_xmd(msg, dst, 48);
+    Fr::from_be_bytes_mod_order(&uniform)
+}
+
+/// RFC 9380 expand_message_xmd with SHA-256
+fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+    let ell = len.div_ceil(32);
+    debug_assert!(ell <= 255 && dst.len() <= 255);
+
+    let mut dst_prime = dst.to_vec();
+    dst_prime.push(dst.len() as u8);
+
+    let b0 = Sha256::new()
+        .chain_update([0u8; BLOCK_SIZE])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut output = Vec::with_capacity(ell * 32);
+    let mut previous = Sha256::new()
+        .chain_update(b0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    output.extend_from_slice(&previous);
+
+    for i in 2..=ell {
+        let mixed: Vec<u8> = b0.iter().zip(previous.iter()).map(|(a, b)| a ^ b).collect();
+        previous = Sha256::new()
+            .chain_update(mixed)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        output.extend_from_slice(&previous);
+    }
+
+    output.truncate(len);
+    output
+}
+
+/// Resolve a file given on the command line, or default to the circuit's
+/// artifact in `target/`
+fn resolve_artifact(
+    base_path: &Path,
+    arg: Option<String>,
+    circuit: &mut Option<String>,
+    extension: &str,
+) -> error::Result<PathBuf> {
+    let path = match arg {
+        Some(p) => std::env::current_dir()?.join(p),
+        None => {
+            if circuit.is_none() {
+                *circuit = Some(super::run::read_circuit_name(base_path)?);
+            }
+            base_path.join(super::run::target_file(
+                circuit.as_deref().unwrap(),
+                extension,
+            ))
+        }
+    };
+
+    if !path.is_file() {
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "{} not found at: {}\nRun 'zklense run --to prove' first or pass the file with --{}.",
+            extension,
+            path.display(),
+            extension
+        )));
+    }
+    Ok(path)
+}
+
+/// Read and parse a gnark file, tagging parse errors with the file path
+fn read_artifact<T>(
+    path: &Path,
+    what: &str,
+    parse: fn(&[u8]) -> std::io::Result<T>,
+) -> error::Result<T> {
+    let bytes = fs::read(path)?;
+    parse(&bytes).map_err(|e| {
+        ZkLenseError::InvalidInput(format!("Invalid {} {}: {}", what, path.display(), e))
+    })
+}
+
+/// Run the verify command
+pub fn run_verify(
+    path: Option<String>,
+    vk: Option<String>,
+    proof: Option<String>,
+    public_witness: Option<String>,
+) -> error::Result<()> {
+    ui::panel_header(
+        emoji::SEARCH,
+        "VERIFY PROOF",
+        Some("Native BN254 Groth16 verification (no sunspot required)"),
+    );
+
+    let base_path = match path {
+        Some(p) => std::env::current_dir()?.join(p),
+        None => std::env::current_dir()?,
+    };
+
+    let mut circuit = None;
+    let vk_path = resolve_artifact(&base_path, vk, &mut circuit, "vk")?;
+    let proof_path = resolve_artifact(&base_path, proof, &mut circuit, "proof")?;
+    let pw_path = resolve_artifact(&base_path, public_witness, &mut circuit, "pw")?;
+
+    let spinner = ui::spinner("Parsing verifying key, proof and public witness...");
+    let parsed =
+        read_artifact(&vk_path, "verifying key", gnark::parse_verifying_key).and_then(|vk| {
+            let proof = read_artifact(&proof_path, "proof", gnark::parse_proof)?;
+            let witness = read_artifact(&pw_path, "public witness", gnark::parse_witness)?;
+            Ok((vk, proof, witness))
+        });
+    let (vk, proof, witness) = match parsed {
+        Ok(parsed) => {
+            ui::spinner_success(&spinner, "Parsed artifacts");
+            parsed
+        }
+        Err(e) => {
+            ui::spinner_error(&spinner, "Failed to parse artifacts");
+            return Err(e);
+        }
+    };
+
+    if witness.nb_secret != 0 {
+        ui::warn(&format!(
+            "{} contains {} secret values; only the public part is used",
+            pw_path.display(),
+            witness.nb_secret
+        ));
+    }
+    let public_inputs = &witness.values[..witness.nb_public as usize];
+
+    ui::section(emoji::FILE, "Artifacts");
+    let items = vec![
+        ("Verifying key", vk_path.to_str().unwrap_or("")),
+        ("Proof", proof_path.to_str().unwrap_or("")),
+        ("Public witness", pw_path.to_str().unwrap_or("")),
+    ];
+    ui::print_tree(&items);
+    ui::blank();
+
+    let spinner = ui::spinner("Running pairing check...");
+    let start = Instant::now();
+    let outcome = verify_proof(&vk, &proof, public_inputs);
+    let duration_ms = start.elapsed().as_millis();
+
+    let public_decimal: Vec<String> = public_inputs.iter().map(gnark::fr_to_decimal).collect();
+    let (valid, reason) = match &outcome {
+        Ok(_) => {
+            ui::spinner_success_with_duration(&spinner, "Pairing check passed", duration_ms);
+            (true, None)
+        }
+        Err(e) => {
+            ui::spinner_error(&spinner, "Verification failed");
+            (false, Some(e.message().to_string()))
+        }
+    };
+
+    ui::section(emoji::PIN, "Public Inputs");
+    if public_inputs.is_empty() {
+        ui::info("The circuit has no public inputs");
+    } else {
+        let mut table = ui::create_table(&["#", "Value"]);
+        for (i, value) in public_decimal.iter().enumerate() {
+            table.add_row(vec![i.to_string(), value.clone()]);
+        }
+        ui_println!("{table}");
+    }
+    if !proof.commitments.is_empty() {
+        ui::info(&format!(
+            "{} commitment(s) hashed into additional public inputs",
+            proof.commitments.len()
+        ));
+    }
+    ui::blank();
+
+    ui::emit_json(&json!({
+        "command": "verify",
+        "status": if valid { "valid" } else { "invalid" },
+        "reason": reason,
+        "vk": vk_path.display().to_string(),
+        "proof": proof_path.display().to_string(),
+        "public_witness": pw_path.display().to_string(),
+        "encoding": {
+            "vk": vk.encoding.to_string(),
+            "proof": proof.encoding.to_string(),
+        },
+        "public_inputs": public_decimal,
+        "commitments": proof.commitments.len(),
+        "duration_ms": duration_ms,
+    }));
+
+    match outcome {
+        Ok(_) => {
+            ui::panel_success(
+                "PROOF VALID",
+                &format!(
+                    "Groth16 proof verified against {} public input(s)",
+                    style(public_inputs.len()).bold()
+                ),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            ui::panel_error(
+                "PROOF INVALID",
+                e.message(),
+                None,
+                Some(&[
+                    "Make sure the proof, public witness and verifying key come from the same setup",
+                    "Re-run 'zklense run --from prove' to regenerate the proof",
+                ]),
+            );
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Fq, G2Projective};
+    use ark_ec::{AffineRepr, PrimeGroup};
+    use ark_ff::{BigInteger, Field};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn fq_bytes(value: &Fq) -> Vec<u8> {
+        value.into_bigint().to_bytes_be()
+    }
+
+    fn g1_compressed(point: &G1Affine) -> Vec<u8> {
+        let mut bytes = fq_bytes(&point.x);
+        let largest = point.y.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO;
+        bytes[0] |= if largest { 0b11 << 6 } else { 0b10 << 6 };
+        bytes
+    }
+
+    fn g2_raw(point: &G2Affine) -> Vec<u8> {
+        [point.x.c1, point.x.c0, point.y.c1, point.y.c0]
+            .iter()
+            .flat_map(fq_bytes)
+            .collect()
+    }
+
+    fn g1_vec(points: &[G1Affine], encode: fn(&G1Affine) -> Vec<u8>) -> Vec<u8> {
+        let mut bytes = (points.len() as u32).to_be_bytes().to_vec();
+        for p in points {
+            bytes.extend(encode(p));
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_expand_message_xmd_matches_rfc_9380() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            hex(&expand_message_xmd(b"", dst, 0x20)),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            hex(&expand_message_xmd(b"abc", dst, 0x20)),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_with_commitment() {
+        let g1 = G1Projective::generator();
+        let g2 = G2Projective::generator();
+        let s = |n: u64| Fr::from(n);
+
+        // Toxic waste and wire values for a circuit with two public inputs
+        // and one commitment over the first of them
+        let (alpha, beta, gamma, delta) = (s(11), s(13), s(17), s(19));
+        let k_scalars = [s(3), s(5), s(7), s(23)];
+        let public = vec![s(42), s(1000)];
+        let commit_scalar = s(29);
+        let sigma = s(31);
+
+        let commitment = (g1 * commit_scalar).into_affine();
+        let key_g = (g2 * s(37)).into_affine();
+        let mut prehash = gnark::g1_raw_bytes(&commitment);
+        prehash.extend(gnark::fr_bytes(&public[0]));
+        let commitment_value = hash_to_field(&prehash, COMMITMENT_DST);
+
+        let k_sum = k_scalars[0]
+            + k_scalars[1] * public[0]
+            + k_scalars[2] * public[1]
+            + k_scalars[3] * commitment_value
+            + commit_scalar;
+        let (a, b) = (s(41), s(43));
+        let c = (a * b - alpha * beta - k_sum * gamma) * delta.inverse().unwrap();
+
+        let mut vk_bytes = Vec::new();
+        for p in [g1 * alpha, g1 * beta] {
+            vk_bytes.extend(g1_compressed(&p.into_affine()));
+        }
+        vk_bytes.extend(g2_raw(&(g2 * beta).into_affine()));
+        vk_bytes.extend(g2_raw(&(g2 * gamma).into_affine()));
+        vk_bytes.extend(g1_compressed(&(g1 * delta).into_affine()));
+        vk_bytes.extend(g2_raw(&(g2 * delta).into_affine()));
+        let k: Vec<G1Affine> = k_scalars.iter().map(|x| (g1 * x).into_affine()).collect();
+        vk_bytes.extend(g1_vec(&k, g1_compressed));
+        vk_bytes.extend(1u32.to_be_bytes());
+        vk_bytes.extend(1u32.to_be_bytes());
+        vk_bytes.extend(1u64.to_be_bytes());
+        vk_bytes.extend(1u32.to_be_bytes());
+        vk_bytes.extend(g2_raw(&key_g));
+        vk_bytes.extend(g2_raw(&(-(key_g * sigma)).into_affine()));
+
+        let mut proof_bytes = gnark::g1_raw_bytes(&(g1 * a).into_affine());
+        proof_bytes.extend(g2_raw(&(g2 * b).into_affine()));
+        proof_bytes.extend(gnark::g1_raw_bytes(&(g1 * c).into_affine()));
+        proof_bytes.extend(g1_vec(&[commitment], gnark::g1_raw_bytes));
+        proof_bytes.extend(gnark::g1_raw_bytes(
+            &(commitment * sigma.inverse().unwrap()).into_affine(),
+        ));
+
+        let mut pw_bytes = Vec::new();
+        for n in [2u32, 0, 2] {
+            pw_bytes.extend(n.to_be_bytes());
+        }
+        for value in &public {
+            pw_bytes.extend(gnark::fr_bytes(value));
+        }
+
+        let vk = gnark::parse_verifying_key(&vk_bytes).unwrap();
+        let proof = gnark::parse_proof(&proof_bytes).unwrap();
+        let witness = gnark::parse_witness(&pw_bytes).unwrap();
+        assert_eq!(vk.encoding, gnark::PointEncoding::Compressed);
+        assert_eq!(proof.encoding, gnark::PointEncoding::Raw);
+        assert_eq!(vk.nb_public_inputs(), 2);
+        assert!(vk.alpha_g1.is_on_curve() && !vk.alpha_g1.is_zero());
+
+        let values = verify_proof(&vk, &proof, &witness.values).unwrap();
+        assert_eq!(values, vec![commitment_value]);
+
+        // A different public input breaks both the commitment hash and the pairing
+        let tampered = vec![s(43), s(1000)];
+        assert_eq!(
+            verify_proof(&vk, &proof, &tampered)
+                .unwrap_err()
+                .exit_code(),
+            11
+        );
+        assert_eq!(
+            verify_proof(&vk, &proof, &public[..1])
+                .unwrap_err()
+                .exit_code(),
+            2
+        );
+    }
+}
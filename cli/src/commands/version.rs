@@ -1,3 +1,12 @@
+use crate::ui;
+
 pub fn run_version() {
+    if ui::is_json_output() {
+        ui::emit_json(&serde_json::json!({
+            "command": "version",
+            "version": env!("CARGO_PKG_VERSION"),
+        }));
+        return;
+    }
     println!("zklense {}", env!("CARGO_PKG_VERSION"));
 }
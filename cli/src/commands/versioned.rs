@@ -0,0 +1,272 @@
+//! Versioned (v0) verify transactions and address lookup tables
+//!
+//! `zklense simulate --v0` simulates the verify transaction as a v0 message,
+//! optionally resolving accounts through address lookup tables, and compares
+//! its size with the legacy encoding. Tables are read from the cluster with
+//! `--lookup-table`, or created from the verify instruction's accounts with
+//! `--create-lookup-table` on a local validator or in-process.
+
+use comfy_table::Cell;
+use solana_address_lookup_table_interface::{
+    instruction::{create_lookup_table, derive_lookup_table_address, extend_lookup_table},
+    state::{AddressLookupTable, LookupTableMeta},
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{AddressLookupTableAccount, VersionedMessage, v0},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::borrow::Cow;
+use std::time::Duration;
+
+use super::fee_payer::FeePayer;
+use super::report::{EncodedSize, TransactionEncodings};
+use crate::error::{self, ZkLenseError};
+use crate::ui::{self, emoji};
+use crate::ui_println;
+
+/// Instructions of a compiled legacy transaction
+fn decompile(transaction: &Transaction) -> Vec<Instruction> {
+    let message = &transaction.message;
+    message
+        .instructions
+        .iter()
+        .map(|instruction| Instruction {
+            program_id: message.account_keys[instruction.program_id_index as usize],
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|&index| {
+                    let index = index as usize;
+                    AccountMeta {
+                        pubkey: message.account_keys[index],
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_maybe_writable(index, None),
+                    }
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+/// Re-encode `transaction` as an unsigned v0 transaction resolving accounts
+/// through `lookup_tables`
+pub fn to_v0(
+    transaction: &Transaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> error::Result<VersionedTransaction> {
+    let message = &transaction.message;
+    let compiled = v0::Message::try_compile(
+        &message.account_keys[0],
+        &decompile(transaction),
+        lookup_tables,
+        message.recent_blockhash,
+    )
+    .map_err(|e| ZkLenseError::InvalidInput(format!("Failed to compile v0 message: {}", e)))?;
+    Ok(VersionedTransaction {
+        signatures: vec![Signature::default(); compiled.header.num_required_signatures as usize],
+        message: VersionedMessage::V0(compiled),
+    })
+}
+
+/// Accounts of `transaction` a lookup table can hold: neither signers nor
+/// invoked programs
+pub fn lookup_candidates(transaction: &Transaction) -> Vec<Pubkey> {
+    let message = &transaction.message;
+    (0..message.account_keys.len())
+        .filter(|&index| !message.is_signer(index) && !message.is_key_called_as_program(index))
+        .map(|index| message.account_keys[index])
+        .collect()
+}
+
+/// Account data of an active lookup table holding `addresses`
+pub fn lookup_table_data(authority: &Pubkey, addresses: &[Pubkey]) -> error::Result<Vec<u8>> {
+    let table = AddressLookupTable {
+        meta: LookupTableMeta {
+            // Every address is usable right away
+            last_extended_slot_start_index: addresses.len() as u8,
+            ..LookupTableMeta::new(*authority)
+        },
+        addresses: Cow::Borrowed(addresses),
+    };
+    table
+        .serialize_for_tests()
+        .map_err(|e| ZkLenseError::Other(format!("Failed to build lookup table: {}", e)))
+}
+
+/// Address a lookup table created by `authority` in `slot` lives at
+pub fn lookup_table_address(authority: &Pubkey, slot: u64) -> Pubkey {
+    derive_lookup_table_address(authority, slot).0
+}
+
+/// Read the lookup table at `address`
+pub async fn fetch_lookup_table(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> error::Result<AddressLookupTableAccount> {
+    let data = client.get_account_data(address).await.map_err(|e| {
+        ZkLenseError::Rpc(format!("Failed to fetch lookup table {}: {}", address, e))
+    })?;
+    let table = AddressLookupTable::deserialize(&data).map_err(|_| {
+        ZkLenseError::InvalidInput(format!(
+            "Account {} is not an address lookup table",
+            address
+        ))
+    })?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Create a lookup table holding `addresses` and wait until it can be used
+pub async fn create_lookup_table_on_chain(
+    client: &RpcClient,
+    fee_payer: &FeePayer,
+    addresses: Vec<Pubkey>,
+) -> error::Result<AddressLookupTableAccount> {
+    let rpc_error = |e: solana_client::client_error::ClientError| {
+        ZkLenseError::Rpc(format!("Failed to create lookup table: {}", e))
+    };
+    let payer = fee_payer.pubkey;
+    let recent_slot = client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .map_err(rpc_error)?;
+    let (create, address) = create_lookup_table(payer, payer, recent_slot);
+    let extend = extend_lookup_table(address, payer, Some(payer), addresses.clone());
+    let mut transaction = Transaction::new_with_payer(&[create, extend], Some(&payer));
+    let blockhash: Hash = client.get_latest_blockhash().await.map_err(rpc_error)?;
+    fee_payer.sign(&mut transaction, blockhash);
+    client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .map_err(rpc_error)?;
+
+    // Addresses become usable in the slot after the one they were added in
+    let extended_slot = client.get_slot().await.map_err(rpc_error)?;
+    while client.get_slot().await.map_err(rpc_error)? <= extended_slot {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    Ok(AddressLookupTableAccount {
+        key: address,
+        addresses,
+    })
+}
+
+fn encoded_size(transaction: &VersionedTransaction) -> EncodedSize {
+    let looked_up_accounts = transaction
+        .message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+        .sum();
+    EncodedSize {
+        transaction_size: bincode::serialized_size(transaction).unwrap_or(0) as usize,
+        message_size: transaction.message.serialize().len(),
+        static_account_keys: transaction.message.static_account_keys().len(),
+        looked_up_accounts,
+    }
+}
+
+/// Sizes of `transaction` in the legacy and v0 encodings
+pub fn compare(
+    transaction: &Transaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> error::Result<TransactionEncodings> {
+    let legacy = encoded_size(&VersionedTransaction::from(transaction.clone()));
+    let v0 = encoded_size(&to_v0(transaction, lookup_tables)?);
+    Ok(TransactionEncodings {
+        lookup_tables: lookup_tables.iter().map(|t| t.key.to_string()).collect(),
+        size_savings: legacy.transaction_size as i64 - v0.transaction_size as i64,
+        legacy,
+        v0,
+    })
+}
+
+/// Print the legacy and v0 sizes side by side
+pub fn print_comparison(encodings: &TransactionEncodings) {
+    ui::section(emoji::FILE, "Transaction Encodings");
+    let mut table = ui::create_table(&["", "Legacy", "v0", "Change"]);
+    let rows = [
+        (
+            "Transaction Size",
+            encodings.legacy.transaction_size,
+            encodings.v0.transaction_size,
+        ),
+        (
+            "Message Size",
+            encodings.legacy.message_size,
+            encodings.v0.message_size,
+        ),
+        (
+            "Static Account Keys",
+            encodings.legacy.static_account_keys,
+            encodings.v0.static_account_keys,
+        ),
+        (
+            "Looked-up Accounts",
+            encodings.legacy.looked_up_accounts,
+            encodings.v0.looked_up_accounts,
+        ),
+    ];
+    for (label, legacy, v0) in rows {
+        let delta = v0 as f64 - legacy as f64;
+        table.add_row(vec![
+            Cell::new(label),
+            Cell::new(legacy),
+            Cell::new(v0),
+            ui::delta_cell(&format!("{:+}", delta as i64), delta),
+        ]);
+    }
+    ui_println!("{table}");
+    if !encodings.lookup_tables.is_empty() {
+        ui::info(&format!(
+            "Lookup tables: {}",
+            encodings.lookup_tables.join(", ")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_table_shrinks_v0_transaction() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let instruction = Instruction {
+            program_id,
+            accounts: accounts
+                .iter()
+                .map(|a| AccountMeta::new(*a, false))
+                .collect(),
+            data: vec![1, 2, 3],
+        };
+        let transaction = Transaction::new_with_payer(&[instruction], Some(&payer));
+        assert_eq!(lookup_candidates(&transaction), accounts);
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts.clone(),
+        };
+        let encodings = compare(&transaction, &[table]).unwrap();
+        assert_eq!(encodings.legacy.static_account_keys, 6);
+        assert_eq!(encodings.v0.static_account_keys, 2);
+        assert_eq!(encodings.v0.looked_up_accounts, 4);
+        assert!(encodings.size_savings > 0);
+
+        let data = lookup_table_data(&payer, &accounts).unwrap();
+        let table = AddressLookupTable::deserialize(&data).unwrap();
+        assert_eq!(table.addresses.as_ref(), accounts.as_slice());
+    }
+}
@@ -6,26 +6,20 @@ use std::path::PathBuf;
 use std::thread;
 
 use crate::commands::init::{DEFAULT_WEB_APP_URL, read_config};
+use crate::error::{self, ZkLenseError};
+use crate::ui;
+use crate::ui_println;
 
-pub fn run_view(path: Option<String>) {
+pub fn run_view(path: Option<String>) -> error::Result<()> {
     // Determine the project directory
     let project_dir = match path {
         Some(p) => PathBuf::from(p),
-        None => match std::env::current_dir() {
-            Ok(dir) => dir,
-            Err(e) => {
-                eprintln!(
-                    "{} Failed to get current directory: {}",
-                    style("✖").red().bold(),
-                    e
-                );
-                eprintln!(
-                    "  {} Try specifying a path: zklense view /path/to/project",
-                    style("→").dim()
-                );
-                std::process::exit(1);
-            }
-        },
+        None => std::env::current_dir().map_err(|e| {
+            ZkLenseError::Other(format!(
+                "Failed to get current directory: {}\nTry specifying a path: zklense view /path/to/project",
+                e
+            ))
+        })?,
     };
 
     let zklense_dir = project_dir.join(".zklense");
@@ -33,31 +27,22 @@ pub fn run_view(path: Option<String>) {
 
     // Check if report.json exists
     if !report_path.exists() {
-        eprintln!(
-            "{} No report found at {}",
-            style("✖").red().bold(),
-            style(report_path.display()).yellow()
-        );
-        eprintln!(
-            "  {} Run profiling first to generate a report.",
-            style("→").dim()
-        );
-        std::process::exit(1);
+        return Err(ZkLenseError::MissingArtifact(format!(
+            "No report found at {}\nRun 'zklense simulate' first to generate a report.",
+            report_path.display()
+        )));
     }
 
     // Read the report file
-    let report_content = match fs::read_to_string(&report_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("{} Failed to read report: {}", style("✖").red().bold(), e);
-            std::process::exit(1);
-        }
-    };
+    let report_content = fs::read_to_string(&report_path)
+        .map_err(|e| ZkLenseError::Other(format!("Failed to read report: {}", e)))?;
 
     // Validate it's valid JSON
     if serde_json::from_str::<serde_json::Value>(&report_content).is_err() {
-        eprintln!("{} Report file is not valid JSON", style("✖").red().bold());
-        std::process::exit(1);
+        return Err(ZkLenseError::Other(format!(
+            "Report file is not valid JSON: {}",
+            report_path.display()
+        )));
     }
 
     // Read web app URL from config, fallback to default
@@ -70,20 +55,11 @@ pub fn run_view(path: Option<String>) {
     };
 
     // Find an available port
-    let listener = match TcpListener::bind("127.0.0.1:0") {
-        Ok(l) => l,
-        Err(e) => {
-            eprintln!(
-                "{} Failed to bind to a port: {}",
-                style("✖").red().bold(),
-                e
-            );
-            std::process::exit(1);
-        }
-    };
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| ZkLenseError::Other(format!("Failed to bind to a port: {}", e)))?;
     let port = listener.local_addr().unwrap().port();
 
-    println!(
+    ui_println!(
         "{} Starting local server on port {}",
         style("◉").cyan().bold(),
         style(port).cyan()
@@ -92,12 +68,20 @@ pub fn run_view(path: Option<String>) {
     // Build the web app URL with the port parameter
     let viewer_url = format!("{}?port={}", web_app_url, port);
 
-    println!(
+    ui_println!(
         "{} Opening viewer at {}",
         style("◉").cyan().bold(),
         style(&viewer_url).underlined()
     );
 
+    ui::emit_json(&serde_json::json!({
+        "command": "view",
+        "status": "serving",
+        "url": viewer_url,
+        "port": port,
+        "report_path": report_path.display().to_string(),
+    }));
+
     // Open the browser
     if let Err(e) = webbrowser::open(&viewer_url) {
         eprintln!(
@@ -105,14 +89,14 @@ pub fn run_view(path: Option<String>) {
             style("⚠").yellow().bold(),
             e
         );
-        println!(
+        ui_println!(
             "  {} Open this URL manually: {}",
             style("→").dim(),
             viewer_url
         );
     }
 
-    println!(
+    ui_println!(
         "{} Serving report... Press Ctrl+C to stop.",
         style("◉").green().bold()
     );
@@ -162,4 +146,6 @@ pub fn run_view(path: Option<String>) {
             }
         }
     }
+
+    Ok(())
 }
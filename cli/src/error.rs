@@ -0,0 +1,202 @@
+//! Error taxonomy and process exit codes
+//!
+//! Every command returns a [`ZkLenseError`], which `main` maps to a stable
+//! exit code so scripts can tell failures apart:
+//!
+//! | Code | Variant            | Meaning                                        |
+//! |------|--------------------|------------------------------------------------|
+//! | 0    | -                  | Success                                        |
+//! | 1    | `Other`            | Unclassified error (I/O, unexpected failures)  |
+//! | 2    | `InvalidInput`     | Invalid or missing command-line input          |
+//! | 3    | `MissingTool`      | Required tool not installed (nargo, sunspot)   |
+//! | 4    | `MissingArtifact`  | Required file not found (Nargo.toml, proof...) |
+//! | 5    | `Config`           | zklense or project configuration error         |
+//! | 6    | `Rpc`              | Solana RPC request failed                      |
+//! | 7    | `CommandFailed`    | An external command exited with an error       |
+//! | 8    | `SimulationFailed` | The simulated transaction failed               |
+//! | 9    | `BudgetExceeded`   | A configured budget was exceeded               |
+//! | 10   | `UserAbort`        | The user declined a required prompt            |
+//! | 11   | `VerificationFailed` | The proof did not verify                     |
+//!
+//! Helpers that return `io::Result` or `anyhow::Result` can still carry a
+//! typed error: convert it with `.into()` and the variant is recovered when
+//! the error reaches a command boundary.
+
+use std::fmt;
+use std::io;
+
+/// Result type returned by command entry points
+pub type Result<T> = std::result::Result<T, ZkLenseError>;
+
+/// Crate-wide error, one variant per exit code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZkLenseError {
+    Other(String),
+    InvalidInput(String),
+    MissingTool(String),
+    MissingArtifact(String),
+    Config(String),
+    Rpc(String),
+    CommandFailed(String),
+    SimulationFailed(String),
+    BudgetExceeded(String),
+    UserAbort(String),
+    VerificationFailed(String),
+}
+
+impl ZkLenseError {
+    /// Stable process exit code for this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ZkLenseError::Other(_) => 1,
+            ZkLenseError::InvalidInput(_) => 2,
+            ZkLenseError::MissingTool(_) => 3,
+            ZkLenseError::MissingArtifact(_) => 4,
+            ZkLenseError::Config(_) => 5,
+            ZkLenseError::Rpc(_) => 6,
+            ZkLenseError::CommandFailed(_) => 7,
+            ZkLenseError::SimulationFailed(_) => 8,
+            ZkLenseError::BudgetExceeded(_) => 9,
+            ZkLenseError::UserAbort(_) => 10,
+            ZkLenseError::VerificationFailed(_) => 11,
+        }
+    }
+
+    /// Stable snake_case name of the variant, used in JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ZkLenseError::Other(_) => "other",
+            ZkLenseError::InvalidInput(_) => "invalid_input",
+            ZkLenseError::MissingTool(_) => "missing_tool",
+            ZkLenseError::MissingArtifact(_) => "missing_artifact",
+            ZkLenseError::Config(_) => "config",
+            ZkLenseError::Rpc(_) => "rpc",
+            ZkLenseError::CommandFailed(_) => "command_failed",
+            ZkLenseError::SimulationFailed(_) => "simulation_failed",
+            ZkLenseError::BudgetExceeded(_) => "budget_exceeded",
+            ZkLenseError::UserAbort(_) => "user_abort",
+            ZkLenseError::VerificationFailed(_) => "verification_failed",
+        }
+    }
+
+    /// The error message
+    pub fn message(&self) -> &str {
+        match self {
+            ZkLenseError::Other(m)
+            | ZkLenseError::InvalidInput(m)
+            | ZkLenseError::MissingTool(m)
+            | ZkLenseError::MissingArtifact(m)
+            | ZkLenseError::Config(m)
+            | ZkLenseError::Rpc(m)
+            | ZkLenseError::CommandFailed(m)
+            | ZkLenseError::SimulationFailed(m)
+            | ZkLenseError::BudgetExceeded(m)
+            | ZkLenseError::UserAbort(m)
+            | ZkLenseError::VerificationFailed(m) => m,
+        }
+    }
+
+    /// Same variant with a different message
+    fn with_message(&self, message: String) -> Self {
+        match self {
+            ZkLenseError::Other(_) => ZkLenseError::Other(message),
+            ZkLenseError::InvalidInput(_) => ZkLenseError::InvalidInput(message),
+            ZkLenseError::MissingTool(_) => ZkLenseError::MissingTool(message),
+            ZkLenseError::MissingArtifact(_) => ZkLenseError::MissingArtifact(message),
+            ZkLenseError::Config(_) => ZkLenseError::Config(message),
+            ZkLenseError::Rpc(_) => ZkLenseError::Rpc(message),
+            ZkLenseError::CommandFailed(_) => ZkLenseError::CommandFailed(message),
+            ZkLenseError::SimulationFailed(_) => ZkLenseError::SimulationFailed(message),
+            ZkLenseError::BudgetExceeded(_) => ZkLenseError::BudgetExceeded(message),
+            ZkLenseError::UserAbort(_) => ZkLenseError::UserAbort(message),
+            ZkLenseError::VerificationFailed(_) => ZkLenseError::VerificationFailed(message),
+        }
+    }
+}
+
+impl fmt::Display for ZkLenseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ZkLenseError {}
+
+/// Extract a typed error carried as the payload of an `io::Error`
+fn typed_payload(e: &io::Error) -> Option<&ZkLenseError> {
+    e.get_ref()
+        .and_then(|inner| inner.downcast_ref::<ZkLenseError>())
+}
+
+impl From<ZkLenseError> for io::Error {
+    fn from(e: ZkLenseError) -> Self {
+        let kind = match e {
+            ZkLenseError::InvalidInput(_) => io::ErrorKind::InvalidInput,
+            ZkLenseError::MissingTool(_) | ZkLenseError::MissingArtifact(_) => {
+                io::ErrorKind::NotFound
+            }
+            ZkLenseError::UserAbort(_) => io::ErrorKind::Interrupted,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+impl From<io::Error> for ZkLenseError {
+    fn from(e: io::Error) -> Self {
+        if let Some(typed) = typed_payload(&e) {
+            return typed.clone();
+        }
+        match e.kind() {
+            io::ErrorKind::InvalidInput => ZkLenseError::InvalidInput(e.to_string()),
+            _ => ZkLenseError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ZkLenseError {
+    fn from(e: anyhow::Error) -> Self {
+        // Keep the full context chain as the message, but take the variant
+        // from the first typed error found in the chain
+        let message = format!("{:#}", e);
+        for cause in e.chain() {
+            if let Some(typed) = cause.downcast_ref::<ZkLenseError>() {
+                return typed.with_message(message);
+            }
+            if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+                return match typed_payload(io_err) {
+                    Some(typed) => typed.with_message(message),
+                    None if io_err.kind() == io::ErrorKind::InvalidInput => {
+                        ZkLenseError::InvalidInput(message)
+                    }
+                    None => ZkLenseError::Other(message),
+                };
+            }
+        }
+        ZkLenseError::Other(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_typed_errors_survive_io_and_anyhow() {
+        let io_err: io::Error = ZkLenseError::MissingArtifact("no proof".to_string()).into();
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(ZkLenseError::from(io_err).exit_code(), 4);
+
+        let wrapped: anyhow::Result<()> = Err(io::Error::from(ZkLenseError::Config(
+            "bad toml".to_string(),
+        )))
+        .context("Failed to read config");
+        let err = ZkLenseError::from(wrapped.unwrap_err());
+        assert_eq!(err.exit_code(), 5);
+        assert!(err.message().starts_with("Failed to read config"));
+
+        let plain = ZkLenseError::from(anyhow::anyhow!("boom"));
+        assert_eq!(plain, ZkLenseError::Other("boom".to_string()));
+    }
+}
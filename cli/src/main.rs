@@ -1,13 +1,39 @@
 use clap::{Parser, Subcommand};
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+use error::ZkLenseError;
 
 mod commands;
+mod error;
 mod ui;
 
 #[derive(Parser)]
-#[command(name = "zklense", version, about = "ZK Profiling Tool")]
+#[command(
+    name = "zklense",
+    version,
+    about = "ZK Profiling Tool",
+    after_help = "Exit codes: 0 success, 1 error, 2 invalid input, 3 missing tool, \
+4 missing artifact, 5 config error, 6 RPC error, 7 command failed, \
+8 simulation failed, 9 budget exceeded, 10 aborted by user, 11 proof invalid"
+)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Answer yes to every confirmation prompt (implies --no-input)
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+
+    /// Never prompt; use defaults or fail on missing arguments.
+    /// Enabled automatically when stdin is not a terminal.
+    #[arg(long, global = true)]
+    no_input: bool,
+
+    /// Print a single JSON document on stdout instead of styled output
+    /// (implies --no-input)
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[derive(Subcommand)]
@@ -27,10 +53,172 @@ enum Commands {
         /// Program ID to simulate against
         #[arg(short, long)]
         program_id: Option<String>,
+
+        /// Fail if more compute units are consumed
+        #[arg(long)]
+        max_cu: Option<u64>,
+
+        /// Fail if more than this percent of the compute unit limit is used
+        #[arg(long)]
+        max_budget_percent: Option<f64>,
+
+        /// Fail if the transaction message is larger (bytes)
+        #[arg(long)]
+        max_message_size: Option<usize>,
+
+        /// Fail if the total fee is higher (lamports)
+        #[arg(long)]
+        max_fee: Option<u64>,
+
+        /// Simulate on a local solana-test-validator with the program preloaded
+        #[arg(long, conflicts_with = "offline")]
+        localnet: bool,
+
+        /// Leave a validator started by --localnet running afterwards
+        #[arg(long)]
+        keep_validator: bool,
+
+        /// Simulate in-process with an embedded Solana runtime, without any RPC endpoint
+        #[arg(long)]
+        offline: bool,
+
+        /// Re-simulate with the consumed compute units plus a safety margin and report the tight limit
+        #[arg(long)]
+        tune_cu: bool,
+
+        /// Safety margin for --tune-cu, in percent of the consumed compute units (default: 10)
+        #[arg(long, requires = "tune_cu")]
+        cu_margin: Option<f64>,
+
+        /// Fee payer keypair file (default: config keypair_path, then ~/.config/solana/id.json)
+        #[arg(short, long)]
+        keypair: Option<String>,
+
+        /// Have the node verify the transaction signature (needs a keypair)
+        #[arg(long, conflicts_with = "replace_blockhash")]
+        sig_verify: bool,
+
+        /// Have the node replace the blockhash with its latest one
+        #[arg(long)]
+        replace_blockhash: bool,
+
+        /// Write the proof to a buffer account over several transactions, then verify from it
+        #[arg(long)]
+        buffered: bool,
+
+        /// Simulate a v0 transaction and compare its size with the legacy encoding
+        #[arg(long)]
+        v0: bool,
+
+        /// Address lookup table for the v0 transaction (repeatable)
+        #[arg(long = "lookup-table", value_name = "ADDRESS", requires = "v0")]
+        lookup_tables: Vec<String>,
+
+        /// Create a lookup table holding the verify instruction's accounts (--localnet or --offline)
+        #[arg(long, requires = "v0", conflicts_with = "lookup_tables")]
+        create_lookup_table: bool,
+
+        /// Heap frame to request in bytes, a multiple of 1024 up to 262144 (default: 32768)
+        #[arg(long, value_name = "BYTES")]
+        heap_frame: Option<u32>,
+
+        /// Binary-search the smallest heap frame verification succeeds with
+        #[arg(long)]
+        measure_heap: bool,
+
+        /// Simulate this many times and report compute unit statistics
+        #[arg(long, value_name = "N", conflicts_with = "buffered")]
+        runs: Option<usize>,
+
+        /// Proof file to simulate instead of the one found in the project (repeatable, cycled through by --runs)
+        #[arg(long = "proof", value_name = "FILE", requires = "witnesses")]
+        proofs: Vec<String>,
+
+        /// Public witness file paired with each --proof (repeatable)
+        #[arg(long = "witness", value_name = "FILE", requires = "proofs")]
+        witnesses: Vec<String>,
+
+        /// Simulate every <name>.proof and <name>.pw pair in a directory or matching a pattern such as 'inputs/*/*.proof'
+        #[arg(long, value_name = "DIR|PATTERN", conflicts_with_all = ["runs", "proofs", "buffered"])]
+        batch: Option<String>,
+    },
+    /// Send the verify transaction and report the confirmed result
+    #[command(name = "submit")]
+    Submit {
+        /// Program ID to submit to
+        #[arg(short, long)]
+        program_id: Option<String>,
+
+        /// Fee payer keypair file (default: config keypair_path, then ~/.config/solana/id.json)
+        #[arg(short, long)]
+        keypair: Option<String>,
+
+        /// Commitment level to wait for (processed, confirmed, finalized)
+        #[arg(long, default_value = "confirmed")]
+        commitment: String,
+
+        /// Times to resend after an unconfirmed attempt's blockhash expires (default: 3)
+        #[arg(long)]
+        max_retries: Option<u32>,
+
+        /// Compute unit limit to request (default: 1,400,000)
+        #[arg(long)]
+        cu_limit: Option<u32>,
+
+        /// Heap frame to request in bytes, a multiple of 1024 up to 262144 (default: 32768)
+        #[arg(long, value_name = "BYTES")]
+        heap_frame: Option<u32>,
+
+        /// Submit to a local solana-test-validator with the program preloaded
+        #[arg(long)]
+        localnet: bool,
+
+        /// Leave a validator started by --localnet running afterwards
+        #[arg(long)]
+        keep_validator: bool,
     },
     #[command(name = "run")]
     Run {
         path: Option<String>,
+
+        /// Re-run every step even if its inputs are unchanged
+        #[arg(long)]
+        force: bool,
+
+        /// First step to run (execute, compile, setup, prove, verify, deploy)
+        #[arg(long, conflicts_with = "only")]
+        from: Option<String>,
+
+        /// Last step to run
+        #[arg(long, conflicts_with = "only")]
+        to: Option<String>,
+
+        /// Run only these steps (comma-separated, e.g. prove,verify)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+    },
+    /// Verify a Groth16 proof natively, without sunspot
+    #[command(name = "verify")]
+    Verify {
+        path: Option<String>,
+
+        /// Verifying key (default: target/<circuit>.vk)
+        #[arg(long)]
+        vk: Option<String>,
+
+        /// Proof (default: target/<circuit>.proof)
+        #[arg(long)]
+        proof: Option<String>,
+
+        /// Public witness (default: target/<circuit>.pw)
+        #[arg(long)]
+        pw: Option<String>,
+    },
+    /// Decode and display proof, witness, key and constraint system files
+    #[command(name = "inspect")]
+    Inspect {
+        /// Artifact files or project directories (defaults to the current project)
+        paths: Vec<String>,
     },
     #[command(name = "generate")]
     Generate {
@@ -42,6 +230,30 @@ enum Commands {
         #[arg(short, long)]
         template: Option<String>,
     },
+    /// List past simulation reports with their key metrics
+    #[command(name = "history")]
+    History {
+        path: Option<String>,
+
+        /// Show only the N most recent reports
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Compare two simulation reports
+    #[command(name = "diff")]
+    Diff {
+        /// Older report: file path or history ID (default: second most recent)
+        old: Option<String>,
+
+        /// Newer report: file path or history ID (default: most recent)
+        new: Option<String>,
+    },
+    /// Work with simulation reports
+    #[command(name = "report")]
+    Report {
+        #[command(subcommand)]
+        action: ReportCommands,
+    },
     /// Manage zklense configuration
     #[command(name = "config")]
     Config {
@@ -50,6 +262,13 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Print the JSON Schema of .zklense/report.json
+    #[command(name = "schema")]
+    Schema,
+}
+
 #[derive(Subcommand)]
 enum ConfigCommands {
     /// Show all configuration values
@@ -58,10 +277,10 @@ enum ConfigCommands {
     /// Get the current Solana network
     #[command(name = "get-network")]
     GetNetwork { path: Option<String> },
-    /// Set the Solana network (devnet, testnet, or mainnet)
+    /// Set the Solana network (devnet, testnet, mainnet or localnet)
     #[command(name = "set-network")]
     SetNetwork {
-        /// Network to use: devnet, testnet, or mainnet
+        /// Network to use: devnet, testnet, mainnet or localnet
         network: String,
         path: Option<String>,
     },
@@ -81,112 +300,198 @@ enum ConfigCommands {
     /// Reset the RPC URL to the default for the current network
     #[command(name = "reset-rpc")]
     ResetRpc { path: Option<String> },
+    /// Set the fee payer keypair used to sign simulated transactions
+    #[command(name = "set-keypair")]
+    SetKeypair {
+        /// Solana CLI keypair file (e.g., ~/.config/solana/id.json)
+        keypair_path: String,
+        path: Option<String>,
+    },
 }
 
 /// Check if the project is initialized, prompting the user if not.
-/// Returns true if we should proceed, false otherwise.
-fn check_initialized(path: Option<&str>) -> bool {
-    match commands::ensure_initialized(path) {
-        Ok(true) => true,
-        Ok(false) => false, // User declined initialization
-        Err(e) => {
-            eprintln!("❌ Error: {}", e);
-            false
-        }
+/// Declining to initialize is reported as a user abort.
+fn check_initialized(path: Option<&str>) -> error::Result<()> {
+    if commands::ensure_initialized(path)? {
+        Ok(())
+    } else {
+        Err(ZkLenseError::UserAbort(
+            "zklense is not initialized in this project".to_string(),
+        ))
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
-
-    match cli.command {
+/// Dispatch a parsed command to its handler
+async fn run(command: Option<Commands>) -> error::Result<()> {
+    match command {
         Some(Commands::Version) => {
             commands::run_version();
+            Ok(())
         }
-        Some(Commands::Simulate { program_id }) => {
-            if !check_initialized(None) {
-                return;
-            }
-            if let Err(e) = commands::run_simulate(program_id).await {
-                eprintln!("Error: {}", e);
-            }
+        Some(Commands::Simulate {
+            program_id,
+            max_cu,
+            max_budget_percent,
+            max_message_size,
+            max_fee,
+            localnet,
+            keep_validator,
+            offline,
+            tune_cu,
+            cu_margin,
+            keypair,
+            sig_verify,
+            replace_blockhash,
+            buffered,
+            v0,
+            lookup_tables,
+            create_lookup_table,
+            heap_frame,
+            measure_heap,
+            runs,
+            proofs,
+            witnesses,
+            batch,
+        }) => {
+            check_initialized(None)?;
+            let options = commands::SimulateOptions {
+                program_id,
+                budgets: commands::Budgets {
+                    max_cu,
+                    max_budget_percent,
+                    max_message_size,
+                    max_total_fee: max_fee,
+                },
+                localnet,
+                keep_validator,
+                offline,
+                tune_cu,
+                cu_margin,
+                keypair,
+                sig_verify,
+                replace_blockhash,
+                buffered,
+                v0,
+                lookup_tables,
+                create_lookup_table,
+                heap_frame,
+                measure_heap,
+                runs,
+                proofs,
+                witnesses,
+                batch,
+            };
+            commands::run_simulate(options).await
         }
-        Some(Commands::Initialize { path }) => {
-            commands::run_init(path);
+        Some(Commands::Submit {
+            program_id,
+            keypair,
+            commitment,
+            max_retries,
+            cu_limit,
+            heap_frame,
+            localnet,
+            keep_validator,
+        }) => {
+            check_initialized(None)?;
+            commands::run_submit(commands::SubmitOptions {
+                program_id,
+                keypair,
+                commitment: Some(commitment),
+                max_retries,
+                cu_limit,
+                heap_frame,
+                localnet,
+                keep_validator,
+            })
+            .await
         }
+        Some(Commands::Initialize { path }) => commands::run_init(path),
         Some(Commands::View { path }) => {
-            if !check_initialized(path.as_deref()) {
-                return;
-            }
-            commands::run_view(path);
+            check_initialized(path.as_deref())?;
+            commands::run_view(path)
         }
-        Some(Commands::Run { path }) => {
-            if !check_initialized(path.as_deref()) {
-                return;
-            }
-            if let Err(e) = commands::run_pipeline(path) {
-                eprintln!("❌ Error: {}", e);
-            }
+        Some(Commands::Run {
+            path,
+            force,
+            from,
+            to,
+            only,
+        }) => {
+            check_initialized(path.as_deref())?;
+            let selection = commands::StepSelection { from, to, only };
+            commands::run_pipeline(path, force, selection)
         }
-        Some(Commands::Generate { name, template }) => {
-            if let Err(e) = commands::run_generate(name, template) {
-                eprintln!("❌ Error: {}", e);
-            }
+        Some(Commands::Verify {
+            path,
+            vk,
+            proof,
+            pw,
+        }) => commands::run_verify(path, vk, proof, pw),
+        Some(Commands::Inspect { paths }) => commands::run_inspect(paths),
+        Some(Commands::Generate { name, template }) => commands::run_generate(name, template),
+        Some(Commands::History { path, limit }) => {
+            check_initialized(path.as_deref())?;
+            commands::run_history(path, limit)
         }
+        Some(Commands::Diff { old, new }) => commands::run_diff(old, new),
+        Some(Commands::Report { action }) => match action {
+            ReportCommands::Schema => commands::run_report(commands::ReportAction::Schema),
+        },
         Some(Commands::Config { action }) => {
             let (config_action, path) = match action {
-                ConfigCommands::Show { path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
-                    (commands::ConfigAction::Show, path)
-                }
-                ConfigCommands::GetNetwork { path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
-                    (commands::ConfigAction::GetNetwork, path)
-                }
+                ConfigCommands::Show { path } => (commands::ConfigAction::Show, path),
+                ConfigCommands::GetNetwork { path } => (commands::ConfigAction::GetNetwork, path),
                 ConfigCommands::SetNetwork { network, path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
                     (commands::ConfigAction::SetNetwork(network), path)
                 }
                 ConfigCommands::ListNetworks { path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
                     (commands::ConfigAction::ListNetworks, path)
                 }
-                ConfigCommands::GetRpc { path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
-                    (commands::ConfigAction::GetRpc, path)
-                }
+                ConfigCommands::GetRpc { path } => (commands::ConfigAction::GetRpc, path),
                 ConfigCommands::SetRpc { rpc_url, path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
                     (commands::ConfigAction::SetRpc(rpc_url), path)
                 }
-                ConfigCommands::ResetRpc { path } => {
-                    if !check_initialized(path.as_deref()) {
-                        return;
-                    }
-                    (commands::ConfigAction::ResetRpc, path)
+                ConfigCommands::ResetRpc { path } => (commands::ConfigAction::ResetRpc, path),
+                ConfigCommands::SetKeypair { keypair_path, path } => {
+                    (commands::ConfigAction::SetKeypair(keypair_path), path)
                 }
             };
 
-            if let Err(e) = commands::run_config(config_action, path) {
-                eprintln!("❌ Error: {}", e);
-            }
+            check_initialized(path.as_deref())?;
+            commands::run_config(config_action, path)
         }
         None => {
             println!("zklense: ZK Profiling Tool");
             println!("Run `zklense --help` to see commands.");
+            Ok(())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let no_input = cli.no_input || cli.yes || cli.json || !std::io::stdin().is_terminal();
+    ui::set_interactivity(no_input, cli.yes);
+    ui::set_json_output(cli.json);
+
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if ui::is_json_output() && !ui::json_emitted() {
+                ui::emit_json(&serde_json::json!({
+                    "status": "error",
+                    "error": {
+                        "kind": e.kind(),
+                        "exit_code": e.exit_code(),
+                        "message": e.message(),
+                    },
+                }));
+            }
+            eprintln!("❌ Error: {}", e);
+            ExitCode::from(e.exit_code())
         }
     }
 }
@@ -2,11 +2,12 @@
 //!
 //! Provides reusable components for:
 //! - Spinners with messages
-//! - Interactive selections (replacing Y/N prompts)
+//! - Interactive selections (replacing Y/N prompts), with a non-interactive mode
 //! - Formatted tables
 //! - Styled panels (info, success, error, warning)
 //! - Multi-step progress tracking
 //! - Consistent emoji theme
+//! - Machine-readable JSON output mode that silences all of the above
 
 #![allow(dead_code)]
 
@@ -16,6 +17,8 @@ use comfy_table::{
 use console::{Style, style};
 use dialoguer::{Select, theme::ColorfulTheme};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 // ============================================================================
@@ -83,12 +86,63 @@ pub fn style_header() -> Style {
     Style::new().bold().cyan()
 }
 
+// ============================================================================
+// JSON OUTPUT MODE
+// ============================================================================
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+static JSON_EMITTED: AtomicBool = AtomicBool::new(false);
+
+/// Switch to JSON output: decorative output is suppressed and commands
+/// print a single JSON document on stdout instead
+pub fn set_json_output(enabled: bool) {
+    JSON_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether JSON output mode is enabled
+pub fn is_json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Print a command's JSON document to stdout (only in JSON output mode)
+pub fn emit_json(document: &serde_json::Value) {
+    if !is_json_output() {
+        return;
+    }
+    // Ignore write errors so a closed pipe (e.g. `| head`) doesn't panic
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(
+        stdout,
+        "{}",
+        serde_json::to_string_pretty(document).unwrap_or_default()
+    );
+    JSON_EMITTED.store(true, Ordering::Relaxed);
+}
+
+/// Whether a command has already printed its JSON document
+pub fn json_emitted() -> bool {
+    JSON_EMITTED.load(Ordering::Relaxed)
+}
+
+/// `println!` for decorative output, silenced in JSON output mode
+#[macro_export]
+macro_rules! ui_println {
+    ($($arg:tt)*) => {
+        if !$crate::ui::is_json_output() {
+            println!($($arg)*);
+        }
+    };
+}
+
 // ============================================================================
 // SPINNERS
 // ============================================================================
 
 /// Create a spinner with a message
 pub fn spinner(message: &str) -> ProgressBar {
+    if is_json_output() {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::default_spinner()
@@ -122,6 +176,19 @@ pub fn spinner_success_with_duration(pb: &ProgressBar, message: &str, duration_m
     ));
 }
 
+/// Print a line for a step that was skipped because it is up to date
+pub fn step_skipped(message: &str) {
+    if is_json_output() {
+        return;
+    }
+    println!(
+        "{} {} {}",
+        style(emoji::SUCCESS).green().bold(),
+        message,
+        style("(up to date)").dim()
+    );
+}
+
 /// Finish a spinner with an error message
 pub fn spinner_error(pb: &ProgressBar, message: &str) {
     pb.set_style(ProgressStyle::default_spinner().template("{msg}").unwrap());
@@ -146,8 +213,52 @@ pub fn spinner_warn(pb: &ProgressBar, message: &str) {
 // INTERACTIVE SELECTIONS
 // ============================================================================
 
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Configure prompting for the whole process. With `no_input`, confirmations
+/// resolve to `assume_yes` and selections to their default item.
+pub fn set_interactivity(no_input: bool, assume_yes: bool) {
+    NO_INPUT.store(no_input, Ordering::Relaxed);
+    ASSUME_YES.store(assume_yes, Ordering::Relaxed);
+}
+
+/// Whether prompts may be shown to the user
+pub fn is_interactive() -> bool {
+    !NO_INPUT.load(Ordering::Relaxed)
+}
+
+/// Error for a value that would normally be prompted for
+pub fn missing_input(what: &str, flag: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!(
+            "{} is required when running non-interactively. Pass it with {}",
+            what, flag
+        ),
+    )
+}
+
+/// Answer a confirmation without prompting
+fn auto_confirm(prompt: &str) -> bool {
+    let answer = ASSUME_YES.load(Ordering::Relaxed);
+    if answer {
+        info(&format!("{} {}", prompt, style("yes (--yes)").dim()));
+    } else {
+        info(&format!(
+            "{} {}",
+            prompt,
+            style("no (non-interactive, pass --yes to accept)").dim()
+        ));
+    }
+    answer
+}
+
 /// Yes/No selection (replaces Y/N prompts)
 pub fn confirm(prompt: &str) -> std::io::Result<bool> {
+    if !is_interactive() {
+        return Ok(auto_confirm(prompt));
+    }
     let items = vec!["Yes", "No"];
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
@@ -160,6 +271,9 @@ pub fn confirm(prompt: &str) -> std::io::Result<bool> {
 
 /// Yes/No selection with custom labels
 pub fn confirm_custom(prompt: &str, yes_label: &str, no_label: &str) -> std::io::Result<bool> {
+    if !is_interactive() {
+        return Ok(auto_confirm(prompt));
+    }
     let items = vec![yes_label, no_label];
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
@@ -172,6 +286,9 @@ pub fn confirm_custom(prompt: &str, yes_label: &str, no_label: &str) -> std::io:
 
 /// Generic selection from a list of options
 pub fn select<T: ToString>(prompt: &str, items: &[T], default: usize) -> std::io::Result<usize> {
+    if !is_interactive() {
+        return Ok(default);
+    }
     let string_items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
     Select::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
@@ -218,6 +335,9 @@ fn pad_line(content: &str) -> String {
 
 /// Print a success panel
 pub fn panel_success(title: &str, message: &str) {
+    if is_json_output() {
+        return;
+    }
     let header = format!("{} {}", emoji::CHECKMARK, title);
     println!();
     println!("{}", style(draw_top_border(&header)).green());
@@ -235,6 +355,9 @@ pub fn panel_error(
     details: Option<&str>,
     suggestions: Option<&[&str]>,
 ) {
+    if is_json_output() {
+        return;
+    }
     let header = format!("{} {}", emoji::CROSSMARK, title);
     println!();
     println!("{}", style(draw_top_border(&header)).red());
@@ -266,6 +389,9 @@ pub fn panel_error(
 
 /// Print an info panel
 pub fn panel_info(title: &str, message: &str) {
+    if is_json_output() {
+        return;
+    }
     let header = format!("{} {}", emoji::INFO, title);
     println!();
     println!("{}", style(draw_top_border(&header)).cyan());
@@ -278,6 +404,9 @@ pub fn panel_info(title: &str, message: &str) {
 
 /// Print a warning panel
 pub fn panel_warning(title: &str, message: &str) {
+    if is_json_output() {
+        return;
+    }
     let header = format!("{} {}", emoji::WARNING, title);
     println!();
     println!("{}", style(draw_top_border(&header)).yellow());
@@ -290,6 +419,9 @@ pub fn panel_warning(title: &str, message: &str) {
 
 /// Print a header panel (used for command headers)
 pub fn panel_header(emoji_icon: &str, title: &str, subtitle: Option<&str>) {
+    if is_json_output() {
+        return;
+    }
     let header = format!("{} {}", emoji_icon, title);
     println!();
     println!("{}", style(draw_top_border(&header)).cyan().bold());
@@ -337,8 +469,24 @@ pub fn add_kv_row(table: &mut Table, emoji_icon: &str, key: &str, value: &str) {
     ]);
 }
 
+/// Create a cell for a change in a cost metric, where lower is better:
+/// increases are red and decreases green
+pub fn delta_cell(text: &str, delta: f64) -> Cell {
+    let cell = Cell::new(text);
+    if delta > 0.0 {
+        cell.fg(Color::Red)
+    } else if delta < 0.0 {
+        cell.fg(Color::Green)
+    } else {
+        cell
+    }
+}
+
 /// Print a tree-style list
 pub fn print_tree(items: &[(&str, &str)]) {
+    if is_json_output() {
+        return;
+    }
     let len = items.len();
     for (i, (label, value)) in items.iter().enumerate() {
         let prefix = if i == len - 1 {
@@ -352,6 +500,9 @@ pub fn print_tree(items: &[(&str, &str)]) {
 
 /// Print a tree-style list with status indicators
 pub fn print_tree_with_status(items: &[(&str, &str, bool)]) {
+    if is_json_output() {
+        return;
+    }
     let len = items.len();
     for (i, (label, value, ok)) in items.iter().enumerate() {
         let prefix = if i == len - 1 {
@@ -406,6 +557,9 @@ impl ProgressStep {
 
 /// Print multi-step progress
 pub fn print_progress(steps: &[ProgressStep], current_message: Option<&str>) {
+    if is_json_output() {
+        return;
+    }
     for (i, step) in steps.iter().enumerate() {
         let step_num = i + 1;
         let total = steps.len();
@@ -456,17 +610,26 @@ pub fn print_progress(steps: &[ProgressStep], current_message: Option<&str>) {
 
 /// Print a section header with emoji
 pub fn section(emoji_icon: &str, title: &str) {
+    if is_json_output() {
+        return;
+    }
     println!();
     println!("  {} {}", emoji_icon, style(title).bold());
 }
 
 /// Print a divider line
 pub fn divider() {
+    if is_json_output() {
+        return;
+    }
     println!("{}", style("─".repeat(65)).dim());
 }
 
 /// Print a blank line
 pub fn blank() {
+    if is_json_output() {
+        return;
+    }
     println!();
 }
 
@@ -476,6 +639,9 @@ pub fn blank() {
 
 /// Print a labeled value
 pub fn print_value(label: &str, value: &str) {
+    if is_json_output() {
+        return;
+    }
     println!(
         "  {} {}",
         style(format!("{}:", label)).dim(),
@@ -485,6 +651,9 @@ pub fn print_value(label: &str, value: &str) {
 
 /// Print a labeled value with emoji
 pub fn print_value_with_emoji(emoji_icon: &str, label: &str, value: &str) {
+    if is_json_output() {
+        return;
+    }
     println!(
         "  {} {} {}",
         emoji_icon,
@@ -495,6 +664,9 @@ pub fn print_value_with_emoji(emoji_icon: &str, label: &str, value: &str) {
 
 /// Print a success message
 pub fn success(message: &str) {
+    if is_json_output() {
+        return;
+    }
     println!("{} {}", style(emoji::SUCCESS).green().bold(), message);
 }
 
@@ -509,6 +681,9 @@ pub fn error(message: &str) {
 
 /// Print a warning message
 pub fn warn(message: &str) {
+    if is_json_output() {
+        return;
+    }
     println!(
         "{} {}",
         style(emoji::WARNING).yellow().bold(),
@@ -518,5 +693,8 @@ pub fn warn(message: &str) {
 
 /// Print an info message
 pub fn info(message: &str) {
+    if is_json_output() {
+        return;
+    }
     println!("{} {}", style(emoji::INFO).cyan(), message);
 }